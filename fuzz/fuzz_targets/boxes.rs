@@ -0,0 +1,39 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![no_main]
+
+use jpegxl_rs::decoder_builder;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects whether boxes are decompressed and whether limits apply
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+    let mut builder = decoder_builder();
+    builder.decompress(flags & 1 != 0);
+    if flags & 2 != 0 {
+        builder.strict();
+    }
+    let Ok(decoder) = builder.build() else {
+        return;
+    };
+    let _ = decoder.exif(data);
+    #[cfg(not(feature = "libjxl_0_10"))]
+    let _ = decoder.gain_map(data);
+});
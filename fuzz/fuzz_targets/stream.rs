@@ -0,0 +1,53 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+#![no_main]
+
+use std::io::Read;
+
+use jpegxl_rs::decoder_builder;
+use libfuzzer_sys::fuzz_target;
+
+/// Reader handing out the input in chunks of a fixed size
+struct Chunked<'a> {
+    data: &'a [u8],
+    chunk: usize,
+}
+
+impl Read for Chunked<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.chunk.min(buf.len()).min(self.data.len());
+        let (head, tail) = self.data.split_at(len);
+        buf[..len].copy_from_slice(head);
+        self.data = tail;
+        Ok(len)
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the chunk size, so input boundaries land anywhere
+    let Some((&chunk, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(decoder) = decoder_builder().strict().build() else {
+        return;
+    };
+    let _ = decoder.decode_reader(Chunked {
+        data,
+        chunk: usize::from(chunk) + 1,
+    });
+});
@@ -0,0 +1,221 @@
+#![allow(missing_docs)]
+#![allow(clippy::missing_panics_doc)]
+
+//! Throughput across pixel types, thread counts, parallel runners and output buffer strategies
+
+use std::time::Duration;
+
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId,
+    Criterion, Throughput,
+};
+use half::f16;
+use jpegxl_rs::{
+    decode::JxlDecoder,
+    decoder_builder,
+    encode::{EncoderSpeed, JxlEncoder},
+    encoder_builder,
+    parallel::ParallelRunner,
+    PixelType, ResizableRunner, SequentialRunner, ThreadsRunner,
+};
+
+const SAMPLE_JXL: &[u8] = include_bytes!("../../samples/bench.jxl");
+const SAMPLE_PNG: &[u8] = include_bytes!("../../samples/bench.png");
+const SAMPLE_JPEG_JXL: &[u8] = include_bytes!("../../samples/sample_jpg.jxl");
+
+const THREAD_COUNTS: [usize; 4] = [1, 2, 4, 8];
+
+fn pixel_group<'c>(c: &'c mut Criterion, name: &str, pixels: u64) -> BenchmarkGroup<'c, WallTime> {
+    let mut group = c.benchmark_group(name);
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(20))
+        .throughput(Throughput::Elements(pixels));
+    group
+}
+
+fn decode<T: PixelType>(decoder: &JxlDecoder) -> Vec<T> {
+    decoder.decode_with::<T>(black_box(SAMPLE_JXL)).unwrap().1
+}
+
+fn decode_runner(group: &mut BenchmarkGroup<WallTime>, name: &str, runner: &dyn ParallelRunner) {
+    let decoder = decoder_builder().parallel_runner(runner).build().unwrap();
+    group.bench_function(name, |b| b.iter_with_large_drop(|| decode::<u8>(&decoder)));
+}
+
+fn encode<T: PixelType>(encoder: &mut JxlEncoder, data: &[T], width: u32, height: u32) -> Vec<u8> {
+    encoder
+        .encode::<T, T>(black_box(data), width, height)
+        .unwrap()
+        .data
+}
+
+fn encode_runner(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    runner: &dyn ParallelRunner,
+    sample: &image::RgbImage,
+) {
+    let mut encoder = fast_encoder(runner);
+    group.bench_function(name, |b| {
+        b.iter_with_large_drop(|| {
+            encode(
+                &mut encoder,
+                sample.as_raw(),
+                sample.width(),
+                sample.height(),
+            )
+        });
+    });
+}
+
+fn fast_encoder(runner: &dyn ParallelRunner) -> JxlEncoder<'_, 'static> {
+    encoder_builder()
+        .speed(EncoderSpeed::Falcon)
+        .parallel_runner(runner)
+        .build()
+        .unwrap()
+}
+
+pub fn decode_benchmark(c: &mut Criterion) {
+    let (metadata, _) = decoder_builder()
+        .build()
+        .unwrap()
+        .decode(SAMPLE_JXL)
+        .unwrap();
+    let pixels = u64::from(metadata.width) * u64::from(metadata.height);
+    let runner = ThreadsRunner::default();
+
+    let mut group = pixel_group(c, "Decoder pixel types", pixels);
+    let decoder = decoder_builder().parallel_runner(&runner).build().unwrap();
+    group.bench_function("u8", |b| {
+        b.iter_with_large_drop(|| decode::<u8>(&decoder));
+    });
+    group.bench_function("u16", |b| {
+        b.iter_with_large_drop(|| decode::<u16>(&decoder));
+    });
+    group.bench_function("f16", |b| {
+        b.iter_with_large_drop(|| decode::<f16>(&decoder));
+    });
+    group.bench_function("f32", |b| {
+        b.iter_with_large_drop(|| decode::<f32>(&decoder));
+    });
+    group.finish();
+
+    let mut group = pixel_group(c, "Decoder threads", pixels);
+    for threads in THREAD_COUNTS {
+        let runner = ThreadsRunner::with_threads(threads).unwrap();
+        let decoder = decoder_builder().parallel_runner(&runner).build().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &decoder, |b, d| {
+            b.iter_with_large_drop(|| decode::<u8>(d));
+        });
+    }
+    group.finish();
+
+    let mut group = pixel_group(c, "Decoder runners", pixels);
+    decode_runner(&mut group, "sequential", &SequentialRunner);
+    decode_runner(&mut group, "threads", &runner);
+    decode_runner(&mut group, "resizable", &ResizableRunner::default());
+    #[cfg(feature = "rayon")]
+    decode_runner(&mut group, "rayon", &jpegxl_rs::RayonRunner::default());
+    group.finish();
+
+    // Reconstructed JPEG buffers start at `init_jpeg_buffer` bytes and grow as needed
+    let mut group = c.benchmark_group("Decoder JPEG buffer");
+    group.throughput(Throughput::Bytes(SAMPLE_JPEG_JXL.len() as u64));
+    for size in [1024, 64 * 1024, 512 * 1024] {
+        let decoder = decoder_builder().init_jpeg_buffer(size).build().unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &decoder, |b, d| {
+            b.iter_with_large_drop(|| d.reconstruct(black_box(SAMPLE_JPEG_JXL)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+pub fn encode_benchmark(c: &mut Criterion) {
+    let sample = image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png).unwrap();
+    let (width, height) = (sample.width(), sample.height());
+    let pixels = u64::from(width) * u64::from(height);
+    let rgb8 = sample.to_rgb8();
+    let rgb16 = sample.to_rgb16();
+    let rgb32f = sample.to_rgb32f();
+    let runner = ThreadsRunner::default();
+
+    let mut group = pixel_group(c, "Encoder pixel types", pixels);
+    let mut encoder = fast_encoder(&runner);
+    group.bench_function("u8", |b| {
+        b.iter_with_large_drop(|| encode(&mut encoder, rgb8.as_raw(), width, height));
+    });
+    group.bench_function("u16", |b| {
+        b.iter_with_large_drop(|| encode(&mut encoder, rgb16.as_raw(), width, height));
+    });
+    group.bench_function("f32", |b| {
+        b.iter_with_large_drop(|| encode(&mut encoder, rgb32f.as_raw(), width, height));
+    });
+    group.finish();
+
+    let mut group = pixel_group(c, "Encoder threads", pixels);
+    for threads in THREAD_COUNTS {
+        let runner = ThreadsRunner::with_threads(threads).unwrap();
+        encode_runner(&mut group, &threads.to_string(), &runner, &rgb8);
+    }
+    group.finish();
+
+    let mut group = pixel_group(c, "Encoder runners", pixels);
+    encode_runner(&mut group, "sequential", &SequentialRunner, &rgb8);
+    encode_runner(&mut group, "threads", &runner, &rgb8);
+    encode_runner(&mut group, "resizable", &ResizableRunner::default(), &rgb8);
+    #[cfg(feature = "rayon")]
+    encode_runner(
+        &mut group,
+        "rayon",
+        &jpegxl_rs::RayonRunner::default(),
+        &rgb8,
+    );
+    group.finish();
+
+    // Output into a fresh `Vec` per image, a reused `Vec`, or straight into a writer
+    let mut group = pixel_group(c, "Encoder output buffer", pixels);
+    for size in [4 * 1024, 64 * 1024, 1024 * 1024] {
+        let mut encoder = encoder_builder()
+            .speed(EncoderSpeed::Falcon)
+            .parallel_runner(&runner)
+            .init_buffer_size(size)
+            .build()
+            .unwrap();
+        group.bench_function(BenchmarkId::new("new", size), |b| {
+            b.iter_with_large_drop(|| encode(&mut encoder, rgb8.as_raw(), width, height));
+        });
+        let mut buffer = vec![];
+        group.bench_function(BenchmarkId::new("reused", size), |b| {
+            b.iter(|| {
+                buffer.clear();
+                encoder
+                    .encode_to_writer::<u8, u8>(
+                        black_box(rgb8.as_raw()),
+                        width,
+                        height,
+                        &mut buffer,
+                    )
+                    .unwrap();
+            });
+        });
+        group.bench_function(BenchmarkId::new("sink", size), |b| {
+            b.iter(|| {
+                encoder
+                    .encode_to_writer::<u8, u8>(
+                        black_box(rgb8.as_raw()),
+                        width,
+                        height,
+                        &mut std::io::sink(),
+                    )
+                    .unwrap();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode_benchmark, encode_benchmark);
+
+criterion_main!(benches);
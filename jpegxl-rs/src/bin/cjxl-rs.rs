@@ -0,0 +1,167 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Encode PNG or JPEG images to JPEG XL, like `cjxl`
+
+use std::{error::Error, path::PathBuf, process::ExitCode};
+
+use jpegxl_rs::{
+    encode::{EncoderResult, EncoderSpeed, JxlEncoder},
+    encoder_builder, ThreadsRunner,
+};
+
+const USAGE: &str =
+    "Usage: cjxl-rs INPUT OUTPUT.jxl [-d DISTANCE] [-q QUALITY] [-e EFFORT] [--lossless_jpeg 0|1]
+               [--num_threads N]
+
+JPEG input is transcoded losslessly unless --lossless_jpeg 0, -d or -q is given. -d 0 or
+-q 100 encodes losslessly.";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    distance: Option<f32>,
+    quality: Option<f32>,
+    effort: EncoderSpeed,
+    lossless_jpeg: bool,
+    num_threads: Option<usize>,
+}
+
+fn effort(value: &str) -> Result<EncoderSpeed, String> {
+    Ok(match value {
+        "1" => EncoderSpeed::Lightning,
+        "2" => EncoderSpeed::Thunder,
+        "3" => EncoderSpeed::Falcon,
+        "4" => EncoderSpeed::Cheetah,
+        "5" => EncoderSpeed::Hare,
+        "6" => EncoderSpeed::Wombat,
+        "7" => EncoderSpeed::Squirrel,
+        "8" => EncoderSpeed::Kitten,
+        "9" => EncoderSpeed::Tortoise,
+        "10" => EncoderSpeed::Glacier,
+        _ => return Err(format!("Effort must be 1 to 10, got {value}")),
+    })
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut paths = vec![];
+    let mut parsed = Args {
+        input: PathBuf::new(),
+        output: PathBuf::new(),
+        distance: None,
+        quality: None,
+        effort: EncoderSpeed::default(),
+        lossless_jpeg: true,
+        num_threads: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value of {arg}"));
+        match arg.as_str() {
+            "-d" | "--distance" => {
+                parsed.distance = Some(value()?.parse().map_err(|e| format!("{arg}: {e}"))?);
+            }
+            "-q" | "--quality" => {
+                parsed.quality = Some(value()?.parse().map_err(|e| format!("{arg}: {e}"))?);
+            }
+            "-e" | "--effort" => parsed.effort = effort(&value()?)?,
+            "--lossless_jpeg" => parsed.lossless_jpeg = value()? != "0",
+            "--num_threads" => {
+                parsed.num_threads = Some(value()?.parse().map_err(|e| format!("{arg}: {e}"))?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    [parsed.input, parsed.output] = <[PathBuf; 2]>::try_from(paths)
+        .map_err(|_| "Expected an input and an output path".to_string())?;
+    Ok(parsed)
+}
+
+fn encode(args: &Args, encoder: &mut JxlEncoder, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let lossy = args.distance.is_some() || args.quality.is_some();
+    if data.starts_with(&[0xFF, 0xD8]) && args.lossless_jpeg && !lossy {
+        return Ok(encoder.encode_jpeg(data)?.data);
+    }
+
+    let image = image::load_from_memory(data)?;
+    if image.color().bytes_per_pixel() > image.color().channel_count() {
+        let result: EncoderResult<u16> = encoder.encode_image(&image)?;
+        Ok(result.data)
+    } else {
+        let result: EncoderResult<u8> = encoder.encode_image(&image)?;
+        Ok(result.data)
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let data = std::fs::read(&args.input)?;
+    let runner = match args.num_threads {
+        Some(n) => ThreadsRunner::with_threads(n).ok_or("Cannot create the thread pool")?,
+        None => ThreadsRunner::default(),
+    };
+
+    let mut builder = encoder_builder();
+    builder
+        .parallel_runner(&runner)
+        .speed(args.effort)
+        .use_container(true);
+    if let Some(quality) = args.quality {
+        builder.jpeg_quality(quality);
+    }
+    if let Some(distance) = args.distance {
+        builder.quality(distance);
+    }
+    let lossless = args.distance == Some(0.0) || args.quality.is_some_and(|q| q >= 100.0);
+    if lossless {
+        builder.lossless_original();
+    }
+    let mut encoder = builder.build()?;
+
+    let output = encode(args, &mut encoder, &data)?;
+    std::fs::write(&args.output, &output)?;
+    println!(
+        "Compressed {} to {} bytes",
+        args.input.display(),
+        output.len()
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{e}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to encode {}: {e}", args.input.display());
+            ExitCode::FAILURE
+        }
+    }
+}
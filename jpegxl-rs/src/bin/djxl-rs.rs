@@ -0,0 +1,130 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Decode a JPEG XL image to PNG, or reconstruct the original JPEG, like `djxl`
+
+use std::{error::Error, path::PathBuf, process::ExitCode};
+
+use jpegxl_rs::{
+    decode::{Data, JxlDecoder},
+    decoder_builder,
+    image::ToDynamic,
+    ThreadsRunner,
+};
+
+const USAGE: &str = "Usage: djxl-rs INPUT.jxl OUTPUT [--bits 8|16] [--num_threads N]
+
+OUTPUT is written as PNG, or as the original JPEG for a .jpg or .jpeg extension if the image
+was transcoded from JPEG losslessly";
+
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    bits: u32,
+    num_threads: Option<usize>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut paths = vec![];
+    let mut bits = 8;
+    let mut num_threads = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value of {arg}"));
+        match arg.as_str() {
+            "--bits" => bits = value()?.parse().map_err(|e| format!("--bits: {e}"))?,
+            "--num_threads" => {
+                num_threads = Some(
+                    value()?
+                        .parse()
+                        .map_err(|e| format!("--num_threads: {e}"))?,
+                );
+            }
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let [input, output] = <[PathBuf; 2]>::try_from(paths)
+        .map_err(|_| "Expected an input and an output path".to_string())?;
+    if !matches!(bits, 8 | 16) {
+        return Err(format!("Unsupported bit depth {bits}"));
+    }
+    Ok(Args {
+        input,
+        output,
+        bits,
+        num_threads,
+    })
+}
+
+fn is_jpeg(output: &std::path::Path) -> bool {
+    output
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"))
+}
+
+fn decode(args: &Args, decoder: &JxlDecoder, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    if is_jpeg(&args.output) {
+        return match decoder.reconstruct(data)? {
+            (_, Data::Jpeg(jpeg)) => Ok(std::fs::write(&args.output, jpeg)?),
+            (_, Data::Pixels(_)) => Err("The image has no JPEG reconstruction data".into()),
+        };
+    }
+
+    let image = if args.bits == 16 {
+        decoder.decode_to_image_with::<u16>(data)?
+    } else {
+        decoder.decode_to_image_with::<u8>(data)?
+    }
+    .ok_or("The image cannot be represented as PNG")?;
+    image.save(&args.output)?;
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let data = std::fs::read(&args.input)?;
+    let runner = match args.num_threads {
+        Some(n) => ThreadsRunner::with_threads(n).ok_or("Cannot create the thread pool")?,
+        None => ThreadsRunner::default(),
+    };
+    let decoder = decoder_builder().parallel_runner(&runner).build()?;
+    decode(args, &decoder, &data)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{e}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Failed to decode {}: {e}", args.input.display());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A token for cancelling an encode or decode from another thread
+///
+/// Clones share the same state, so keep a clone to call [`cancel`](Self::cancel) on
+/// and give the other to [`JxlEncoder::cancellation`](crate::encode::JxlEncoder::cancellation)
+/// or [`JxlDecoder::cancellation`](crate::decode::JxlDecoder::cancellation).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token which is not cancelled
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the encodes and decodes using this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Clear the cancellation, so the token can be used for the next encode
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+
+    /// Return whether [`cancel`](Self::cancel) was called
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Return a guard which cancels a clone of this token when dropped
+    #[cfg(feature = "tokio")]
+    pub(crate) fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+/// Cancel the token when an async call is dropped before completion
+#[cfg(feature = "tokio")]
+pub(crate) struct CancelOnDrop(CancellationToken);
+
+#[cfg(feature = "tokio")]
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! C-compatible API on top of the safe interface
+//!
+//! Build a shared library with `cargo rustc --crate-type cdylib --features capi`, and
+//! declare the functions as:
+//!
+//! ```c
+//! int jxl_rs_decode_to_rgba8(const uint8_t *data, size_t size, uint8_t **pixels,
+//!                            size_t *pixels_size, uint32_t *width, uint32_t *height);
+//! int jxl_rs_encode_rgba8(const uint8_t *pixels, uint32_t width, uint32_t height,
+//!                         uint8_t **data, size_t *data_size);
+//! void jxl_rs_free(uint8_t *buffer, size_t size);
+//! ```
+//!
+//! Buffers returned by the library must be released with `jxl_rs_free`.
+
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    slice,
+};
+
+use crate::{
+    decode::PixelFormat,
+    decoder_builder,
+    encode::{EncoderFrame, EncoderResult},
+    encoder_builder,
+};
+
+/// The call succeeded
+pub const JXL_RS_OK: i32 = 0;
+/// A pointer argument is null or the pixel buffer is too small
+pub const JXL_RS_INVALID_ARGUMENT: i32 = 1;
+/// Decoding failed
+pub const JXL_RS_DECODE_ERROR: i32 = 2;
+/// Encoding failed
+pub const JXL_RS_ENCODE_ERROR: i32 = 3;
+/// The library panicked
+pub const JXL_RS_PANIC: i32 = 4;
+
+/// Give the ownership of `buffer` to the caller
+fn into_raw(buffer: Vec<u8>, ptr: &mut *mut u8, size: &mut usize) {
+    let buffer = buffer.into_boxed_slice();
+    *size = buffer.len();
+    *ptr = Box::into_raw(buffer).cast();
+}
+
+/// Decode a JPEG XL image to 8-bit RGBA pixels
+///
+/// On success, `*pixels` points to `*pixels_size` bytes which must be released with
+/// [`jxl_rs_free`].
+///
+/// # Safety
+/// `data` must be valid for reads of `size` bytes, and the other pointers must be valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jxl_rs_decode_to_rgba8(
+    data: *const u8,
+    size: usize,
+    pixels: *mut *mut u8,
+    pixels_size: *mut usize,
+    width: *mut u32,
+    height: *mut u32,
+) -> i32 {
+    if data.is_null() || pixels.is_null() || pixels_size.is_null() {
+        return JXL_RS_INVALID_ARGUMENT;
+    }
+    if width.is_null() || height.is_null() {
+        return JXL_RS_INVALID_ARGUMENT;
+    }
+    let data = unsafe { slice::from_raw_parts(data, size) };
+
+    catch_unwind(AssertUnwindSafe(|| {
+        let Ok(decoder) = decoder_builder()
+            .pixel_format(PixelFormat {
+                num_channels: 4,
+                ..PixelFormat::default()
+            })
+            .build()
+        else {
+            return JXL_RS_DECODE_ERROR;
+        };
+        let Ok((metadata, buffer)) = decoder.decode_with::<u8>(data) else {
+            return JXL_RS_DECODE_ERROR;
+        };
+
+        unsafe {
+            *width = metadata.width;
+            *height = metadata.height;
+            into_raw(buffer, &mut *pixels, &mut *pixels_size);
+        }
+        JXL_RS_OK
+    }))
+    .unwrap_or(JXL_RS_PANIC)
+}
+
+/// Encode 8-bit RGBA pixels to a JPEG XL image with default settings
+///
+/// On success, `*data` points to `*data_size` bytes which must be released with
+/// [`jxl_rs_free`].
+///
+/// # Safety
+/// `pixels` must be valid for reads of `width * height * 4` bytes, and the other pointers
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn jxl_rs_encode_rgba8(
+    pixels: *const u8,
+    width: u32,
+    height: u32,
+    data: *mut *mut u8,
+    data_size: *mut usize,
+) -> i32 {
+    if pixels.is_null() || data.is_null() || data_size.is_null() {
+        return JXL_RS_INVALID_ARGUMENT;
+    }
+    let Some(len) = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4))
+    else {
+        return JXL_RS_INVALID_ARGUMENT;
+    };
+    let pixels = unsafe { slice::from_raw_parts(pixels, len) };
+
+    catch_unwind(AssertUnwindSafe(|| {
+        let Ok(mut encoder) = encoder_builder().has_alpha(true).build() else {
+            return JXL_RS_ENCODE_ERROR;
+        };
+        let frame = EncoderFrame::new(pixels).num_channels(4);
+        let Ok(result): Result<EncoderResult<u8>, _> = encoder.encode_frame(&frame, width, height)
+        else {
+            return JXL_RS_ENCODE_ERROR;
+        };
+
+        unsafe { into_raw(result.data, &mut *data, &mut *data_size) };
+        JXL_RS_OK
+    }))
+    .unwrap_or(JXL_RS_PANIC)
+}
+
+/// Release a buffer returned by this library. Null pointers are ignored.
+///
+/// # Safety
+/// `buffer` and `size` must come from the same successful call of this library, and the
+/// buffer must not be released twice.
+#[no_mangle]
+pub unsafe extern "C" fn jxl_rs_free(buffer: *mut u8, size: usize) {
+    if !buffer.is_null() {
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(buffer, size)) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::null_mut;
+
+    use super::*;
+    use crate::tests::SAMPLE_JXL;
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_capi() {
+        let (mut pixels, mut pixels_size, mut width, mut height) = (null_mut(), 0, 0, 0);
+        let ret = unsafe {
+            jxl_rs_decode_to_rgba8(
+                SAMPLE_JXL.as_ptr(),
+                SAMPLE_JXL.len(),
+                &mut pixels,
+                &mut pixels_size,
+                &mut width,
+                &mut height,
+            )
+        };
+        assert_eq!(ret, JXL_RS_OK);
+        assert_eq!(pixels_size, (width * height * 4) as usize);
+
+        let (mut data, mut data_size) = (null_mut(), 0);
+        let ret = unsafe { jxl_rs_encode_rgba8(pixels, width, height, &mut data, &mut data_size) };
+        assert_eq!(ret, JXL_RS_OK);
+        assert!(data_size > 0);
+
+        unsafe {
+            jxl_rs_free(pixels, pixels_size);
+            jxl_rs_free(data, data_size);
+            jxl_rs_free(null_mut(), 0);
+        }
+
+        let ret = unsafe {
+            jxl_rs_decode_to_rgba8(
+                [0; 16].as_ptr(),
+                16,
+                &mut pixels,
+                &mut pixels_size,
+                &mut width,
+                &mut height,
+            )
+        };
+        assert_eq!(ret, JXL_RS_DECODE_ERROR);
+        let ret = unsafe { jxl_rs_encode_rgba8(null_mut(), 1, 1, &mut data, &mut data_size) };
+        assert_eq!(ret, JXL_RS_INVALID_ARGUMENT);
+    }
+}
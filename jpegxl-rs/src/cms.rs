@@ -0,0 +1,292 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Conversion to 8-bit sRGB with `qcms`
+
+use std::mem::MaybeUninit;
+
+use half::f16;
+use jpegxl_sys::common::types::JxlDataType;
+use qcms::{DataType, Intent, Profile, Transform};
+
+use crate::{
+    decode::{JxlDecoder, Metadata, PixelFormat, Pixels},
+    DecodeError,
+};
+
+impl Pixels {
+    /// Convert the pixels to 8-bit sRGB using the ICC profile in `metadata`
+    ///
+    /// The pixels must not have padded rows. Grayscale images are expanded to RGB, so the
+    /// result has 3 channels, or 4 channels if there is an alpha channel. Float and 16-bit
+    /// pixels are only reduced to 8 bits after the color conversion.
+    ///
+    /// # Errors
+    /// Return [`DecodeError::ColorConversion`] if the ICC profile is missing or unusable, and
+    /// [`DecodeError::UnsupportedPixelFormat`] if the number of channels is not 1 to 4
+    pub fn to_srgb(&self, metadata: &Metadata) -> Result<Vec<u8>, DecodeError> {
+        let len = match self {
+            Self::Float(v) => v.len(),
+            Self::Uint8(v) => v.len(),
+            Self::Uint16(v) => v.len(),
+            Self::Float16(v) => v.len(),
+        };
+        let num_pixels = num_pixels(metadata)?;
+        let num_channels = if num_pixels == 0 || len % num_pixels != 0 {
+            0
+        } else {
+            len / num_pixels
+        };
+
+        let icc_profile = metadata.icc_profile.as_deref();
+        let data: Vec<_> = match self {
+            Self::Uint8(v) => return to_srgb(v.clone(), num_channels, icc_profile),
+            Self::Float(v) => v.clone(),
+            Self::Uint16(v) => v.iter().map(|&x| f32::from(x) / 65535.0).collect(),
+            Self::Float16(v) => v.iter().copied().map(f16::to_f32).collect(),
+        };
+        to_srgb_interpolated(&data, num_channels, icc_profile)
+    }
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Decode the JPEG XL image to 8-bit sRGB pixels
+    ///
+    /// The ICC profile is always requested for this call, and rows are never padded.
+    /// Grayscale images are expanded to RGB.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding or the color conversion fails
+    pub fn decode_to_srgb(&self, data: &[u8]) -> Result<(Metadata, Vec<u8>), DecodeError> {
+        let mut pixels = vec![];
+        let mut format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(JxlDataType::Uint8),
+            self.pixel_format.map(|f| PixelFormat { align: 0, ..f }),
+            true,
+            None,
+            None,
+            format.as_mut_ptr(),
+            &mut pixels,
+        )?;
+        let num_pixels = num_pixels(&metadata)?;
+        let num_channels = pixels.len() / num_pixels.max(1);
+        let pixels = to_srgb(pixels, num_channels, metadata.icc_profile.as_deref())?;
+        Ok((metadata, pixels))
+    }
+}
+
+/// Return `width * height`, or [`DecodeError::SizeOverflow`] if it overflows `usize`
+fn num_pixels(metadata: &Metadata) -> Result<usize, DecodeError> {
+    usize::try_from(u64::from(metadata.width) * u64::from(metadata.height))
+        .map_err(|_| DecodeError::SizeOverflow)
+}
+
+/// Map a normalized float to `u8`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn unorm(x: f32) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Number of pixels converted at once by [`to_srgb_interpolated`]
+const CHUNK_PIXELS: usize = 4096;
+
+/// Split a normalized float into its cell on the 8-bit grid and the position inside it
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn grid(x: f32) -> (u8, f32) {
+    let x = x.clamp(0.0, 1.0) * 255.0;
+    let cell = x.floor();
+    (cell as u8, x - cell)
+}
+
+fn to_srgb(
+    data: Vec<u8>,
+    num_channels: usize,
+    icc_profile: Option<&[u8]>,
+) -> Result<Vec<u8>, DecodeError> {
+    Ok(SrgbTransform::new(num_channels, icc_profile)?.run(data))
+}
+
+/// Convert normalized samples to 8-bit sRGB
+///
+/// `qcms` only transforms 8-bit data, so instead of quantizing the input first, each pixel
+/// is transformed at the corners of its cell on the 8-bit grid and interpolated between them.
+fn to_srgb_interpolated(
+    data: &[f32],
+    num_channels: usize,
+    icc_profile: Option<&[u8]>,
+) -> Result<Vec<u8>, DecodeError> {
+    let transform = SrgbTransform::new(num_channels, icc_profile)?;
+    // Alpha is passed through by `qcms`, so only color channels span the cell
+    let color_channels = if num_channels < 3 { 1 } else { 3 };
+    let corners = 1 << color_channels;
+    let dst_channels = transform.dst_ty.bytes_per_pixel();
+
+    let mut output = Vec::with_capacity(data.len() / num_channels * dst_channels);
+    for chunk in data.chunks(CHUNK_PIXELS * num_channels) {
+        let mut lattice = Vec::with_capacity(chunk.len() * corners);
+        for pixel in chunk.chunks_exact(num_channels) {
+            for corner in 0..corners {
+                lattice.extend(pixel.iter().enumerate().map(|(c, &x)| {
+                    if c < color_channels {
+                        grid(x).0.saturating_add(u8::from(corner >> c & 1 == 1))
+                    } else {
+                        unorm(x)
+                    }
+                }));
+            }
+        }
+        let lattice = transform.run(lattice);
+
+        for (pixel, cell) in chunk
+            .chunks_exact(num_channels)
+            .zip(lattice.chunks_exact(corners * dst_channels))
+        {
+            let mut fract = [0.0; 3];
+            for (f, &x) in fract.iter_mut().zip(&pixel[..color_channels]) {
+                *f = grid(x).1;
+            }
+            for c in 0..dst_channels {
+                if c >= 3 {
+                    output.push(cell[c]);
+                    continue;
+                }
+                let value: f32 = (0..corners)
+                    .map(|corner| {
+                        let weight: f32 = fract[..color_channels]
+                            .iter()
+                            .enumerate()
+                            .map(|(i, &f)| if corner >> i & 1 == 1 { f } else { 1.0 - f })
+                            .product();
+                        weight * f32::from(cell[corner * dst_channels + c])
+                    })
+                    .sum();
+                output.push(unorm(value / 255.0));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// An 8-bit `qcms` transform from an ICC profile to sRGB
+struct SrgbTransform {
+    transform: Transform,
+    src_ty: DataType,
+    dst_ty: DataType,
+}
+
+impl SrgbTransform {
+    fn new(num_channels: usize, icc_profile: Option<&[u8]>) -> Result<Self, DecodeError> {
+        let (src_ty, dst_ty) = match num_channels {
+            1 => (DataType::Gray8, DataType::RGB8),
+            2 => (DataType::GrayA8, DataType::RGBA8),
+            3 => (DataType::RGB8, DataType::RGB8),
+            4 => (DataType::RGBA8, DataType::RGBA8),
+            n => {
+                return Err(DecodeError::UnsupportedPixelFormat(
+                    u32::try_from(n).unwrap_or(u32::MAX),
+                ))
+            }
+        };
+
+        let input = icc_profile
+            .and_then(|icc| Profile::new_from_slice(icc, false))
+            .ok_or(DecodeError::ColorConversion)?;
+        let mut output = Profile::new_sRGB();
+        output.precache_output_transform();
+        let transform = Transform::new_to(&input, &output, src_ty, dst_ty, Intent::default())
+            .ok_or(DecodeError::ColorConversion)?;
+
+        Ok(Self {
+            transform,
+            src_ty,
+            dst_ty,
+        })
+    }
+
+    fn run(&self, mut data: Vec<u8>) -> Vec<u8> {
+        if self.src_ty == self.dst_ty {
+            self.transform.apply(&mut data);
+            data
+        } else {
+            let num_pixels = data.len() / self.src_ty.bytes_per_pixel();
+            let mut converted = vec![0; num_pixels * self.dst_ty.bytes_per_pixel()];
+            self.transform.convert(&data, &mut converted);
+            converted
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        decoder_builder,
+        tests::{SAMPLE_JXL, SAMPLE_JXL_GRAY},
+    };
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decode_to_srgb() -> TestResult {
+        let decoder = decoder_builder().build()?;
+
+        let (metadata, pixels) = decoder.decode_to_srgb(SAMPLE_JXL)?;
+        let num_pixels = (metadata.width * metadata.height) as usize;
+        assert_eq!(pixels.len(), num_pixels * 4);
+        assert!(metadata.icc_profile.is_some());
+        assert!(!decoder.icc_profile);
+
+        let (metadata, pixels) = decoder.decode_to_srgb(SAMPLE_JXL_GRAY)?;
+        assert_eq!(
+            pixels.len(),
+            (metadata.width * metadata.height) as usize * 3
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn pixels_to_srgb() -> TestResult {
+        let decoder = decoder_builder().icc_profile(true).build()?;
+        let (metadata, pixels) = decoder.decode(SAMPLE_JXL)?;
+        let srgb = pixels.to_srgb(&metadata)?;
+        assert_eq!(srgb.len(), (metadata.width * metadata.height) as usize * 4);
+
+        let (_, expected) = decoder_builder().build()?.decode_to_srgb(SAMPLE_JXL)?;
+        for pixels in [
+            Pixels::Float(decoder.decode_with::<f32>(SAMPLE_JXL)?.1),
+            Pixels::Uint16(decoder.decode_with::<u16>(SAMPLE_JXL)?.1),
+        ] {
+            let srgb = pixels.to_srgb(&metadata)?;
+            assert_eq!(srgb.len(), expected.len());
+            assert!(srgb.iter().zip(&expected).all(|(a, b)| a.abs_diff(*b) <= 2));
+        }
+
+        let decoder = decoder_builder().build()?;
+        let (metadata, pixels) = decoder.decode(SAMPLE_JXL)?;
+        assert!(matches!(
+            pixels.to_srgb(&metadata),
+            Err(DecodeError::ColorConversion)
+        ));
+
+        Ok(())
+    }
+}
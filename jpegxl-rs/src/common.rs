@@ -22,21 +22,24 @@ use half::f16;
 
 use jpegxl_sys::common::types::{JxlDataType, JxlPixelFormat};
 
+/// Basic information
+pub type BasicInfo = jpegxl_sys::metadata::codestream_header::JxlBasicInfo;
+/// Orientation
+pub type Orientation = jpegxl_sys::metadata::codestream_header::JxlOrientation;
+
 /// Endianness of the pixels
 pub type Endianness = jpegxl_sys::common::types::JxlEndianness;
 
-mod private {
-    pub trait Sealed {}
-
-    impl Sealed for u8 {}
-    impl Sealed for u16 {}
-    impl Sealed for half::f16 {}
-    impl Sealed for f32 {}
-}
-
 /// Pixel data type.
 /// `u8`, `u16`, `f16` and `f32` are supported.
-pub trait PixelType: private::Sealed + Sized {
+///
+/// Custom representations, e.g. a newtype over one of the above, can implement this trait
+/// by delegating to the sample type they wrap.
+///
+/// # Safety
+/// The type must have the size and memory layout of a native-endian sample of
+/// [`PixelType::pixel_type`], because encoder input is passed to `libjxl` as raw bytes.
+pub unsafe trait PixelType: Sized + Copy {
     /// Return the C const
     fn pixel_type() -> JxlDataType;
 
@@ -45,9 +48,46 @@ pub trait PixelType: private::Sealed + Sized {
 
     /// Convert the data to the pixel type
     fn convert(data: &[u8], pixel_format: &JxlPixelFormat) -> Vec<Self>;
+
+    /// Return the sample in the nominal range of 0.0 to 1.0
+    fn to_normalized(self) -> f32;
+
+    /// Create a sample from a value in the nominal range of 0.0 to 1.0,
+    /// clamping and rounding for integer types
+    fn from_normalized(value: f32) -> Self;
+}
+
+/// Convert samples between pixel types, scaling integers to the full range of the target type
+///
+/// Conversions to a type of higher precision are lossless. When reducing the bit depth to
+/// an integer type, `dither` adds noise of up to half a step before rounding, which avoids
+/// banding in smooth gradients.
+#[must_use]
+pub fn convert_pixels<T: PixelType, U: PixelType>(data: &[T], dither: bool) -> Vec<U> {
+    let (src_bits, src_exp) = T::bits_per_sample();
+    let (dst_bits, dst_exp) = U::bits_per_sample();
+    let reduces_depth = dst_exp == 0 && (src_exp != 0 || dst_bits < src_bits);
+
+    if !(dither && reduces_depth) {
+        return data
+            .iter()
+            .map(|&x| U::from_normalized(x.to_normalized()))
+            .collect();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let step = 1.0 / ((1u64 << dst_bits) - 1) as f32;
+    let mut noise = 0.5f32;
+    data.iter()
+        .map(|&x| {
+            // Additive recurrence with the golden ratio, which spreads evenly without patterns
+            noise = (noise + 0.618_034).fract();
+            U::from_normalized(x.to_normalized() + (noise - 0.5) * step)
+        })
+        .collect()
 }
 
-impl PixelType for u8 {
+unsafe impl PixelType for u8 {
     fn pixel_type() -> JxlDataType {
         JxlDataType::Uint8
     }
@@ -59,9 +99,18 @@ impl PixelType for u8 {
     fn convert(data: &[u8], _pixel_format: &JxlPixelFormat) -> Vec<Self> {
         data.to_vec()
     }
+
+    fn to_normalized(self) -> f32 {
+        f32::from(self) / 255.0
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_normalized(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
 }
 
-impl PixelType for u16 {
+unsafe impl PixelType for u16 {
     fn pixel_type() -> JxlDataType {
         JxlDataType::Uint16
     }
@@ -79,9 +128,18 @@ impl PixelType for u16 {
         }
         buf
     }
+
+    fn to_normalized(self) -> f32 {
+        f32::from(self) / 65535.0
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_normalized(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+    }
 }
 
-impl PixelType for f32 {
+unsafe impl PixelType for f32 {
     fn pixel_type() -> JxlDataType {
         JxlDataType::Float
     }
@@ -100,9 +158,17 @@ impl PixelType for f32 {
         }
         buf
     }
+
+    fn to_normalized(self) -> f32 {
+        self
+    }
+
+    fn from_normalized(value: f32) -> Self {
+        value
+    }
 }
 
-impl PixelType for f16 {
+unsafe impl PixelType for f16 {
     fn pixel_type() -> JxlDataType {
         JxlDataType::Float16
     }
@@ -122,4 +188,71 @@ impl PixelType for f16 {
             })
             .collect()
     }
+
+    fn to_normalized(self) -> f32 {
+        self.to_f32()
+    }
+
+    fn from_normalized(value: f32) -> Self {
+        f16::from_f32(value)
+    }
+}
+
+/// Animation timing of an image
+#[derive(Debug, Clone, Copy)]
+pub struct Animation {
+    /// Numerator of the ticks per second, the unit of frame durations
+    pub tps_numerator: u32,
+    /// Denominator of the ticks per second
+    pub tps_denominator: u32,
+    /// Number of times to play the animation, 0 to loop forever
+    pub num_loops: u32,
+}
+
+impl Default for Animation {
+    /// 100 ticks per second, looping forever
+    fn default() -> Self {
+        Self {
+            tps_numerator: 100,
+            tps_denominator: 1,
+            num_loops: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn lossless_conversion() {
+        let data: Vec<u8> = (0..=255).collect();
+        let wide: Vec<u16> = convert_pixels(&data, true);
+        assert!(wide
+            .iter()
+            .zip(&data)
+            .all(|(&w, &x)| w == u16::from(x) * 257));
+        assert_eq!(convert_pixels::<u16, u8>(&wide, false), data);
+
+        let float: Vec<f32> = convert_pixels(&wide, false);
+        assert_eq!(convert_pixels::<f32, u16>(&float, false), wide);
+        let half: Vec<f16> = convert_pixels(&data, false);
+        assert_eq!(convert_pixels::<f16, u8>(&half, false), data);
+    }
+
+    #[test]
+    fn dithering() {
+        // A value between two steps of `u8` is rounded to both with dithering
+        let data = vec![0.3f32 / 255.0 + 100.0 / 255.0; 1000];
+        assert!(convert_pixels::<f32, u8>(&data, false)
+            .iter()
+            .all(|&x| x == 100));
+
+        let dithered = convert_pixels::<f32, u8>(&data, true);
+        assert!(dithered.iter().all(|&x| x == 100 || x == 101));
+        let ups: usize = dithered.iter().map(|&x| usize::from(x - 100)).sum();
+        assert!((250..350).contains(&ups), "{ups}");
+    }
 }
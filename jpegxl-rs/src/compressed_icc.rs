@@ -0,0 +1,104 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Compressed ICC profiles, the representation JPEG XL uses to store ICC profiles in the
+//! codestream and in gain map bundles
+
+use std::ptr::null_mut;
+
+use jpegxl_sys::{
+    common::{memory_manager::JxlMemoryManager, types::JxlBool},
+    metadata::compressed_icc as api,
+};
+
+use crate::memory::{Global, GlobalAllocManager, MemoryManager};
+#[cfg(feature = "decoder")]
+use crate::DecodeError;
+#[cfg(feature = "encoder")]
+use crate::EncodeError;
+
+/// Compress an ICC profile into the JPEG XL representation
+///
+/// # Errors
+/// Return [`EncodeError::BadInput`] if the profile fails to be compressed
+#[cfg(feature = "encoder")]
+pub fn compress_icc(icc: &[u8]) -> Result<Vec<u8>, EncodeError> {
+    transform(|mm, output, size| unsafe {
+        api::JxlICCProfileEncode(mm, icc.as_ptr(), icc.len(), output, size)
+    })
+    .ok_or(EncodeError::BadInput)
+}
+
+/// Expand a compressed ICC profile, e.g. the `alt_icc` of a gain map bundle
+///
+/// # Errors
+/// Return [`DecodeError::InvalidInput`] if the data is not a valid compressed profile
+#[cfg(feature = "decoder")]
+pub fn decompress_icc(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    transform(|mm, output, size| unsafe {
+        api::JxlICCProfileDecode(mm, data.as_ptr(), data.len(), output, size)
+    })
+    .ok_or(DecodeError::InvalidInput)
+}
+
+/// Run a conversion which allocates its output with the memory manager, and copy the output
+/// into a `Vec` before freeing it
+fn transform(
+    f: impl FnOnce(*const JxlMemoryManager, *mut *mut u8, *mut usize) -> JxlBool,
+) -> Option<Vec<u8>> {
+    let allocator = GlobalAllocManager::<Global>::default();
+    let mm = allocator.manager();
+
+    let mut output = null_mut();
+    let mut size = 0;
+    let success = f(&mm, &mut output, &mut size) == JxlBool::True;
+    if output.is_null() {
+        return None;
+    }
+
+    // Safety: `output` is a buffer of `size` bytes allocated with `mm`
+    let data = unsafe { std::slice::from_raw_parts(output, size) }.to_vec();
+    if let Some(free) = mm.free {
+        unsafe { free(mm.opaque, output.cast()) };
+    }
+
+    success.then_some(data)
+}
+
+#[cfg(all(test, feature = "decoder", feature = "encoder"))]
+mod tests {
+    use super::*;
+    use crate::{decoder_builder, tests::SAMPLE_JXL};
+
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    #[test]
+    fn test_roundtrip() -> TestResult {
+        let decoder = decoder_builder().icc_profile(true).build()?;
+        let (metadata, _) = decoder.decode(SAMPLE_JXL)?;
+        let icc = metadata.icc_profile.expect("ICC profile not retrieved");
+
+        let compressed = compress_icc(&icc)?;
+        assert!(compressed.len() < icc.len());
+        assert_eq!(decompress_icc(&compressed)?, icc);
+
+        assert!(compress_icc(&[]).is_err());
+        assert!(decompress_icc(&[0xff; 4]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Test support for the `libjxl` [conformance suite](https://github.com/libjxl/conformance)
+//!
+//! Check out the suite and fetch its test data with `scripts/download_and_symlink.sh`, then
+//! load it with [`load`] or [`load_from_env`]. Each [`TestCase`] decodes `input.jxl` and
+//! compares every frame with `reference_image.npy` against the tolerances in `test.json`.
+//!
+//! Pixels are compared in the color space of the image without color management, so test
+//! cases whose reference is in another color space are expected to fail.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use byteorder::{ByteOrder, LE};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    decode::{JxlDecoder, PixelFormat},
+    decoder_builder,
+    errors::ConformanceError,
+};
+
+/// Environment variable with the path to the conformance suite, read by [`load_from_env`]
+pub const CONFORMANCE_DIR_ENV: &str = "JXL_CONFORMANCE_DIR";
+
+const INPUT: &str = "input.jxl";
+const REFERENCE: &str = "reference_image.npy";
+
+#[derive(Debug, Clone, Deserialize)]
+struct Descriptor {
+    frames: Vec<Tolerance>,
+    #[serde(default)]
+    sha256sums: BTreeMap<String, String>,
+}
+
+/// Maximum errors allowed for a frame, relative to the nominal range of the samples
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Tolerance {
+    /// Maximum root-mean-square error
+    pub rms_error: f64,
+    /// Maximum absolute error of a single sample
+    pub peak_error: f64,
+}
+
+/// Errors of a decoded frame compared with the reference
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameReport {
+    /// Root-mean-square error
+    pub rms_error: f64,
+    /// Maximum absolute error of a single sample
+    pub peak_error: f64,
+}
+
+impl FrameReport {
+    /// Peak signal-to-noise ratio in dB, infinite if the frame matches exactly
+    #[must_use]
+    pub fn psnr(&self) -> f64 {
+        -20.0 * self.rms_error.log10()
+    }
+}
+
+/// A test case of the conformance suite
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// Name of the test case, i.e. its directory name
+    pub name: String,
+    /// Directory of the test case
+    pub dir: PathBuf,
+    /// Tolerances of each frame
+    pub frames: Vec<Tolerance>,
+    sha256sums: BTreeMap<String, String>,
+}
+
+impl TestCase {
+    /// Load a test case from a directory with a `test.json`
+    ///
+    /// # Errors
+    /// Return a [`ConformanceError`] if `test.json` cannot be read or parsed
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, ConformanceError> {
+        let dir = dir.as_ref();
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let json = fs::read(dir.join("test.json"))?;
+        let descriptor: Descriptor = serde_json::from_slice(&json)
+            .map_err(|e| ConformanceError::InvalidTestCase(format!("{name}: {e}")))?;
+
+        Ok(Self {
+            name,
+            dir: dir.to_path_buf(),
+            frames: descriptor.frames,
+            sha256sums: descriptor.sha256sums,
+        })
+    }
+
+    /// Verify the files of the test case against the checksums in `test.json`, to catch
+    /// incomplete downloads
+    ///
+    /// # Errors
+    /// Return a [`ConformanceError`] if a file cannot be read or its checksum mismatches
+    pub fn verify_checksums(&self) -> Result<(), ConformanceError> {
+        for (file, expected) in &self.sha256sums {
+            let digest = Sha256::digest(fs::read(self.dir.join(file))?);
+            let actual = format!("{digest:x}");
+            if !actual.eq_ignore_ascii_case(expected) {
+                return Err(ConformanceError::ChecksumMismatch(file.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode the input and compare every frame with the reference
+    ///
+    /// # Errors
+    /// Return a [`ConformanceError`] if decoding fails, the reference is invalid or a frame
+    /// exceeds its tolerance
+    pub fn run(&self) -> Result<Vec<FrameReport>, ConformanceError> {
+        self.verify_checksums()?;
+        let reference = Npy::read(&fs::read(self.dir.join(REFERENCE))?).ok_or_else(|| {
+            ConformanceError::InvalidTestCase(format!("{}: {REFERENCE}", self.name))
+        })?;
+        let invalid_shape = || {
+            ConformanceError::InvalidTestCase(format!("{}: shape {:?}", self.name, reference.shape))
+        };
+        let &[num_frames, height, width, num_channels] = reference.shape.as_slice() else {
+            return Err(invalid_shape());
+        };
+        // `chunks_exact` can't split the reference into empty frames
+        let frame_len = height
+            .checked_mul(width)
+            .and_then(|len| len.checked_mul(num_channels))
+            .filter(|&len| len > 0)
+            .ok_or_else(invalid_shape)?;
+
+        let decoder = decoder_builder()
+            .pixel_format(PixelFormat {
+                num_channels: u32::try_from(num_channels).unwrap_or(u32::MAX),
+                ..PixelFormat::default()
+            })
+            .build()?;
+        let frames = decode(&decoder, &fs::read(self.dir.join(INPUT))?)?;
+        if frames.len() != num_frames || frames.len() != self.frames.len() {
+            return Err(ConformanceError::FrameCount {
+                expected: num_frames,
+                actual: frames.len(),
+            });
+        }
+
+        frames
+            .iter()
+            .zip(reference.data.chunks_exact(frame_len))
+            .zip(&self.frames)
+            .enumerate()
+            .map(|(index, ((frame, reference), tolerance))| {
+                if frame.len() != reference.len() {
+                    return Err(ConformanceError::DimensionMismatch(index));
+                }
+                let report = compare(frame, reference);
+                if report.rms_error > tolerance.rms_error
+                    || report.peak_error > tolerance.peak_error
+                {
+                    return Err(ConformanceError::Mismatch { index, report });
+                }
+                Ok(report)
+            })
+            .collect()
+    }
+}
+
+fn decode(decoder: &JxlDecoder, data: &[u8]) -> Result<Vec<Vec<f32>>, ConformanceError> {
+    let (_, frames) = decoder.decode_frames::<f32>(data)?;
+    Ok(frames.into_iter().map(|f| f.pixels).collect())
+}
+
+fn compare(frame: &[f32], reference: &[f32]) -> FrameReport {
+    let (sum, peak) = frame
+        .iter()
+        .zip(reference)
+        .map(|(&a, &b)| f64::from((a - b).abs()))
+        .fold((0.0, 0.0_f64), |(sum, peak), e| (sum + e * e, peak.max(e)));
+    #[allow(clippy::cast_precision_loss)]
+    let rms_error = (sum / frame.len().max(1) as f64).sqrt();
+    FrameReport {
+        rms_error,
+        peak_error: peak,
+    }
+}
+
+/// Load every test case under `testcases` of the conformance suite
+///
+/// # Errors
+/// Return a [`ConformanceError`] if the directory or a `test.json` cannot be read
+pub fn load(dir: impl AsRef<Path>) -> Result<Vec<TestCase>, ConformanceError> {
+    let mut cases = vec![];
+    for entry in fs::read_dir(dir.as_ref().join("testcases"))? {
+        let path = entry?.path();
+        if path.join("test.json").is_file() {
+            cases.push(TestCase::open(path)?);
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Load the conformance suite from the directory in [`CONFORMANCE_DIR_ENV`], `None` if it is
+/// not set
+///
+/// # Errors
+/// Return a [`ConformanceError`] if the suite cannot be loaded
+pub fn load_from_env() -> Option<Result<Vec<TestCase>, ConformanceError>> {
+    std::env::var_os(CONFORMANCE_DIR_ENV).map(load)
+}
+
+/// A little-endian `f32` array in `NumPy` format
+struct Npy {
+    shape: Vec<usize>,
+    data: Vec<f32>,
+}
+
+impl Npy {
+    fn read(bytes: &[u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(b"\x93NUMPY")?;
+        let (&major, rest) = rest.split_first()?;
+        let (header_len, rest) = match major {
+            1 => (usize::from(LE::read_u16(rest.get(1..3)?)), rest.get(3..)?),
+            2 | 3 => (
+                usize::try_from(LE::read_u32(rest.get(1..5)?)).ok()?,
+                rest.get(5..)?,
+            ),
+            _ => return None,
+        };
+        let header = std::str::from_utf8(rest.get(..header_len)?).ok()?;
+        let data = rest.get(header_len..)?;
+
+        if !header.contains("'descr': '<f4'") || !header.contains("'fortran_order': False") {
+            return None;
+        }
+        let shape = header.split_once("'shape': (")?.1.split_once(')')?.0;
+        let shape = shape
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().ok())
+            .collect::<Option<Vec<usize>>>()?;
+
+        let len = shape
+            .iter()
+            .try_fold(1_usize, |acc, &d| acc.checked_mul(d))?;
+        let data = data.get(..len.checked_mul(4)?)?;
+        let mut values = vec![0.0; len];
+        LE::read_f32_into(data, &mut values);
+        Some(Self {
+            shape,
+            data: values,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::tests::SAMPLE_JXL;
+
+    fn npy(shape: &[usize], data: &[f32]) -> Vec<u8> {
+        let shape = shape
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut header =
+            format!("{{'descr': '<f4', 'fortran_order': False, 'shape': ({shape}), }}");
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&u16::try_from(header.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for v in data {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn write_case(dir: &Path, reference: &[u8], checksum: &str) -> TestResult {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(INPUT), SAMPLE_JXL)?;
+        fs::write(dir.join(REFERENCE), reference)?;
+        fs::write(
+            dir.join("test.json"),
+            format!(
+                r#"{{"frames": [{{"rms_error": 0.001, "peak_error": 0.004}}],
+                    "sha256sums": {{"{INPUT}": "{checksum}"}}}}"#
+            ),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn run() -> TestResult {
+        let decoder = decoder_builder()
+            .pixel_format(PixelFormat {
+                num_channels: 4,
+                ..PixelFormat::default()
+            })
+            .build()?;
+        let (metadata, frames) = decoder.decode_frames::<f32>(SAMPLE_JXL)?;
+        let shape = [1, metadata.height as usize, metadata.width as usize, 4];
+        let pixels = &frames[0].pixels;
+        let checksum = format!("{:x}", Sha256::digest(SAMPLE_JXL));
+
+        let root =
+            std::env::temp_dir().join(format!("jpegxl-rs-conformance-{}", std::process::id()));
+        let dir = root.join("testcases").join("sample");
+        let result = (|| -> TestResult {
+            write_case(&dir, &npy(&shape, pixels), &checksum)?;
+            let cases = load(&root)?;
+            assert_eq!(cases.len(), 1);
+            assert_eq!(cases[0].name, "sample");
+            let reports = cases[0].run()?;
+            assert!(reports[0].psnr().is_infinite());
+
+            let shifted: Vec<_> = pixels.iter().map(|v| v + 0.1).collect();
+            write_case(&dir, &npy(&shape, &shifted), &checksum)?;
+            assert!(matches!(
+                TestCase::open(&dir)?.run(),
+                Err(ConformanceError::Mismatch { index: 0, .. })
+            ));
+
+            write_case(&dir, &npy(&[1, 0, shape[2], 4], &[]), &checksum)?;
+            assert!(matches!(
+                TestCase::open(&dir)?.run(),
+                Err(ConformanceError::InvalidTestCase(_))
+            ));
+
+            write_case(&dir, &npy(&shape, pixels), "00")?;
+            assert!(matches!(
+                TestCase::open(&dir)?.run(),
+                Err(ConformanceError::ChecksumMismatch(_))
+            ));
+            Ok(())
+        })();
+        fs::remove_dir_all(&root)?;
+        result
+    }
+
+    #[test]
+    fn invalid_npy() {
+        assert!(Npy::read(b"\x93NUMPY").is_none());
+        assert!(Npy::read(&npy(&[2, 2], &[0.0; 3])).is_none());
+        let array = Npy::read(&npy(&[1, 2], &[0.5, 1.0])).unwrap();
+        assert_eq!(array.shape, vec![1, 2]);
+        assert_eq!(array.data, vec![0.5, 1.0]);
+    }
+
+    /// Run the suite in [`CONFORMANCE_DIR_ENV`], if it is set
+    #[test]
+    fn suite() -> TestResult {
+        let Some(cases) = load_from_env() else {
+            return Ok(());
+        };
+        for case in cases? {
+            case.run().map_err(|e| format!("{}: {e}", case.name))?;
+        }
+        Ok(())
+    }
+}
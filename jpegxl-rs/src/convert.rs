@@ -0,0 +1,219 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Convert GIF and APNG animations to animated JPEG XL
+
+use std::io::Cursor;
+
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder},
+    error::{DecodingError, ImageFormatHint},
+    AnimationDecoder, Frame, ImageError, ImageFormat, ImageResult,
+};
+
+use crate::encode::{Animation, EncoderResult, JxlEncoder};
+
+/// Convert a GIF or APNG animation to an animated JPEG XL image, detected by the signature
+///
+/// See [`gif_to_jxl`] and [`apng_to_jxl`].
+///
+/// # Errors
+/// Return an [`ImageError`] if the input is neither GIF nor PNG, fails to be decoded, or the
+/// encoder fails
+pub fn animation_to_jxl(encoder: &mut JxlEncoder, data: &[u8]) -> ImageResult<EncoderResult<u8>> {
+    match image::guess_format(data)? {
+        ImageFormat::Gif => gif_to_jxl(encoder, data),
+        ImageFormat::Png => apng_to_jxl(encoder, data),
+        format => Err(ImageError::Decoding(DecodingError::new(
+            format.into(),
+            "Expected a GIF or APNG animation",
+        ))),
+    }
+}
+
+/// Convert a GIF animation to an animated JPEG XL image
+///
+/// Frame durations are kept in milliseconds, and the loop count is taken from the
+/// `NETSCAPE2.0` extension. Animations without the extension play once.
+///
+/// # Errors
+/// Return an [`ImageError`] if the GIF fails to be decoded or the encoder fails
+pub fn gif_to_jxl(encoder: &mut JxlEncoder, data: &[u8]) -> ImageResult<EncoderResult<u8>> {
+    let frames = GifDecoder::new(Cursor::new(data))?
+        .into_frames()
+        .collect_frames()?;
+    encode(encoder, &frames, gif_loop_count(data).unwrap_or(1))
+}
+
+/// Convert an APNG animation to an animated JPEG XL image
+///
+/// Frame durations are kept in milliseconds, and the loop count is taken from the `acTL`
+/// chunk. A still PNG becomes a single frame animation.
+///
+/// # Errors
+/// Return an [`ImageError`] if the PNG fails to be decoded or the encoder fails
+pub fn apng_to_jxl(encoder: &mut JxlEncoder, data: &[u8]) -> ImageResult<EncoderResult<u8>> {
+    let frames = PngDecoder::new(Cursor::new(data))?
+        .apng()?
+        .into_frames()
+        .collect_frames()?;
+    encode(encoder, &frames, apng_loop_count(data).unwrap_or(1))
+}
+
+/// Encode the frames with millisecond ticks and the loop count, restoring the animation
+/// settings of the encoder afterwards
+fn encode(
+    encoder: &mut JxlEncoder,
+    frames: &[Frame],
+    num_loops: u32,
+) -> ImageResult<EncoderResult<u8>> {
+    if frames.is_empty() {
+        return Err(ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Unknown,
+            "The animation has no frames",
+        )));
+    }
+
+    let animation = encoder.animation.replace(Animation {
+        tps_numerator: 1000,
+        tps_denominator: 1,
+        num_loops,
+    });
+    let result = encoder.encode_animation(frames);
+    encoder.animation = animation;
+    Ok(result?)
+}
+
+/// Read the loop count of the `NETSCAPE2.0` application extension, 0 for infinite
+fn gif_loop_count(data: &[u8]) -> Option<u32> {
+    const EXTENSION: &[u8] = b"\x21\xff\x0bNETSCAPE2.0\x03\x01";
+
+    let start = data.windows(EXTENSION.len()).position(|w| w == EXTENSION)? + EXTENSION.len();
+    let count = data.get(start..start + 2)?;
+    Some(u32::from(u16::from_le_bytes([count[0], count[1]])))
+}
+
+/// Read the number of plays of the `acTL` chunk, 0 for infinite
+fn apng_loop_count(data: &[u8]) -> Option<u32> {
+    // Skip the signature, then walk the chunks until the image data
+    let mut chunks = data.get(8..)?;
+    while chunks.len() >= 8 {
+        let len = u32::from_be_bytes(chunks[..4].try_into().ok()?) as usize;
+        let kind = &chunks[4..8];
+        // A length overflowing `usize` is a malformed chunk
+        let body = chunks.get(8..len.checked_add(8)?)?;
+        match kind {
+            b"acTL" => return Some(u32::from_be_bytes(body.get(4..8)?.try_into().ok()?)),
+            b"IDAT" => return None,
+            _ => chunks = chunks.get(len.checked_add(12)?..)?,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{
+        codecs::gif::{GifEncoder, Repeat},
+        Delay, RgbaImage,
+    };
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decode::Metadata, decoder_builder, encoder_builder};
+
+    fn frames() -> Vec<Frame> {
+        [(255, 100), (0, 250)]
+            .into_iter()
+            .map(|(red, ms)| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(8, 8, image::Rgba([red, 0, 0, 255])),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(ms, 1),
+                )
+            })
+            .collect()
+    }
+
+    fn check(result: &[u8], num_loops: u32) -> TestResult {
+        let decoder = decoder_builder().build()?;
+        let (Metadata { animation, .. }, frames) = decoder.decode_frames::<u8>(result)?;
+        let animation = animation.expect("Expected an animation");
+        assert_eq!(animation.num_loops, num_loops);
+        assert_eq!(
+            (animation.tps_numerator, animation.tps_denominator),
+            (1000, 1)
+        );
+        assert_eq!(
+            frames.iter().map(|f| f.duration).collect::<Vec<_>>(),
+            [100, 250]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_gif() -> TestResult {
+        let mut gif = vec![];
+        {
+            let mut gif_encoder = GifEncoder::new(&mut gif);
+            gif_encoder.set_repeat(Repeat::Finite(3))?;
+            gif_encoder.encode_frames(frames())?;
+        }
+        assert_eq!(gif_loop_count(&gif), Some(3));
+
+        let mut encoder = encoder_builder().build()?;
+        let result = animation_to_jxl(&mut encoder, &gif)?;
+        check(&result, 3)?;
+        assert!(encoder.animation.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_apng() -> TestResult {
+        let mut apng = vec![];
+        {
+            let mut png_encoder = png::Encoder::new(&mut apng, 8, 8);
+            png_encoder.set_color(png::ColorType::Rgba);
+            png_encoder.set_animated(2, 0)?;
+            let mut writer = png_encoder.write_header()?;
+            for frame in frames() {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                writer.set_frame_delay(u16::try_from(numer / denom)?, 1000)?;
+                writer.write_image_data(frame.buffer().as_raw())?;
+            }
+        }
+        assert_eq!(apng_loop_count(&apng), Some(0));
+
+        // A chunk claiming more data than there is
+        let mut truncated = apng[..8].to_vec();
+        truncated.extend_from_slice(&u32::MAX.to_be_bytes());
+        truncated.extend_from_slice(b"tEXt");
+        assert_eq!(apng_loop_count(&truncated), None);
+
+        let mut encoder = encoder_builder().build()?;
+        let result = animation_to_jxl(&mut encoder, &apng)?;
+        check(&result, 0)?;
+
+        assert!(animation_to_jxl(&mut encoder, crate::tests::SAMPLE_JXL).is_err());
+
+        Ok(())
+    }
+}
@@ -17,32 +17,58 @@
 
 //! Decoder of JPEG XL format
 
-use std::{mem::MaybeUninit, ptr::null};
+use std::{
+    fmt,
+    io::Read,
+    mem::MaybeUninit,
+    path::Path,
+    ptr::{self, null},
+};
 
 #[allow(clippy::wildcard_imports)]
 use jpegxl_sys::{
-    common::types::{JxlDataType, JxlPixelFormat},
+    color::color_encoding::JxlTransferFunction,
+    common::types::{JxlBool, JxlDataType, JxlPixelFormat},
     decode::*,
-    metadata::codestream_header::{JxlBasicInfo, JxlOrientation},
 };
 
 use crate::{
     common::{Endianness, PixelType},
     errors::{check_dec_status, DecodeError},
     memory::MemoryManager,
-    parallel::ParallelRunner,
-    utils::check_valid_signature,
+    parallel::{ModeRunner, ParallelMode, ParallelRunner},
+    trace::{event, span},
 };
+use jpegxl_sys::{common::types::JxlBoxType, metadata::codestream_header::JxlExtraChannelType};
+
+#[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+use crate::gain_map::{GainMapBundle, GAIN_MAP_BOX};
+#[cfg(feature = "boxes")]
+use crate::utils::check_valid_signature;
 
+mod input;
+mod limits;
+mod linear;
+mod playback;
+mod progressive;
 mod result;
+mod seek;
+mod warnings;
+use input::Input;
+pub use limits::*;
+use linear::Transfer;
+pub use playback::Playback;
+pub use progressive::*;
 pub use result::*;
+pub use warnings::DecodeWarning;
+
+pub use crate::{
+    cancel::CancellationToken,
+    common::{Animation, BasicInfo, Orientation},
+};
 
-/// Basic information
-pub type BasicInfo = JxlBasicInfo;
 /// Progressive decoding steps
 pub type ProgressiveDetail = JxlProgressiveDetail;
-/// Orientation
-pub type Orientation = JxlOrientation;
 
 /// Desired Pixel Format
 #[derive(Clone, Copy, Debug)]
@@ -80,6 +106,44 @@ impl Default for PixelFormat {
     }
 }
 
+/// Effective settings of a [`JxlDecoder`], with defaults resolved
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DecoderConfig {
+    /// Desired pixel format, `None` to follow the image
+    pub pixel_format: Option<PixelFormat>,
+    /// Whether the as-in-bitstream orientation is kept
+    pub skip_reorientation: bool,
+    /// Whether associated alpha is unpremultiplied
+    pub unpremul_alpha: bool,
+    /// Whether spot colors are rendered
+    pub render_spotcolors: bool,
+    /// Whether zero-duration frames are coalesced
+    pub coalescing: bool,
+    /// Peak display luminance to tone map to, `None` to keep the image's
+    pub desired_intensity_target: Option<f32>,
+    /// Whether the output is linear light
+    pub linear_output: bool,
+    /// Whether boxes are returned decompressed
+    pub decompress: bool,
+    /// Steps at which progressive previews are emitted
+    pub progressive_detail: JxlProgressiveDetail,
+    /// Whether the ICC profile is retrieved
+    pub icc_profile: bool,
+    /// Initial buffer size for JPEG reconstruction
+    pub init_jpeg_buffer: usize,
+    /// Whether a cancellation token is set
+    pub cancellable: bool,
+    /// Limits on the input, `None` without limits
+    pub limits: Option<DecodeLimits>,
+    /// Whether a custom parallel runner is set, which takes precedence over `parallel_mode`
+    pub custom_parallel_runner: bool,
+    /// Parallel mode used without a custom runner
+    pub parallel_mode: ParallelMode,
+    /// Whether a custom memory manager is set
+    pub custom_memory_manager: bool,
+}
+
 /// JPEG XL Decoder
 #[derive(Builder)]
 #[builder(build_fn(skip, error = "None"))]
@@ -125,6 +189,16 @@ pub struct JxlDecoder<'pr, 'mm> {
     /// is not meant to be considered authoritative in any way. It may change from version
     /// to version
     pub desired_intensity_target: Option<f32>,
+    /// Output linear light in the primaries of the image, e.g. for rendering and compositing
+    ///
+    /// libjxl converts XYB images (lossy, without `uses_original_profile`) itself, for other
+    /// images the transfer function of the file is undone on the output, which then has to be
+    /// `f32`. Images with only an ICC profile, or with the HLG transfer function, return
+    /// [`DecodeError::ColorConversion`] in that case.
+    ///
+    /// # Default
+    /// `false`, and the pixels keep the transfer function of the image
+    pub linear_output: Option<bool>,
     /// Configures whether to get boxes in raw mode or in decompressed mode.
     ///
     /// # Default
@@ -149,9 +223,33 @@ pub struct JxlDecoder<'pr, 'mm> {
     /// Default: 512 KiB
     pub init_jpeg_buffer: usize,
 
+    /// Set a token to cancel decoding from another thread
+    ///
+    /// It is checked between decoder events, e.g. after each frame, so a still image
+    /// may be decoded completely before the decoder stops.
+    ///
+    /// # Default
+    /// `None`
+    pub cancellation: Option<CancellationToken>,
+
+    /// Set limits to reject images that would need excessive resources, see also
+    /// [`JxlDecoderBuilder::strict`]
+    ///
+    /// # Default
+    /// `None`, without limits
+    pub limits: Option<DecodeLimits>,
+
     /// Set parallel runner
     pub parallel_runner: Option<&'pr dyn ParallelRunner>,
 
+    /// Set the parallel mode, used when no `parallel_runner` is set
+    ///
+    /// Default: [`ParallelMode::Single`]
+    parallel_mode: ParallelMode,
+    /// Runner created for `parallel_mode`
+    #[builder(setter(skip))]
+    threads_runner: Option<ModeRunner<'mm>>,
+
     /// Set memory manager
     pub memory_manager: Option<&'mm dyn MemoryManager>,
 }
@@ -160,9 +258,16 @@ impl<'pr, 'mm> JxlDecoderBuilder<'pr, 'mm> {
     /// Build a [`JxlDecoder`]
     ///
     /// # Errors
-    /// Return [`DecodeError::CannotCreateDecoder`] if it fails to create the decoder.
+    /// Return [`DecodeError::InvalidConfiguration`] if a setting is out of range, or
+    /// [`DecodeError::CannotCreateDecoder`] if it fails to create the decoder.
     pub fn build(&mut self) -> Result<JxlDecoder<'pr, 'mm>, DecodeError> {
+        self.validate()?;
+
         let mm = self.memory_manager.flatten();
+        // Create the runner first so a failure doesn't leak the libjxl handle
+        let parallel_mode = self.parallel_mode.unwrap_or_default();
+        let threads_runner = parallel_mode.runner(mm, DecodeError::CannotCreateDecoder)?;
+
         let dec = unsafe {
             mm.map_or_else(
                 || JxlDecoderCreate(null()),
@@ -182,70 +287,183 @@ impl<'pr, 'mm> JxlDecoderBuilder<'pr, 'mm> {
             render_spotcolors: self.render_spotcolors.flatten(),
             coalescing: self.coalescing.flatten(),
             desired_intensity_target: self.desired_intensity_target.flatten(),
+            linear_output: self.linear_output.flatten(),
             decompress: self.decompress.flatten(),
             progressive_detail: self.progressive_detail.flatten(),
             icc_profile: self.icc_profile.unwrap_or_default(),
             init_jpeg_buffer: self.init_jpeg_buffer.unwrap_or(512 * 1024),
+            cancellation: self.cancellation.clone().flatten(),
+            limits: self.limits.flatten(),
             parallel_runner: self.parallel_runner.flatten(),
+            parallel_mode,
+            threads_runner,
             memory_manager: mm,
         })
     }
 }
 
-impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
-    pub(crate) fn decode_internal(
+impl JxlDecoderBuilder<'_, '_> {
+    /// Harden the decoder for untrusted input with [`DecodeLimits::STRICT`]
+    ///
+    /// Images exceeding the limits, e.g. in dimensions or number of frames, and containers
+    /// with unknown or oversized boxes are rejected before their buffers are allocated.
+    pub fn strict(&mut self) -> &mut Self {
+        self.limits(DecodeLimits::STRICT)
+    }
+
+    fn validate(&self) -> Result<(), DecodeError> {
+        let invalid = |msg: String| Err(DecodeError::InvalidConfiguration(msg));
+
+        if let Some(format) = self.pixel_format.flatten() {
+            if format.num_channels > 4 {
+                return invalid(format!(
+                    "`num_channels` must be 1 to 4, or 0 for automatic, got {}",
+                    format.num_channels
+                ));
+            }
+            if format.align > 1 && !format.align.is_power_of_two() {
+                return invalid(format!(
+                    "`align` must be a power of two, got {}",
+                    format.align
+                ));
+            }
+        }
+        if self.init_jpeg_buffer == Some(0) {
+            return invalid("`init_jpeg_buffer` must not be zero".into());
+        }
+
+        Ok(())
+    }
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Return the parallel runner, or the one created for `parallel_mode`
+    fn runner(&self) -> Option<&dyn ParallelRunner> {
+        self.parallel_runner
+            .or_else(|| self.threads_runner.as_ref().map(|r| r as _))
+    }
+
+    /// Reset the decoder to reject the input with `error`
+    fn reset_with(&self, error: DecodeError) -> DecodeError {
+        unsafe { JxlDecoderReset(self.ptr) };
+        error
+    }
+
+    /// Process the input, returning [`DecodeError::UnknownStatus`] for a status unknown to
+    /// the bindings
+    fn process_input(&self) -> Result<JxlDecoderStatus, DecodeError> {
+        let status = unsafe { JxlDecoderProcessInput(self.ptr) };
+        JxlDecoderStatus::try_from(status).map_err(DecodeError::UnknownStatus)
+    }
+
+    /// Check the type and size of the current box against the limits
+    fn check_box(&self) -> Result<(), DecodeError> {
+        let Some(limits) = &self.limits else {
+            return Ok(());
+        };
+        let mut box_type = JxlBoxType([0; 4]);
+        check_dec_status(unsafe { JxlDecoderGetBoxType(self.ptr, &mut box_type, false.into()) })?;
+        let mut size = 0;
+        check_dec_status(unsafe { JxlDecoderGetBoxSizeRaw(self.ptr, &mut size) })?;
+        limits.check_box(box_type.0.map(|c| c.to_ne_bytes()[0]), size)
+    }
+
+    /// Reset the decoder and return [`DecodeError::Cancelled`] if the token is cancelled
+    fn check_cancelled(&self) -> Result<(), DecodeError> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            unsafe { JxlDecoderReset(self.ptr) };
+            return Err(DecodeError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Decode the input, resetting the decoder on every error so it can be reused
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn decode_internal<'d>(
         &self,
-        data: &[u8],
+        input: impl Into<Input<'d>>,
         data_type: Option<JxlDataType>,
+        pixel_format: Option<PixelFormat>,
         with_icc_profile: bool,
-        mut reconstruct_jpeg_buffer: Option<&mut Vec<u8>>,
+        reconstruct_jpeg_buffer: Option<&mut Vec<u8>>,
+        frames: Option<&mut Vec<Frame<u8>>>,
         format: *mut JxlPixelFormat,
         pixels: &mut Vec<u8>,
     ) -> Result<Metadata, DecodeError> {
-        let Some(sig) = check_valid_signature(data) else {
-            return Err(DecodeError::InvalidInput);
-        };
-        if !sig {
-            return Err(DecodeError::InvalidInput);
-        }
+        span!(
+            "jxl_decode",
+            reconstruct_jpeg = reconstruct_jpeg_buffer.is_some(),
+            frames = frames.is_some()
+        );
+
+        self.decode_events(
+            input.into(),
+            data_type,
+            pixel_format,
+            with_icc_profile,
+            reconstruct_jpeg_buffer,
+            frames,
+            format,
+            pixels,
+        )
+        .map_err(|e| self.reset_with(e))
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(not(feature = "jpeg"), allow(clippy::needless_pass_by_value))]
+    fn decode_events(
+        &self,
+        mut input: Input<'_>,
+        data_type: Option<JxlDataType>,
+        pixel_format: Option<PixelFormat>,
+        with_icc_profile: bool,
+        reconstruct_jpeg_buffer: Option<&mut Vec<u8>>,
+        mut frames: Option<&mut Vec<Frame<u8>>>,
+        format: *mut JxlPixelFormat,
+        pixels: &mut Vec<u8>,
+    ) -> Result<Metadata, DecodeError> {
         let mut basic_info = MaybeUninit::uninit();
         let mut icc = if with_icc_profile { Some(vec![]) } else { None };
+        let mut num_frames = 0;
+        let mut warnings = vec![];
+        let (mut transfer, mut width) = (None, 0);
 
-        self.setup_decoder(with_icc_profile, reconstruct_jpeg_buffer.is_some())?;
-
-        let next_in = data.as_ptr();
-        let avail_in = std::mem::size_of_val(data) as _;
+        self.setup_decoder(
+            with_icc_profile,
+            reconstruct_jpeg_buffer.is_some(),
+            frames.is_some(),
+        )?;
 
-        check_dec_status(unsafe { JxlDecoderSetInput(self.ptr, next_in, avail_in) })?;
-        unsafe { JxlDecoderCloseInput(self.ptr) };
+        input.start(self.ptr)?;
+        #[cfg(feature = "jpeg")]
+        let mut reconstruct_jpeg_buffer = reconstruct_jpeg_buffer;
 
         let mut status;
         loop {
             use JxlDecoderStatus as s;
 
-            status = unsafe { JxlDecoderProcessInput(self.ptr) };
+            self.check_cancelled()?;
+            status = self.process_input()?;
 
             match status {
-                s::NeedMoreInput | s::Error => return Err(DecodeError::GenericError),
+                s::NeedMoreInput => input.refill(self.ptr)?,
+                s::Error => return Err(DecodeError::GenericError),
 
                 // Get the basic info
-                s::BasicInfo => {
-                    check_dec_status(unsafe {
-                        JxlDecoderGetBasicInfo(self.ptr, basic_info.as_mut_ptr())
-                    })?;
-
-                    if let Some(pr) = self.parallel_runner {
-                        pr.callback_basic_info(unsafe { &*basic_info.as_ptr() });
-                    }
-                }
+                s::BasicInfo => self.get_basic_info(&mut basic_info, &mut warnings)?,
 
                 // Get color encoding
                 s::ColorEncoding => {
-                    self.get_icc_profile(unsafe { icc.as_mut().unwrap_unchecked() })?;
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    transfer = self.get_color_encoding(info, icc.as_mut())?;
                 }
 
                 // Get JPEG reconstruction buffer
+                #[cfg(feature = "jpeg")]
                 s::JPEGReconstruction => {
                     // Safety: JpegReconstruction is only called when reconstruct_jpeg_buffer
                     // is not None
@@ -257,76 +475,195 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
                 }
 
                 // JPEG buffer need more space
+                #[cfg(feature = "jpeg")]
                 s::JPEGNeedMoreOutput => {
                     // Safety: JpegNeedMoreOutput is only called when reconstruct_jpeg_buffer
                     // is not None
                     let buf = unsafe { reconstruct_jpeg_buffer.as_mut().unwrap_unchecked() };
                     let need_to_write = unsafe { JxlDecoderReleaseJPEGBuffer(self.ptr) };
 
-                    buf.resize(buf.len() + need_to_write, 0);
+                    let len = buf.len().checked_add(need_to_write);
+                    buf.resize(len.ok_or(DecodeError::SizeOverflow)?, 0);
                     check_dec_status(unsafe {
                         JxlDecoderSetJPEGBuffer(self.ptr, buf.as_mut_ptr(), buf.len())
                     })?;
                 }
 
+                // Start a new frame
+                s::Frame => {
+                    num_frames += 1;
+                    if let Some(limits) = self.limits {
+                        limits.check_frames(num_frames)?;
+                    }
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(self.get_frame()?);
+                    }
+                    event!(index = num_frames - 1, "frame");
+                }
+                s::Box => self.check_box()?,
+
                 // Get the output buffer
                 s::NeedImageOutBuffer => {
-                    self.output(unsafe { &*basic_info.as_ptr() }, data_type, format, pixels)?;
+                    let pixels = output_buffer(&mut frames, pixels);
+                    let info = unsafe { &*basic_info.as_ptr() };
+                    let float_only = transfer.is_some();
+                    width =
+                        self.output(info, data_type, pixel_format, float_only, format, pixels)?;
+                    warnings::check_output(info, unsafe { &*format }, &mut warnings);
+                    event!(bytes = pixels.len(), "frame output");
                 }
 
-                s::FullImage => continue,
+                s::FullImage => {
+                    if let Some(transfer) = transfer {
+                        let pixels = output_buffer(&mut frames, pixels);
+                        transfer.apply(unsafe { &*format }, width, pixels);
+                    }
+                }
                 s::Success => {
+                    #[cfg(feature = "jpeg")]
                     if let Some(buf) = reconstruct_jpeg_buffer.as_mut() {
                         let remaining = unsafe { JxlDecoderReleaseJPEGBuffer(self.ptr) };
 
                         buf.truncate(buf.len() - remaining);
                         buf.shrink_to_fit();
+                        if buf.is_empty() {
+                            warnings::warn(&mut warnings, DecodeWarning::NoJpegReconstruction);
+                        }
                     }
 
                     unsafe { JxlDecoderReset(self.ptr) };
 
-                    let info = unsafe { basic_info.assume_init() };
-                    return Ok(Metadata {
-                        width: info.xsize,
-                        height: info.ysize,
-                        intensity_target: info.intensity_target,
-                        min_nits: info.min_nits,
-                        orientation: info.orientation,
-                        num_color_channels: info.num_color_channels,
-                        has_alpha_channel: info.alpha_bits > 0,
-                        intrinsic_width: info.intrinsic_xsize,
-                        intrinsic_height: info.intrinsic_ysize,
-                        icc_profile: icc,
-                    });
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    return Ok(Metadata::new(info, icc, warnings));
                 }
+                // JPEG reconstruction is never subscribed without the `jpeg` feature
+                #[cfg(not(feature = "jpeg"))]
+                s::JPEGReconstruction | s::JPEGNeedMoreOutput => unreachable!(),
                 s::NeedPreviewOutBuffer => todo!(),
                 s::BoxNeedMoreOutput => todo!(),
                 s::PreviewImage => todo!(),
-                s::Frame => todo!(),
-                s::Box => todo!(),
                 s::BoxComplete => todo!(),
                 s::FrameProgression => todo!(),
+                _ => return Err(DecodeError::UnknownStatus(status as i32)),
             }
         }
     }
 
-    fn setup_decoder(&self, icc: bool, reconstruct_jpeg: bool) -> Result<(), DecodeError> {
-        if let Some(runner) = self.parallel_runner {
+    /// Read the decompressed content of the first box with the given type
+    #[cfg(feature = "boxes")]
+    pub(crate) fn read_box(
+        &self,
+        data: &[u8],
+        box_type: [u8; 4],
+    ) -> Result<Option<Vec<u8>>, DecodeError> {
+        if check_valid_signature(data) != Some(true) {
+            return Err(DecodeError::InvalidInput);
+        }
+
+        check_dec_status(unsafe {
+            JxlDecoderSubscribeEvents(self.ptr, JxlDecoderStatus::Box as i32)
+        })?;
+        check_dec_status(unsafe { JxlDecoderSetDecompressBoxes(self.ptr, true.into()) })?;
+        check_dec_status(unsafe { JxlDecoderSetInput(self.ptr, data.as_ptr(), data.len()) })?;
+        unsafe { JxlDecoderCloseInput(self.ptr) };
+
+        let mut buffer: Option<Vec<u8>> = None;
+        let result = loop {
+            use JxlDecoderStatus as s;
+
+            let status = match self.process_input() {
+                Ok(status) => status,
+                Err(e) => break Err(e),
+            };
+            match status {
+                // The box ends at the next box or at the end of the file, as `BoxComplete`
+                // is only available since libjxl 0.11
+                s::Box => {
+                    if let Some(buf) = buffer.as_mut() {
+                        self.release_box_buffer(buf);
+                        break Ok(buffer);
+                    }
+                    let mut t = JxlBoxType([0; 4]);
+                    check_dec_status(unsafe {
+                        JxlDecoderGetBoxType(self.ptr, &mut t, true.into())
+                    })?;
+                    if t.0.map(|c| c.to_ne_bytes()[0]) == box_type {
+                        let buf = buffer.insert(vec![0; 1024]);
+                        check_dec_status(unsafe {
+                            JxlDecoderSetBoxBuffer(self.ptr, buf.as_mut_ptr(), buf.len())
+                        })?;
+                    }
+                }
+                s::BoxNeedMoreOutput => {
+                    // Safety: a buffer is only set when the box type matches
+                    let buf = unsafe { buffer.as_mut().unwrap_unchecked() };
+                    let remaining = unsafe { JxlDecoderReleaseBoxBuffer(self.ptr) };
+                    let written = buf.len() - remaining;
+
+                    let len = buf.len().checked_mul(2).ok_or(DecodeError::SizeOverflow)?;
+                    if let Some(limits) = &self.limits {
+                        limits.check_box_size(len).map_err(|e| self.reset_with(e))?;
+                    }
+                    buf.resize(len, 0);
+                    check_dec_status(unsafe {
+                        JxlDecoderSetBoxBuffer(
+                            self.ptr,
+                            buf.as_mut_ptr().add(written),
+                            buf.len() - written,
+                        )
+                    })?;
+                }
+                s::Success => {
+                    if let Some(buf) = buffer.as_mut() {
+                        self.release_box_buffer(buf);
+                    }
+                    break Ok(buffer);
+                }
+                _ => break Err(DecodeError::GenericError),
+            }
+        };
+
+        unsafe { JxlDecoderReset(self.ptr) };
+        result
+    }
+
+    /// Release the box buffer and drop the unwritten tail
+    #[cfg(feature = "boxes")]
+    fn release_box_buffer(&self, buf: &mut Vec<u8>) {
+        let remaining = unsafe { JxlDecoderReleaseBoxBuffer(self.ptr) };
+        buf.truncate(buf.len() - remaining);
+    }
+
+    fn setup_decoder(
+        &self,
+        icc: bool,
+        reconstruct_jpeg: bool,
+        frames: bool,
+    ) -> Result<(), DecodeError> {
+        if let Some(runner) = self.runner() {
             check_dec_status(unsafe {
                 JxlDecoderSetParallelRunner(self.ptr, runner.runner(), runner.as_opaque_ptr())
             })?;
         }
 
         let events = {
-            use JxlDecoderStatus::{BasicInfo, ColorEncoding, FullImage, JPEGReconstruction};
+            use JxlDecoderStatus::{
+                BasicInfo, ColorEncoding, Frame, FullImage, JPEGReconstruction,
+            };
 
             let mut events = BasicInfo as i32 | FullImage as i32;
-            if icc {
+            if icc || self.linear_output == Some(true) {
                 events |= ColorEncoding as i32;
             }
             if reconstruct_jpeg {
                 events |= JPEGReconstruction as i32;
             }
+            if frames || self.limits.is_some() {
+                events |= Frame as i32;
+            }
+            if self.limits.is_some() {
+                events |= JxlDecoderStatus::Box as i32;
+            }
 
             events
         };
@@ -351,11 +688,130 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         Ok(())
     }
 
+    fn get_basic_info(
+        &self,
+        basic_info: &mut MaybeUninit<BasicInfo>,
+        warnings: &mut Vec<DecodeWarning>,
+    ) -> Result<(), DecodeError> {
+        check_dec_status(unsafe { JxlDecoderGetBasicInfo(self.ptr, basic_info.as_mut_ptr()) })?;
+
+        let info = unsafe { basic_info.assume_init_ref() };
+        event!(
+            width = info.xsize,
+            height = info.ysize,
+            bits_per_sample = info.bits_per_sample,
+            num_color_channels = info.num_color_channels,
+            alpha_bits = info.alpha_bits,
+            "basic info"
+        );
+        if let Some(limits) = &self.limits {
+            limits
+                .check_basic_info(info)
+                .map_err(|e| self.reset_with(e))?;
+        }
+        if let Some(pr) = self.runner() {
+            pr.callback_basic_info(info);
+        }
+
+        // The main alpha channel is part of the output, and spot colors when rendered
+        let mut main_alpha = info.alpha_bits > 0;
+        let mut ignored = 0;
+        for index in 0..info.num_extra_channels {
+            let mut ec = MaybeUninit::uninit();
+            check_dec_status(unsafe {
+                JxlDecoderGetExtraChannelInfo(self.ptr, index as usize, ec.as_mut_ptr())
+            })?;
+            // Read the raw type, a newer libjxl may report one unknown to the bindings
+            let ty = unsafe { ptr::addr_of!((*ec.as_ptr()).r#type).cast::<i32>().read() };
+            match JxlExtraChannelType::try_from(ty) {
+                Ok(JxlExtraChannelType::Alpha) if std::mem::take(&mut main_alpha) => {}
+                Ok(JxlExtraChannelType::SpotColor) if self.render_spotcolors.unwrap_or(true) => {}
+                _ => ignored += 1,
+            }
+        }
+        if ignored > 0 {
+            warnings::warn(warnings, DecodeWarning::ExtraChannelsIgnored(ignored));
+        }
+        Ok(())
+    }
+
+    /// Read the color encoding, returning the transfer function to undo on the output
+    fn get_color_encoding(
+        &self,
+        info: &BasicInfo,
+        icc: Option<&mut Vec<u8>>,
+    ) -> Result<Option<Transfer>, DecodeError> {
+        let transfer = if self.linear_output == Some(true) {
+            self.request_linear(info).map_err(|e| self.reset_with(e))?
+        } else {
+            None
+        };
+        if let Some(icc) = icc {
+            self.get_icc_profile(icc)?;
+            event!(icc_bytes = icc.len(), "color encoding");
+        }
+        Ok(transfer)
+    }
+
+    /// Request linear output, returning the transfer function to undo on the output if libjxl
+    /// does not convert the image itself
+    fn request_linear(&self, info: &BasicInfo) -> Result<Option<Transfer>, DecodeError> {
+        let mut encoding = MaybeUninit::uninit();
+        // Fails if the image only has an ICC profile
+        check_dec_status(unsafe {
+            JxlDecoderGetColorAsEncodedProfile(
+                self.ptr,
+                JxlColorProfileTarget::Data,
+                encoding.as_mut_ptr(),
+            )
+        })
+        .map_err(|_| DecodeError::ColorConversion)?;
+        // Read the raw fields, a newer libjxl may report values unknown to the bindings
+        let encoding = encoding.as_mut_ptr();
+        let transfer_function = unsafe {
+            ptr::addr_of!((*encoding).transfer_function)
+                .cast::<i32>()
+                .read()
+        };
+        let transfer_function = JxlTransferFunction::try_from(transfer_function)
+            .map_err(|_| DecodeError::ColorConversion)?;
+        if transfer_function == JxlTransferFunction::Linear {
+            return Ok(None);
+        }
+
+        // Without a CMS, libjxl only converts XYB images to another color encoding
+        if info.uses_original_profile == JxlBool::False {
+            unsafe {
+                ptr::addr_of_mut!((*encoding).transfer_function).write(JxlTransferFunction::Linear);
+            }
+            check_dec_status(unsafe { JxlDecoderSetPreferredColorProfile(self.ptr, encoding) })?;
+            return Ok(None);
+        }
+
+        let gamma = unsafe { ptr::addr_of!((*encoding).gamma).read() };
+        Transfer::new(transfer_function, gamma, info.intensity_target)
+            .map(Some)
+            .ok_or(DecodeError::ColorConversion)
+    }
+
+    /// Return the width in pixels of the current frame, available until its image is output
+    fn frame_width(&self) -> Result<usize, DecodeError> {
+        let mut header = MaybeUninit::uninit();
+        check_dec_status(unsafe { JxlDecoderGetFrameHeader(self.ptr, header.as_mut_ptr()) })?;
+        let xsize = unsafe { ptr::addr_of!((*header.as_ptr()).layer_info.xsize).read() };
+        usize::try_from(xsize).map_err(|_| DecodeError::SizeOverflow)
+    }
+
     fn get_icc_profile(&self, icc_profile: &mut Vec<u8>) -> Result<(), DecodeError> {
         let mut icc_size = 0;
         check_dec_status(unsafe {
             JxlDecoderGetICCProfileSize(self.ptr, JxlColorProfileTarget::Data, &mut icc_size)
         })?;
+        if let Some(limits) = &self.limits {
+            limits
+                .check_box_size(icc_size)
+                .map_err(|e| self.reset_with(e))?;
+        }
         icc_profile.resize(icc_size, 0);
 
         check_dec_status(unsafe {
@@ -370,15 +826,60 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         Ok(())
     }
 
+    fn get_frame(&self) -> Result<Frame<u8>, DecodeError> {
+        let mut header = MaybeUninit::uninit();
+        check_dec_status(unsafe { JxlDecoderGetFrameHeader(self.ptr, header.as_mut_ptr()) })?;
+        // Only read the fields needed, the blend mode may be unknown to the bindings
+        let header = header.as_ptr();
+        let (duration, name_length) = unsafe {
+            (
+                ptr::addr_of!((*header).duration).read(),
+                ptr::addr_of!((*header).name_length).read(),
+            )
+        };
+        let (width, height, x0, y0) = unsafe {
+            (
+                ptr::addr_of!((*header).layer_info.xsize).read(),
+                ptr::addr_of!((*header).layer_info.ysize).read(),
+                ptr::addr_of!((*header).layer_info.crop_x0).read(),
+                ptr::addr_of!((*header).layer_info.crop_y0).read(),
+            )
+        };
+
+        let name_len = usize::try_from(name_length)
+            .ok()
+            .and_then(|n| n.checked_add(1))
+            .ok_or(DecodeError::SizeOverflow)?;
+        let mut name = vec![0; name_len];
+        check_dec_status(unsafe {
+            JxlDecoderGetFrameName(self.ptr, name.as_mut_ptr().cast(), name.len())
+        })?;
+        name.pop();
+
+        Ok(Frame {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            duration,
+            width,
+            height,
+            x0,
+            y0,
+            pixels: Vec::new(),
+        })
+    }
+
+    /// Set the output buffer of the current frame, returning the width in pixels of its rows
     fn output(
         &self,
         info: &BasicInfo,
         data_type: Option<JxlDataType>,
+        pixel_format: Option<PixelFormat>,
+        float_only: bool,
         format: *mut JxlPixelFormat,
         pixels: &mut Vec<u8>,
-    ) -> Result<(), DecodeError> {
+    ) -> Result<usize, DecodeError> {
         let data_type = match data_type {
             Some(v) => v,
+            None if float_only => JxlDataType::Float,
             None => match (info.bits_per_sample, info.exponent_bits_per_sample) {
                 (x, 0) if x <= 8 => JxlDataType::Uint8,
                 (x, 0) if x <= 16 => JxlDataType::Uint16,
@@ -387,8 +888,12 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
                 (x, _) => return Err(DecodeError::UnsupportedBitWidth(x)),
             },
         };
+        // The transfer function can only be undone on `f32` output
+        if float_only && data_type != JxlDataType::Float {
+            return Err(self.reset_with(DecodeError::ColorConversion));
+        }
 
-        let f = self.pixel_format.unwrap_or_default();
+        let f = pixel_format.or(self.pixel_format).unwrap_or_default();
         let pixel_format = JxlPixelFormat {
             num_channels: if f.num_channels == 0 {
                 info.num_color_channels + u32::from(info.alpha_bits > 0)
@@ -411,7 +916,7 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         })?;
 
         unsafe { *format = pixel_format };
-        Ok(())
+        self.frame_width()
     }
 
     /// Decode a JPEG XL image
@@ -424,8 +929,33 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         let metadata = self.decode_internal(
             data,
             None,
+            None,
+            self.icc_profile,
+            None,
+            None,
+            pixel_format.as_mut_ptr(),
+            &mut buffer,
+        )?;
+        Ok((
+            metadata,
+            Pixels::new(buffer, unsafe { &pixel_format.assume_init() }),
+        ))
+    }
+
+    /// Decode a JPEG XL image from a reader, which is read in chunks as the decoder needs
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails or the reader fails
+    pub fn decode_reader(&self, mut reader: impl Read) -> Result<(Metadata, Pixels), DecodeError> {
+        let mut buffer = vec![];
+        let mut pixel_format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            Input::reader(&mut reader),
+            None,
+            None,
             self.icc_profile,
             None,
+            None,
             pixel_format.as_mut_ptr(),
             &mut buffer,
         )?;
@@ -435,6 +965,41 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         ))
     }
 
+    /// Read the gain map bundle from the `jhgm` box, if there is one
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails or the bundle is invalid
+    #[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+    pub fn gain_map(&self, data: &[u8]) -> Result<Option<GainMapBundle>, DecodeError> {
+        self.read_box(data, GAIN_MAP_BOX)?
+            .map(|b| GainMapBundle::from_bytes(&b))
+            .transpose()
+    }
+
+    /// Read the raw Exif (TIFF) data from the `Exif` box, if there is one
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails or the box is truncated
+    #[cfg(feature = "boxes")]
+    pub fn exif(&self, data: &[u8]) -> Result<Option<Vec<u8>>, DecodeError> {
+        self.read_box(data, *b"Exif")?
+            .map(|mut b| {
+                // Skip the 4-byte offset to the TIFF header
+                let offset = b
+                    .get(..4)
+                    .and_then(|o| {
+                        usize::try_from(u32::from_be_bytes([o[0], o[1], o[2], o[3]]))
+                            .ok()?
+                            .checked_add(4)
+                    })
+                    .filter(|&o| o <= b.len())
+                    .ok_or(DecodeError::InvalidInput)?;
+                b.drain(..offset);
+                Ok(b)
+            })
+            .transpose()
+    }
+
     /// Decode a JPEG XL image to a specific pixel type
     ///
     /// # Errors
@@ -442,14 +1007,26 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
     pub fn decode_with<T: PixelType>(
         &self,
         data: &[u8],
+    ) -> Result<(Metadata, Vec<T>), DecodeError> {
+        self.decode_with_format(data, None)
+    }
+
+    /// Decode a JPEG XL image to a specific pixel type, with `format` overriding
+    /// [`JxlDecoder::pixel_format`] for this call
+    pub(crate) fn decode_with_format<T: PixelType>(
+        &self,
+        data: &[u8],
+        format: Option<PixelFormat>,
     ) -> Result<(Metadata, Vec<T>), DecodeError> {
         let mut buffer = vec![];
         let mut pixel_format = MaybeUninit::uninit();
         let metadata = self.decode_internal(
             data,
             Some(T::pixel_type()),
+            format,
             self.icc_profile,
             None,
+            None,
             pixel_format.as_mut_ptr(),
             &mut buffer,
         )?;
@@ -464,6 +1041,101 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         Ok((metadata, buf))
     }
 
+    /// Decode a JPEG XL image to a [`DecoderResult`], which gives access to rows and pixels
+    ///
+    /// Rows are never padded, regardless of [`PixelFormat::align`].
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails, or
+    /// [`DecodeError::SizeOverflow`] if the number of pixels overflows `usize`
+    pub fn decode_to_result<T: PixelType>(
+        &self,
+        data: &[u8],
+    ) -> Result<DecoderResult<T>, DecodeError> {
+        let format = self.pixel_format.map(|f| PixelFormat { align: 0, ..f });
+        let (metadata, data) = self.decode_with_format::<T>(data, format)?;
+        let num_pixels = (metadata.width as usize)
+            .checked_mul(metadata.height as usize)
+            .ok_or(DecodeError::SizeOverflow)?;
+        Ok(DecoderResult {
+            num_channels: data.len() / num_pixels.max(1),
+            metadata,
+            data,
+        })
+    }
+
+    /// Decode a JPEG XL image to big-endian 16-bit samples, the byte layout of 16-bit PNG
+    ///
+    /// The channels are interleaved as set in [`PixelFormat::num_channels`], and rows are
+    /// never padded, so the bytes can be passed to a PNG encoder without swapping.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails
+    pub fn decode_png16(&self, data: &[u8]) -> Result<(Metadata, Vec<u8>), DecodeError> {
+        let pixel_format = PixelFormat {
+            endianness: Endianness::Big,
+            align: 0,
+            ..self.pixel_format.unwrap_or_default()
+        };
+
+        let mut buffer = vec![];
+        let mut format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(JxlDataType::Uint16),
+            Some(pixel_format),
+            self.icc_profile,
+            None,
+            None,
+            format.as_mut_ptr(),
+            &mut buffer,
+        )?;
+
+        Ok((metadata, buffer))
+    }
+
+    /// Decode every frame of a JPEG XL image to a specific pixel type
+    ///
+    /// Zero-duration frames are merged unless [`JxlDecoder::coalescing`] is disabled, so
+    /// disable it to get the layers of a still image.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails
+    pub fn decode_frames<T: PixelType>(
+        &self,
+        data: &[u8],
+    ) -> Result<(Metadata, Vec<Frame<T>>), DecodeError> {
+        let mut buffer = vec![];
+        let mut frames = vec![];
+        let mut pixel_format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(T::pixel_type()),
+            None,
+            self.icc_profile,
+            None,
+            Some(&mut frames),
+            pixel_format.as_mut_ptr(),
+            &mut buffer,
+        )?;
+
+        // Safety: every frame is decoded with the pixel type `T`
+        let frames = frames
+            .into_iter()
+            .map(|f| Frame {
+                pixels: unsafe { T::convert(&f.pixels, pixel_format.assume_init_ref()) },
+                name: f.name,
+                duration: f.duration,
+                width: f.width,
+                height: f.height,
+                x0: f.x0,
+                y0: f.y0,
+            })
+            .collect();
+
+        Ok((metadata, frames))
+    }
+
     /// Reconstruct JPEG data. Fallback to pixels if JPEG reconstruction fails
     ///
     /// # Note
@@ -471,6 +1143,7 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
     ///
     /// # Errors
     /// Return a [`DecodeError`] when internal decoder fails
+    #[cfg(feature = "jpeg")]
     pub fn reconstruct(&self, data: &[u8]) -> Result<(Metadata, Data), DecodeError> {
         let mut buffer = vec![];
         let mut pixel_format = MaybeUninit::uninit();
@@ -478,8 +1151,10 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
         let metadata = self.decode_internal(
             data,
             None,
+            None,
             self.icc_profile,
             Some(&mut jpeg_buf),
+            None,
             pixel_format.as_mut_ptr(),
             &mut buffer,
         )?;
@@ -495,7 +1170,51 @@ impl<'pr, 'mm> JxlDecoder<'pr, 'mm> {
     }
 }
 
-impl<'prl, 'mm> Drop for JxlDecoder<'prl, 'mm> {
+impl JxlDecoder<'_, '_> {
+    /// Return the effective settings of the decoder
+    #[must_use]
+    pub fn config(&self) -> DecoderConfig {
+        DecoderConfig {
+            pixel_format: self.pixel_format,
+            skip_reorientation: self.skip_reorientation.unwrap_or(false),
+            unpremul_alpha: self.unpremul_alpha.unwrap_or(false),
+            render_spotcolors: self.render_spotcolors.unwrap_or(true),
+            coalescing: self.coalescing.unwrap_or(true),
+            desired_intensity_target: self.desired_intensity_target,
+            linear_output: self.linear_output.unwrap_or(false),
+            decompress: self.decompress.unwrap_or(false),
+            progressive_detail: self.progressive_detail.unwrap_or(JxlProgressiveDetail::DC),
+            icc_profile: self.icc_profile,
+            init_jpeg_buffer: self.init_jpeg_buffer,
+            cancellable: self.cancellation.is_some(),
+            limits: self.limits,
+            custom_parallel_runner: self.parallel_runner.is_some(),
+            parallel_mode: self.parallel_mode,
+            custom_memory_manager: self.memory_manager.is_some(),
+        }
+    }
+}
+
+impl fmt::Debug for JxlDecoder<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JxlDecoder")
+            .field("config", &self.config())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Return the buffer of the current frame, or of the image
+fn output_buffer<'a>(
+    frames: &'a mut Option<&mut Vec<Frame<u8>>>,
+    pixels: &'a mut Vec<u8>,
+) -> &'a mut Vec<u8> {
+    match frames.as_mut().and_then(|f| f.last_mut()) {
+        Some(frame) => &mut frame.pixels,
+        None => pixels,
+    }
+}
+
+impl Drop for JxlDecoder<'_, '_> {
     fn drop(&mut self) {
         unsafe { JxlDecoderDestroy(self.ptr) };
     }
@@ -507,6 +1226,71 @@ pub fn decoder_builder<'prl, 'mm>() -> JxlDecoderBuilder<'prl, 'mm> {
     JxlDecoderBuilder::default()
 }
 
+/// Decode a JPEG XL image from a reader with default settings, e.g. from
+/// [`stdin`](std::io::stdin)
+///
+/// See [`JxlDecoder::decode_reader`].
+///
+/// # Errors
+/// Return a [`DecodeError`] when internal decoder fails or the reader fails
+pub fn decode_reader(reader: impl Read) -> Result<(Metadata, Pixels), DecodeError> {
+    decoder_builder().build()?.decode_reader(reader)
+}
+
+/// Decode a JPEG XL image with default settings, running on [`ParallelMode::Auto`]
+///
+/// The number of channels is determined by the image. Use a [`JxlDecoder`] for other
+/// settings.
+///
+/// # Errors
+/// Return a [`DecodeError`] when internal decoder fails
+pub fn decode_bytes(data: &[u8]) -> Result<(Metadata, Pixels), DecodeError> {
+    decoder_builder()
+        .parallel_mode(ParallelMode::Auto)
+        .build()?
+        .decode(data)
+}
+
+/// Read and decode a JPEG XL file, see [`decode_bytes`]
+///
+/// # Errors
+/// Return a [`DecodeError`] when the file fails to be read or internal decoder fails
+pub fn decode_file(path: impl AsRef<Path>) -> Result<(Metadata, Pixels), DecodeError> {
+    decode_bytes(&std::fs::read(path)?)
+}
+
+/// Decode a JPEG XL image on the blocking pool of the current [`tokio`] runtime
+///
+/// `configure` sets up the decoder on the blocking thread. Dropping the future cancels the
+/// decoding, see [`JxlDecoder::cancellation`].
+///
+/// # Errors
+/// Return a [`DecodeError`] when internal decoder fails, or [`DecodeError::Cancelled`] if the
+/// runtime is shutting down
+///
+/// # Panics
+/// Panic if not called within a runtime, or if `configure` panics
+#[cfg(feature = "tokio")]
+pub async fn decode_async<F>(data: Vec<u8>, configure: F) -> Result<(Metadata, Pixels), DecodeError>
+where
+    F: FnOnce(&mut JxlDecoderBuilder<'_, '_>) + Send + 'static,
+{
+    let token = CancellationToken::new();
+    let _guard = token.cancel_on_drop();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut builder = decoder_builder();
+        configure(&mut builder);
+        builder.cancellation(token).build()?.decode(&data)
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(DecodeError::Cancelled),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,129 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::io::{ErrorKind, Read};
+
+use jpegxl_sys::decode::{
+    JxlDecoder, JxlDecoderCloseInput, JxlDecoderReleaseInput, JxlDecoderSetInput,
+};
+
+use crate::{errors::check_dec_status, trace::event, utils::check_valid_signature, DecodeError};
+
+/// Size of each chunk read from a reader
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Compressed data given to the decoder
+pub(crate) enum Input<'a> {
+    /// The whole input in memory
+    Slice(&'a [u8]),
+    /// Input read in chunks when the decoder needs more
+    Reader {
+        reader: &'a mut dyn Read,
+        buffer: Vec<u8>,
+        eof: bool,
+    },
+}
+
+impl<'a> From<&'a [u8]> for Input<'a> {
+    fn from(data: &'a [u8]) -> Self {
+        Self::Slice(data)
+    }
+}
+
+impl<'a> Input<'a> {
+    pub(crate) fn reader(reader: &'a mut dyn Read) -> Self {
+        Self::Reader {
+            reader,
+            buffer: vec![],
+            eof: false,
+        }
+    }
+
+    /// Check the signature and set the first input
+    pub(crate) fn start(&mut self, dec: *mut JxlDecoder) -> Result<(), DecodeError> {
+        match self {
+            Self::Slice(data) => {
+                if check_valid_signature(data) != Some(true) {
+                    return Err(DecodeError::InvalidInput);
+                }
+                set_input(dec, data, true)?;
+            }
+            Self::Reader {
+                reader,
+                buffer,
+                eof,
+            } => {
+                buffer.clear();
+                *eof = false;
+                let sig = loop {
+                    match check_valid_signature(buffer) {
+                        None if !*eof => *eof = read_chunk(reader, buffer)?,
+                        sig => break sig,
+                    }
+                };
+                if sig != Some(true) {
+                    return Err(DecodeError::InvalidInput);
+                }
+                set_input(dec, buffer, *eof)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Give more input to the decoder after [`JxlDecoderStatus::NeedMoreInput`]
+    ///
+    /// [`JxlDecoderStatus::NeedMoreInput`]: jpegxl_sys::decode::JxlDecoderStatus::NeedMoreInput
+    pub(crate) fn refill(&mut self, dec: *mut JxlDecoder) -> Result<(), DecodeError> {
+        match self {
+            // The input is truncated
+            Self::Slice(_) | Self::Reader { eof: true, .. } => Err(DecodeError::GenericError),
+            Self::Reader {
+                reader,
+                buffer,
+                eof,
+            } => {
+                let remaining = unsafe { JxlDecoderReleaseInput(dec) };
+                buffer.drain(..buffer.len() - remaining);
+                *eof = read_chunk(reader, buffer)?;
+                set_input(dec, buffer, *eof)
+            }
+        }
+    }
+}
+
+/// Append up to [`CHUNK_SIZE`] bytes to `buffer`, and return whether the reader is exhausted
+fn read_chunk(reader: &mut dyn Read, buffer: &mut Vec<u8>) -> Result<bool, DecodeError> {
+    let start = buffer.len();
+    buffer.resize(start + CHUNK_SIZE, 0);
+    let read = loop {
+        match reader.read(&mut buffer[start..]) {
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            result => break result,
+        }
+    };
+    buffer.truncate(start + *read.as_ref().unwrap_or(&0));
+    Ok(read? == 0)
+}
+
+fn set_input(dec: *mut JxlDecoder, buffer: &[u8], eof: bool) -> Result<(), DecodeError> {
+    event!(bytes = buffer.len(), eof, "input set");
+    check_dec_status(unsafe { JxlDecoderSetInput(dec, buffer.as_ptr(), buffer.len()) })?;
+    if eof {
+        unsafe { JxlDecoderCloseInput(dec) };
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Limits for decoding untrusted input
+
+use crate::{common::BasicInfo, errors::DecodeError};
+
+/// Box types defined by the JPEG XL container format and the metadata it carries
+const KNOWN_BOXES: [&[u8; 4]; 12] = [
+    b"JXL ", b"ftyp", b"jxll", b"jxli", b"jxlc", b"jxlp", b"jbrd", b"Exif", b"xml ", b"jumb",
+    b"brob", b"jhgm",
+];
+
+/// Limits enforced by [`JxlDecoder`](super::JxlDecoder) to reject images that would need
+/// excessive resources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum width or height in pixels
+    pub max_dimension: u32,
+    /// Maximum number of pixels of the image
+    pub max_pixels: u64,
+    /// Maximum number of frames
+    pub max_frames: usize,
+    /// Maximum size in bytes of a metadata box or the ICC profile
+    pub max_box_size: u64,
+    /// Whether to reject boxes not defined by the container format
+    pub reject_unknown_boxes: bool,
+}
+
+impl DecodeLimits {
+    /// Conservative limits for untrusted input: 16384 pixels per side, 64 megapixels,
+    /// 1000 frames, 16 MiB per box and no unknown boxes
+    pub const STRICT: Self = Self {
+        max_dimension: 16384,
+        max_pixels: 64 * 1024 * 1024,
+        max_frames: 1000,
+        max_box_size: 16 * 1024 * 1024,
+        reject_unknown_boxes: true,
+    };
+
+    pub(crate) fn check_basic_info(&self, info: &BasicInfo) -> Result<(), DecodeError> {
+        if info.xsize.max(info.ysize) > self.max_dimension {
+            return Err(DecodeError::LimitExceeded("image dimensions"));
+        }
+        if u64::from(info.xsize) * u64::from(info.ysize) > self.max_pixels {
+            return Err(DecodeError::LimitExceeded("number of pixels"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_frames(&self, count: usize) -> Result<(), DecodeError> {
+        if count > self.max_frames {
+            return Err(DecodeError::LimitExceeded("number of frames"));
+        }
+        Ok(())
+    }
+
+    /// Check a box with its raw size, where 0 means it extends to the end of the file
+    pub(crate) fn check_box(&self, box_type: [u8; 4], size: u64) -> Result<(), DecodeError> {
+        if self.reject_unknown_boxes && !KNOWN_BOXES.contains(&&box_type) {
+            return Err(DecodeError::InvalidInput);
+        }
+        // Codestream boxes hold the image itself, which is bounded by the pixel limits
+        let codestream = box_type == *b"jxlc" || box_type == *b"jxlp";
+        if !codestream && (size == 0 || size > self.max_box_size) {
+            return Err(DecodeError::LimitExceeded("box size"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_box_size(&self, size: usize) -> Result<(), DecodeError> {
+        if size as u64 > self.max_box_size {
+            return Err(DecodeError::LimitExceeded("box size"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_box() {
+        let limits = DecodeLimits::STRICT;
+        assert!(limits.check_box(*b"jxlc", 0).is_ok());
+        assert!(limits.check_box(*b"Exif", 1024).is_ok());
+        assert!(matches!(
+            limits.check_box(*b"Exif", 0),
+            Err(DecodeError::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            limits.check_box(*b"abcd", 16),
+            Err(DecodeError::InvalidInput)
+        ));
+        assert!(DecodeLimits {
+            reject_unknown_boxes: false,
+            ..limits
+        }
+        .check_box(*b"abcd", 16)
+        .is_ok());
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use jpegxl_sys::{
+    color::color_encoding::JxlTransferFunction,
+    common::types::{JxlDataType, JxlEndianness, JxlPixelFormat},
+};
+
+/// Transfer function undone on `f32` output, for images libjxl does not convert itself
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Transfer {
+    Srgb,
+    Bt709,
+    /// Exponent from encoded to linear values
+    Gamma(f32),
+    /// Absolute luminance of PQ relative to the intensity target, which maps to 1.0
+    Pq(f32),
+}
+
+impl Transfer {
+    /// Return `None` for transfer functions without a conversion, e.g. HLG, which depends on
+    /// the display
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn new(
+        transfer_function: JxlTransferFunction,
+        gamma: f64,
+        intensity_target: f32,
+    ) -> Option<Self> {
+        match transfer_function {
+            JxlTransferFunction::SRGB => Some(Self::Srgb),
+            JxlTransferFunction::BT709 => Some(Self::Bt709),
+            JxlTransferFunction::Gamma if gamma > 0.0 => Some(Self::Gamma(1.0 / gamma as f32)),
+            JxlTransferFunction::DCI => Some(Self::Gamma(2.6)),
+            JxlTransferFunction::PQ => Some(Self::Pq(10000.0 / intensity_target)),
+            _ => None,
+        }
+    }
+
+    /// Convert an encoded sample to linear light, keeping the sign of out-of-gamut values
+    pub(crate) fn to_linear(self, v: f32) -> f32 {
+        let x = v.abs();
+        let linear = match self {
+            Self::Srgb if x <= 0.040_45 => x / 12.92,
+            Self::Srgb => ((x + 0.055) / 1.055).powf(2.4),
+            Self::Bt709 if x < 0.081 => x / 4.5,
+            Self::Bt709 => ((x + 0.099) / 1.099).powf(1.0 / 0.45),
+            Self::Gamma(exp) => x.powf(exp),
+            Self::Pq(scale) => {
+                const M1: f32 = 2610.0 / 16384.0;
+                const M2: f32 = 2523.0 / 4096.0 * 128.0;
+                const C1: f32 = 3424.0 / 4096.0;
+                const C2: f32 = 2413.0 / 4096.0 * 32.0;
+                const C3: f32 = 2392.0 / 4096.0 * 32.0;
+                let e = x.powf(1.0 / M2);
+                ((e - C1).max(0.0) / (C2 - C3 * e)).powf(1.0 / M1) * scale
+            }
+        };
+        linear.copysign(v)
+    }
+
+    /// Convert the color samples of `f32` pixels in place, leaving alpha and row padding
+    /// untouched
+    ///
+    /// `width` is the number of pixels in a row, which is padded to a multiple of
+    /// `format.align` bytes except for the last one.
+    pub(crate) fn apply(self, format: &JxlPixelFormat, width: usize, pixels: &mut [u8]) {
+        debug_assert!(format.data_type == JxlDataType::Float);
+        let read = match format.endianness {
+            JxlEndianness::Little => f32::from_le_bytes,
+            JxlEndianness::Big => f32::from_be_bytes,
+            JxlEndianness::Native => f32::from_ne_bytes,
+        };
+        let write = match format.endianness {
+            JxlEndianness::Little => f32::to_le_bytes,
+            JxlEndianness::Big => f32::to_be_bytes,
+            JxlEndianness::Native => f32::to_ne_bytes,
+        };
+        let channels = format.num_channels as usize;
+        let color = if channels % 2 == 0 {
+            channels - 1
+        } else {
+            channels
+        };
+
+        let row_bytes = width * channels * 4;
+        if row_bytes == 0 {
+            return;
+        }
+        let stride = match format.align {
+            0 | 1 => row_bytes,
+            align => row_bytes.div_ceil(align) * align,
+        };
+
+        for row in pixels.chunks_mut(stride) {
+            let len = row.len().min(row_bytes);
+            for pixel in row[..len].chunks_exact_mut(channels * 4) {
+                for sample in pixel.chunks_exact_mut(4).take(color) {
+                    let v = read(sample.try_into().expect("4-byte sample"));
+                    sample.copy_from_slice(&write(self.to_linear(v)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_linear() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        assert!(close(Transfer::Srgb.to_linear(0.5), 0.214_041));
+        assert!(close(Transfer::Srgb.to_linear(-0.5), -0.214_041));
+        assert!(close(Transfer::Bt709.to_linear(0.5), 0.259_589));
+        assert!(close(Transfer::Gamma(2.2).to_linear(0.5), 0.217_638));
+        // 100 nits in PQ, with an intensity target of 100 nits
+        assert!(close(Transfer::Pq(100.0).to_linear(0.508_078), 1.0));
+        for t in [Transfer::Srgb, Transfer::Bt709, Transfer::Gamma(2.2)] {
+            assert!(close(t.to_linear(0.0), 0.0));
+            assert!(close(t.to_linear(1.0), 1.0));
+        }
+    }
+
+    #[test]
+    fn apply() {
+        let format = JxlPixelFormat {
+            num_channels: 2,
+            data_type: JxlDataType::Float,
+            endianness: JxlEndianness::Big,
+            align: 0,
+        };
+        let mut pixels: Vec<u8> = [0.5f32, 0.5].iter().flat_map(|v| v.to_be_bytes()).collect();
+        Transfer::Srgb.apply(&format, 1, &mut pixels);
+
+        let gray = f32::from_be_bytes(pixels[..4].try_into().unwrap());
+        let alpha = f32::from_be_bytes(pixels[4..].try_into().unwrap());
+        assert!((gray - 0.214_041).abs() < 1e-4);
+        assert!((alpha - 0.5).abs() < f32::EPSILON);
+
+        // Rows of 3 pixels with 3 channels take 36 bytes, padded to 64 except the last one
+        let format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Float,
+            endianness: JxlEndianness::Little,
+            align: 64,
+        };
+        let row: Vec<u8> = [0.5f32; 9].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let mut pixels = [&row[..], &[0xAA; 28], &row].concat();
+        Transfer::Srgb.apply(&format, 3, &mut pixels);
+
+        for row in [&pixels[..36], &pixels[64..]] {
+            for sample in row.chunks_exact(4) {
+                let v = f32::from_le_bytes(sample.try_into().unwrap());
+                assert!((v - 0.214_041).abs() < 1e-4);
+            }
+        }
+        assert_eq!(pixels[36..64], [0xAA; 28]);
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use super::{Frame, JxlDecoder, Metadata};
+use crate::{
+    common::{Animation, PixelType},
+    DecodeError,
+};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Decoded frames of an animation, giving the frame to show at a time since playback started
+///
+/// The ticks per second and the loop count of the animation are honored, so a viewer only
+/// has to keep track of the elapsed time. The frames must be coalesced, as they are by default.
+#[derive(Debug)]
+pub struct Playback<T> {
+    metadata: Metadata,
+    frames: Vec<Frame<T>>,
+}
+
+impl<T> Playback<T> {
+    /// Create a playback from the result of [`JxlDecoder::decode_frames`]
+    #[must_use]
+    pub fn new(metadata: Metadata, frames: Vec<Frame<T>>) -> Self {
+        Self { metadata, frames }
+    }
+
+    /// Return the metadata of the image
+    #[must_use]
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Return all frames
+    #[must_use]
+    pub fn frames(&self) -> &[Frame<T>] {
+        &self.frames
+    }
+
+    /// Take the frames out of the playback
+    #[must_use]
+    pub fn into_frames(self) -> Vec<Frame<T>> {
+        self.frames
+    }
+
+    /// Return the duration of one loop of the animation, `None` for still images
+    #[must_use]
+    pub fn loop_duration(&self) -> Option<Duration> {
+        let animation = self.metadata.animation?;
+        let total = self.frames.iter().map(|f| u128::from(f.duration)).sum();
+        tick_time(&animation, total)
+    }
+
+    /// Return the index of the frame to show after `elapsed`
+    #[must_use]
+    pub fn index_at(&self, elapsed: Duration) -> usize {
+        self.locate(elapsed).0
+    }
+
+    /// Return the frame to show after `elapsed`, `None` if there are no frames
+    #[must_use]
+    pub fn frame_at(&self, elapsed: Duration) -> Option<&Frame<T>> {
+        self.frames.get(self.index_at(elapsed))
+    }
+
+    /// Return how long after `elapsed` the next frame is due, e.g. to schedule a redraw.
+    /// `None` once the last loop has ended, and for still images
+    #[must_use]
+    pub fn next_change(&self, elapsed: Duration) -> Option<Duration> {
+        let animation = self.metadata.animation?;
+        let end = self.locate(elapsed).1?;
+        tick_time(&animation, end).map(|end| end.saturating_sub(elapsed))
+    }
+
+    fn locate(&self, elapsed: Duration) -> (usize, Option<u128>) {
+        match &self.metadata.animation {
+            Some(animation) => locate(animation, self.frames.iter().map(|f| f.duration), elapsed),
+            None => (self.frames.len().saturating_sub(1), None),
+        }
+    }
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Decode every frame of an animation for playback, see [`JxlDecoder::decode_frames`]
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails
+    pub fn decode_playback<T: PixelType>(&self, data: &[u8]) -> Result<Playback<T>, DecodeError> {
+        let (metadata, frames) = self.decode_frames(data)?;
+        Ok(Playback::new(metadata, frames))
+    }
+}
+
+/// Find the frame visible after `elapsed`, and the tick at which the next one is due if the
+/// animation goes on
+pub(crate) fn locate<I>(
+    animation: &Animation,
+    durations: I,
+    elapsed: Duration,
+) -> (usize, Option<u128>)
+where
+    I: IntoIterator<Item = u32>,
+    I::IntoIter: Clone,
+{
+    let durations = durations.into_iter();
+    let last = durations.clone().count().saturating_sub(1);
+    let total: u128 = durations.clone().map(u128::from).sum();
+    if total == 0 || animation.tps_numerator == 0 {
+        return (last, None);
+    }
+
+    // Ticks elapsed, rounded down
+    let ticks = elapsed.as_nanos() * u128::from(animation.tps_numerator)
+        / (NANOS_PER_SEC * u128::from(animation.tps_denominator.max(1)));
+    let loops = u128::from(animation.num_loops);
+    if loops != 0 && ticks >= total * loops {
+        return (last, None);
+    }
+
+    let mut end = ticks - ticks % total;
+    for (index, duration) in durations.enumerate() {
+        end += u128::from(duration);
+        if end > ticks {
+            // The last frame stays after the last loop
+            return (index, (loops == 0 || end < total * loops).then_some(end));
+        }
+    }
+    (last, None)
+}
+
+/// Time at which `tick` starts, rounded up to the nanosecond
+fn tick_time(animation: &Animation, tick: u128) -> Option<Duration> {
+    let numerator = u128::from(animation.tps_numerator);
+    if numerator == 0 {
+        return None;
+    }
+    let nanos = tick
+        .checked_mul(NANOS_PER_SEC * u128::from(animation.tps_denominator.max(1)))?
+        .div_ceil(numerator);
+    let secs = u64::try_from(nanos / NANOS_PER_SEC).ok()?;
+    // Less than a second
+    #[allow(clippy::cast_possible_truncation)]
+    Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let animation = Animation {
+            tps_numerator: 100,
+            tps_denominator: 1,
+            num_loops: 2,
+        };
+        let durations = [10, 20, 30];
+        let at = |ms| locate(&animation, durations, Duration::from_millis(ms));
+
+        assert_eq!(at(0), (0, Some(10)));
+        assert_eq!(at(99), (0, Some(10)));
+        assert_eq!(at(100), (1, Some(30)));
+        assert_eq!(at(299), (1, Some(30)));
+        assert_eq!(at(300), (2, Some(60)));
+        // The second loop
+        assert_eq!(at(650), (0, Some(70)));
+        assert_eq!(at(1199), (2, None));
+        // After the last loop
+        assert_eq!(at(1200), (2, None));
+        assert_eq!(locate(&animation, durations, Duration::MAX), (2, None));
+
+        let forever = Animation {
+            num_loops: 0,
+            ..animation
+        };
+        assert_eq!(
+            locate(&forever, durations, Duration::from_millis(6_000_050)).0,
+            0
+        );
+        assert_eq!(locate(&forever, [0, 0], Duration::ZERO), (1, None));
+        assert_eq!(locate(&forever, [], Duration::ZERO), (0, None));
+    }
+
+    #[test]
+    fn test_tick_time() {
+        let animation = Animation {
+            tps_numerator: 3,
+            tps_denominator: 1,
+            num_loops: 0,
+        };
+        assert_eq!(tick_time(&animation, 0), Some(Duration::ZERO));
+        assert_eq!(
+            tick_time(&animation, 1),
+            Some(Duration::from_nanos(333_333_334))
+        );
+        // The frame has changed at the rounded up time
+        let at = tick_time(&animation, 1).unwrap_or_default();
+        assert_eq!(locate(&animation, [1, 1], at).0, 1);
+        assert_eq!(tick_time(&animation, (u128::from(u64::MAX) + 1) * 3), None);
+    }
+}
@@ -0,0 +1,328 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::mem::MaybeUninit;
+
+use jpegxl_sys::decode::{
+    JxlDecoderCloseInput, JxlDecoderFlushImage, JxlDecoderGetBasicInfo,
+    JxlDecoderGetIntendedDownsamplingRatio, JxlDecoderReset, JxlDecoderSetInput,
+    JxlDecoderSetProgressiveDetail, JxlDecoderStatus, JxlDecoderSubscribeEvents,
+};
+
+#[cfg(feature = "stream")]
+use super::{decoder_builder, JxlDecoderBuilder};
+use super::{JxlDecoder, Pixels};
+#[cfg(feature = "stream")]
+use crate::cancel::{CancelOnDrop, CancellationToken};
+use crate::{errors::check_dec_status, utils::check_valid_signature, DecodeError};
+
+/// A preview of the image at a progressive step
+#[derive(Debug)]
+pub struct ProgressivePreview {
+    /// Width of the image
+    pub width: u32,
+    /// Height of the image
+    pub height: u32,
+    /// Downsampling ratio of the preview compared to the final image, e.g. 8 for the DC
+    /// step. The pixels always have the full image dimensions.
+    pub downsampling: usize,
+    /// Whether this is the fully decoded frame
+    pub is_final: bool,
+    /// Pixels of the preview
+    pub pixels: Pixels,
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Decode a JPEG XL image progressively, calling `on_preview` at every progressive step
+    /// configured with [`JxlDecoder::progressive_detail`], and with the final image
+    ///
+    /// Each frame of an animation ends with a final preview.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails
+    pub fn decode_progressive(
+        &self,
+        data: &[u8],
+        mut on_preview: impl FnMut(ProgressivePreview),
+    ) -> Result<(), DecodeError> {
+        if check_valid_signature(data) != Some(true) {
+            return Err(DecodeError::InvalidInput);
+        }
+
+        self.setup_progressive(data)
+            .map_err(|e| self.reset_with(e))?;
+
+        let mut basic_info = MaybeUninit::uninit();
+        let mut pixel_format = MaybeUninit::uninit();
+        let mut buffer = vec![];
+        let mut transfer = None;
+        let mut width = 0;
+        let mut num_frames = 0;
+
+        let result = loop {
+            use JxlDecoderStatus as s;
+
+            if let Err(e) = self.check_cancelled() {
+                break Err(e);
+            }
+            let status = match self.process_input() {
+                Ok(status) => status,
+                Err(e) => break Err(e),
+            };
+            match status {
+                s::BasicInfo => {
+                    if let Err(e) = check_dec_status(unsafe {
+                        JxlDecoderGetBasicInfo(self.ptr, basic_info.as_mut_ptr())
+                    }) {
+                        break Err(e);
+                    }
+                    let info = unsafe { &*basic_info.as_ptr() };
+                    if let Some(Err(e)) = self.limits.map(|l| l.check_basic_info(info)) {
+                        break Err(e);
+                    }
+                    if let Some(pr) = self.runner() {
+                        pr.callback_basic_info(info);
+                    }
+                }
+                s::ColorEncoding => {
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    transfer = match self.get_color_encoding(info, None) {
+                        Ok(transfer) => transfer,
+                        Err(e) => break Err(e),
+                    };
+                }
+                s::Frame => {
+                    num_frames += 1;
+                    if let Some(Err(e)) = self.limits.map(|l| l.check_frames(num_frames)) {
+                        break Err(e);
+                    }
+                }
+                s::Box => {
+                    if let Err(e) = self.check_box() {
+                        break Err(e);
+                    }
+                }
+                s::NeedImageOutBuffer => {
+                    width = match self.output(
+                        unsafe { &*basic_info.as_ptr() },
+                        None,
+                        None,
+                        transfer.is_some(),
+                        pixel_format.as_mut_ptr(),
+                        &mut buffer,
+                    ) {
+                        Ok(width) => width,
+                        Err(e) => break Err(e),
+                    };
+                }
+                s::FrameProgression | s::FullImage => {
+                    let is_final = status == s::FullImage;
+                    let downsampling = if is_final {
+                        1
+                    } else {
+                        if let Err(e) = check_dec_status(unsafe { JxlDecoderFlushImage(self.ptr) })
+                        {
+                            break Err(e);
+                        }
+                        unsafe { JxlDecoderGetIntendedDownsamplingRatio(self.ptr) }
+                    };
+
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    let format = unsafe { pixel_format.assume_init_ref() };
+                    let mut pixels = buffer.clone();
+                    if let Some(transfer) = transfer {
+                        transfer.apply(format, width, &mut pixels);
+                    }
+                    on_preview(ProgressivePreview {
+                        width: info.xsize,
+                        height: info.ysize,
+                        downsampling,
+                        is_final,
+                        pixels: Pixels::new(pixels, format),
+                    });
+                }
+                s::Success => break Ok(()),
+                _ => break Err(DecodeError::GenericError),
+            }
+        };
+
+        unsafe { JxlDecoderReset(self.ptr) };
+        result
+    }
+
+    /// Subscribe to the progressive events and set the input
+    fn setup_progressive(&self, data: &[u8]) -> Result<(), DecodeError> {
+        self.setup_decoder(false, false, false)?;
+        let events = {
+            use JxlDecoderStatus::{BasicInfo, ColorEncoding, Frame, FrameProgression, FullImage};
+
+            let mut events = BasicInfo as i32
+                | ColorEncoding as i32
+                | FrameProgression as i32
+                | FullImage as i32;
+            if self.limits.is_some() {
+                events |= Frame as i32 | JxlDecoderStatus::Box as i32;
+            }
+            events
+        };
+        check_dec_status(unsafe { JxlDecoderSubscribeEvents(self.ptr, events) })?;
+        if let Some(detail) = self.progressive_detail {
+            check_dec_status(unsafe { JxlDecoderSetProgressiveDetail(self.ptr, detail) })?;
+        }
+        check_dec_status(unsafe { JxlDecoderSetInput(self.ptr, data.as_ptr(), data.len()) })?;
+        unsafe { JxlDecoderCloseInput(self.ptr) };
+        Ok(())
+    }
+}
+
+/// Stream of progressive previews decoded on the blocking pool of a [`tokio`] runtime
+///
+/// Dropping the stream cancels the decoding. The stream ends after the final image, or
+/// after the first error.
+#[cfg(feature = "stream")]
+pub struct ProgressiveStream {
+    receiver: tokio::sync::mpsc::Receiver<Result<ProgressivePreview, DecodeError>>,
+    _guard: CancelOnDrop,
+}
+
+#[cfg(feature = "stream")]
+impl futures_core::Stream for ProgressiveStream {
+    type Item = Result<ProgressivePreview, DecodeError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Decode a JPEG XL image progressively on the blocking pool of the current [`tokio`]
+/// runtime, see [`JxlDecoder::decode_progressive`]
+///
+/// `configure` sets up the decoder on the blocking thread.
+///
+/// # Panics
+/// Panic if not called within a runtime
+#[cfg(feature = "stream")]
+pub fn decode_progressive_stream<F>(data: Vec<u8>, configure: F) -> ProgressiveStream
+where
+    F: FnOnce(&mut JxlDecoderBuilder<'_, '_>) + Send + 'static,
+{
+    let token = CancellationToken::new();
+    let guard = token.cancel_on_drop();
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+
+    tokio::task::spawn_blocking(move || {
+        let mut builder = decoder_builder();
+        configure(&mut builder);
+        let result = builder
+            .cancellation(token.clone())
+            .build()
+            .and_then(|decoder| {
+                decoder.decode_progressive(&data, |preview| {
+                    // Stop at the next event if the stream is dropped
+                    if sender.blocking_send(Ok(preview)).is_err() {
+                        token.cancel();
+                    }
+                })
+            });
+        if let Err(e) = result {
+            _ = sender.blocking_send(Err(e));
+        }
+    });
+
+    ProgressiveStream {
+        receiver,
+        _guard: guard,
+    }
+}
+
+#[cfg(all(test, any(feature = "encoder", feature = "stream")))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::tests::SAMPLE_JXL;
+    #[cfg(feature = "encoder")]
+    use crate::{
+        decode::ProgressiveDetail, decoder_builder, encode::EncoderResult, encoder_builder,
+    };
+
+    #[test]
+    #[cfg(feature = "encoder")]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decode_progressive() -> TestResult {
+        let decoder = decoder_builder()
+            .progressive_detail(ProgressiveDetail::Passes)
+            .build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(SAMPLE_JXL)?;
+        let mut encoder = encoder_builder().progressive(true).build()?;
+        let data: EncoderResult<u8> = encoder.encode(&pixels, metadata.width, metadata.height)?;
+        let mut previews = vec![];
+        decoder.decode_progressive(&data, |p| previews.push(p))?;
+
+        let (_, Pixels::Uint8(reference)) = decoder.decode(&data)? else {
+            panic!("Expected u8 pixels");
+        };
+        let last = previews.pop().expect("Failed to get the final image");
+        assert!(last.is_final);
+        assert_eq!((last.width, last.height), (metadata.width, metadata.height));
+        assert!(matches!(last.pixels, Pixels::Uint8(p) if p == reference));
+        assert!(!previews.is_empty());
+        assert!(previews.iter().all(|p| !p.is_final && p.downsampling > 1));
+
+        assert!(matches!(
+            decoder.decode_progressive(&[], |_| {}),
+            Err(DecodeError::InvalidInput)
+        ));
+        assert!(decoder
+            .decode_progressive(&data[..data.len() / 2], |_| {})
+            .is_err());
+        decoder.decode_progressive(&data, |_| {})?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn progressive_stream() -> TestResult {
+        use std::{future::poll_fn, pin::Pin};
+
+        use futures_core::Stream;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let previews = runtime.block_on(async {
+            let mut stream = decode_progressive_stream(SAMPLE_JXL.to_vec(), |_| {});
+            let mut previews = vec![];
+            while let Some(preview) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+                previews.push(preview?);
+            }
+            Ok::<_, DecodeError>(previews)
+        })?;
+        assert!(previews.last().is_some_and(|p| p.is_final));
+
+        let errors = runtime.block_on(async {
+            let mut stream = decode_progressive_stream(vec![], |_| {});
+            poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        });
+        assert!(matches!(errors, Some(Err(DecodeError::InvalidInput))));
+
+        Ok(())
+    }
+}
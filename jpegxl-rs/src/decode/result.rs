@@ -16,13 +16,13 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 use half::f16;
-use jpegxl_sys::common::types::{JxlDataType, JxlPixelFormat};
+use jpegxl_sys::common::types::{JxlBool, JxlDataType, JxlPixelFormat};
 
-use super::Orientation;
-use crate::common::PixelType;
+use super::{DecodeWarning, Orientation};
+use crate::common::{convert_pixels, Animation, BasicInfo, PixelType};
 
 /// Result of decoding
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Metadata {
     /// Width of the image
     pub width: u32,
@@ -38,6 +38,16 @@ pub struct Metadata {
     pub num_color_channels: u32,
     /// Whether the image has an alpha channel, from metadata
     pub has_alpha_channel: bool,
+    /// Bits per alpha sample of the original image, 0 without alpha channel
+    pub alpha_bits: u32,
+    /// Whether the color channels are premultiplied by alpha in the image, the decoded pixels
+    /// are straight alpha if [`JxlDecoder::unpremul_alpha`](super::JxlDecoder::unpremul_alpha)
+    /// is enabled
+    pub alpha_premultiplied: bool,
+    /// Bits per sample of the original image, independent of the decoded pixel type
+    pub bits_per_sample: u32,
+    /// Exponent bits per sample of the original image, 0 for integer samples
+    pub exponent_bits_per_sample: u32,
     /// Intrinsic width of the image.
     /// Applications are advised to resample the decoded image to the intrinsic dimensions
     pub intrinsic_width: u32,
@@ -46,6 +56,41 @@ pub struct Metadata {
     pub intrinsic_height: u32,
     /// ICC profile
     pub icc_profile: Option<Vec<u8>>,
+    /// Animation timing, `None` for still images
+    pub animation: Option<Animation>,
+    /// Non-fatal issues found while decoding
+    pub warnings: Vec<DecodeWarning>,
+}
+
+impl Metadata {
+    pub(crate) fn new(
+        info: &BasicInfo,
+        icc_profile: Option<Vec<u8>>,
+        warnings: Vec<DecodeWarning>,
+    ) -> Self {
+        Self {
+            width: info.xsize,
+            height: info.ysize,
+            intensity_target: info.intensity_target,
+            min_nits: info.min_nits,
+            orientation: info.orientation,
+            num_color_channels: info.num_color_channels,
+            has_alpha_channel: info.alpha_bits > 0,
+            alpha_bits: info.alpha_bits,
+            alpha_premultiplied: info.alpha_premultiplied == JxlBool::True,
+            bits_per_sample: info.bits_per_sample,
+            exponent_bits_per_sample: info.exponent_bits_per_sample,
+            intrinsic_width: info.intrinsic_xsize,
+            intrinsic_height: info.intrinsic_ysize,
+            icc_profile,
+            animation: (info.have_animation == JxlBool::True).then_some(Animation {
+                tps_numerator: info.animation.tps_numerator,
+                tps_denominator: info.animation.tps_denominator,
+                num_loops: info.animation.num_loops,
+            }),
+            warnings,
+        }
+    }
 }
 
 /// Pixels returned from the decoder
@@ -70,6 +115,157 @@ impl Pixels {
             JxlDataType::Float16 => Self::Float16(f16::convert(&data, pixel_format)),
         }
     }
+
+    /// Convert the pixels to another pixel type, see [`convert_pixels`]
+    #[must_use]
+    pub fn convert<T: PixelType>(&self, dither: bool) -> Vec<T> {
+        match self {
+            Self::Float(v) => convert_pixels(v, dither),
+            Self::Uint8(v) => convert_pixels(v, dither),
+            Self::Uint16(v) => convert_pixels(v, dither),
+            Self::Float16(v) => convert_pixels(v, dither),
+        }
+    }
+
+    /// Return the pixels as bytes in native endianness
+    #[cfg(feature = "bytemuck")]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Float(v) => bytemuck::cast_slice(v),
+            Self::Uint8(v) => v,
+            Self::Uint16(v) => bytemuck::cast_slice(v),
+            Self::Float16(v) => bytemuck::cast_slice(v),
+        }
+    }
+
+    /// Reinterpret the pixels as a slice of another type, e.g. `rgb::RGBA8`
+    ///
+    /// # Errors
+    /// Return a [`bytemuck::PodCastError`] if the size or alignment doesn't match
+    #[cfg(feature = "bytemuck")]
+    pub fn cast<T: bytemuck::Pod>(&self) -> Result<&[T], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice(self.as_bytes())
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl<T: bytemuck::Pod> Frame<T> {
+    /// Return the pixels as bytes in native endianness
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.pixels)
+    }
+
+    /// Reinterpret the pixels as a slice of another type
+    ///
+    /// # Errors
+    /// Return a [`bytemuck::PodCastError`] if the size or alignment doesn't match
+    pub fn cast<U: bytemuck::Pod>(&self) -> Result<&[U], bytemuck::PodCastError> {
+        bytemuck::try_cast_slice(&self.pixels)
+    }
+}
+
+/// Pixels of a decoded image with their layout, returned by
+/// [`JxlDecoder::decode_to_result`](super::JxlDecoder::decode_to_result)
+///
+/// Rows are never padded.
+#[derive(Debug)]
+pub struct DecoderResult<T> {
+    /// Metadata of the image
+    pub metadata: Metadata,
+    /// Number of interleaved channels of each pixel
+    pub num_channels: usize,
+    /// Samples of all pixels, row by row
+    pub data: Vec<T>,
+}
+
+impl<T: PixelType> DecoderResult<T> {
+    /// Return the samples of row `y`, or `None` if it is out of bounds
+    #[must_use]
+    pub fn row(&self, y: u32) -> Option<&[T]> {
+        let row_len = (self.metadata.width as usize).checked_mul(self.num_channels)?;
+        let start = (y as usize).checked_mul(row_len)?;
+        self.data.get(start..start.checked_add(row_len)?)
+    }
+
+    /// Return an iterator over the pixels as arrays of `N` channels, row by row
+    ///
+    /// # Panics
+    /// Panic if `N` is not [`DecoderResult::num_channels`]
+    #[must_use]
+    pub fn pixels<const N: usize>(&self) -> impl ExactSizeIterator<Item = [T; N]> + '_ {
+        assert_eq!(
+            N, self.num_channels,
+            "the image has a different number of channels"
+        );
+        self.data.chunks_exact(N).map(|p| {
+            let mut pixel = [p[0]; N];
+            pixel.copy_from_slice(p);
+            pixel
+        })
+    }
+
+    /// Convert the samples to another pixel type, scaled to its full range
+    ///
+    /// Dithering is applied when the original image has more precision than the target type,
+    /// e.g. for a 10-bit image converted to `u8`, but not for an 8-bit image decoded to `u16`.
+    #[must_use]
+    pub fn convert<U: PixelType>(&self) -> DecoderResult<U> {
+        let (bits, exponent_bits) = U::bits_per_sample();
+        let dither = exponent_bits == 0
+            && (self.metadata.exponent_bits_per_sample != 0
+                || self.metadata.bits_per_sample > bits);
+
+        DecoderResult {
+            metadata: self.metadata.clone(),
+            num_channels: self.num_channels,
+            data: convert_pixels(&self.data, dither),
+        }
+    }
+
+    /// Convert the samples to `u8`, see [`DecoderResult::convert`]
+    #[must_use]
+    pub fn to_u8(&self) -> DecoderResult<u8> {
+        self.convert()
+    }
+
+    /// Convert the samples to `u16`, see [`DecoderResult::convert`]
+    #[must_use]
+    pub fn to_u16(&self) -> DecoderResult<u16> {
+        self.convert()
+    }
+
+    /// Convert the samples to `f16`, see [`DecoderResult::convert`]
+    #[must_use]
+    pub fn to_f16(&self) -> DecoderResult<f16> {
+        self.convert()
+    }
+
+    /// Convert the samples to `f32`, see [`DecoderResult::convert`]
+    #[must_use]
+    pub fn to_f32(&self) -> DecoderResult<f32> {
+        self.convert()
+    }
+}
+
+/// A frame of an animated or layered image
+#[derive(Debug, Clone)]
+pub struct Frame<T> {
+    /// Name of the frame, empty if it has no name
+    pub name: String,
+    /// Duration of the frame in ticks
+    pub duration: u32,
+    /// Width of the frame, which is the image width unless coalescing is disabled
+    pub width: u32,
+    /// Height of the frame, which is the image height unless coalescing is disabled
+    pub height: u32,
+    /// Horizontal offset of the frame on the canvas, which can be negative
+    pub x0: i32,
+    /// Vertical offset of the frame on the canvas, which can be negative
+    pub y0: i32,
+    /// Pixels of the frame
+    pub pixels: Vec<T>,
 }
 
 /// Reconstruction result
@@ -97,12 +293,98 @@ mod tests {
                 orientation: Orientation::Identity,
                 num_color_channels: 0,
                 has_alpha_channel: false,
+                alpha_bits: 0,
+                alpha_premultiplied: false,
+                bits_per_sample: 8,
+                exponent_bits_per_sample: 0,
                 intrinsic_width: 0,
                 intrinsic_height: 0,
                 icc_profile: None,
+                animation: None,
+                warnings: vec![],
             }
         );
 
         println!("{:?}", Pixels::Float(vec![]));
     }
+
+    #[test]
+    fn test_decoder_result() {
+        let result = DecoderResult {
+            metadata: Metadata {
+                width: 2,
+                height: 2,
+                intensity_target: 0.0,
+                min_nits: 0.0,
+                orientation: Orientation::Identity,
+                num_color_channels: 1,
+                has_alpha_channel: true,
+                alpha_bits: 8,
+                alpha_premultiplied: false,
+                bits_per_sample: 8,
+                exponent_bits_per_sample: 0,
+                intrinsic_width: 2,
+                intrinsic_height: 2,
+                icc_profile: None,
+                animation: None,
+                warnings: vec![],
+            },
+            num_channels: 2,
+            data: vec![1u8, 2, 3, 4, 5, 6, 7, 8],
+        };
+
+        assert_eq!(result.row(1), Some(&[5, 6, 7, 8][..]));
+        assert_eq!(result.row(2), None);
+        assert_eq!(
+            result.pixels::<2>().collect::<Vec<_>>(),
+            [[1, 2], [3, 4], [5, 6], [7, 8]]
+        );
+
+        // 8-bit samples survive a round trip through any type
+        assert_eq!(result.to_u16().to_u8().data, result.data);
+        assert_eq!(result.to_f16().to_u8().data, result.data);
+        assert!((result.to_f32().data[7] - 8.0 / 255.0).abs() < f32::EPSILON);
+
+        // Samples between two steps of `u8` are dithered for a 16-bit image
+        let mut wide = result.to_u16();
+        wide.metadata.bits_per_sample = 16;
+        wide.data = vec![100 * 257 + 128; 8];
+        let narrow = wide.to_u8().data;
+        assert!(narrow.contains(&100) && narrow.contains(&101));
+
+        // A row length overflowing `usize` is out of bounds
+        wide.num_channels = usize::MAX;
+        assert_eq!(wide.row(0), None);
+    }
+
+    #[test]
+    fn test_convert() {
+        let pixels = Pixels::Uint16(vec![0, 257, 65535]);
+        assert_eq!(pixels.convert::<u8>(false), [0, 1, 255]);
+        assert_eq!(pixels.convert::<f32>(false), [0.0, 257.0 / 65535.0, 1.0]);
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_cast() {
+        let pixels = Pixels::Uint16(vec![1, 2, 3, 4]);
+        assert_eq!(pixels.as_bytes().len(), 8);
+        assert_eq!(pixels.cast::<[u16; 2]>(), Ok(&[[1, 2], [3, 4]][..]));
+        assert!(pixels.cast::<[u8; 3]>().is_err());
+
+        let pixels = Pixels::Float16(vec![f16::ONE]);
+        assert_eq!(pixels.cast::<u16>(), Ok(&[f16::ONE.to_bits()][..]));
+
+        let frame = Frame {
+            name: String::new(),
+            duration: 0,
+            width: 1,
+            height: 1,
+            x0: 0,
+            y0: 0,
+            pixels: vec![1u8, 2, 3, 4],
+        };
+        assert_eq!(frame.as_bytes(), &[1, 2, 3, 4]);
+        assert_eq!(frame.cast::<[u8; 4]>(), Ok(&[[1, 2, 3, 4]][..]));
+    }
 }
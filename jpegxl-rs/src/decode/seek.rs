@@ -0,0 +1,167 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::{mem::MaybeUninit, ptr, time::Duration};
+
+use jpegxl_sys::{
+    common::types::JxlBool,
+    decode::{
+        JxlDecoderGetBasicInfo, JxlDecoderGetFrameHeader, JxlDecoderReset, JxlDecoderRewind,
+        JxlDecoderSetCoalescing, JxlDecoderSkipFrames, JxlDecoderStatus, JxlDecoderSubscribeEvents,
+    },
+};
+
+use super::{playback, warnings::check_output, Input, JxlDecoder, Metadata, Pixels};
+use crate::{
+    common::{Animation, BasicInfo},
+    errors::check_dec_status,
+    trace::{event, span},
+    DecodeError,
+};
+
+impl JxlDecoder<'_, '_> {
+    /// Decode the image visible `t_ms` milliseconds into an animation, e.g. for a thumbnail
+    ///
+    /// The frame headers are read first to find the frame, and the frames before it are
+    /// skipped without rendering the ones it doesn't depend on. Frames are always coalesced.
+    /// The time wraps around while the animation loops, and stays on the last frame after
+    /// the last loop. Still images are decoded regardless of the time.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails
+    pub fn decode_frame_at(
+        &self,
+        data: &[u8],
+        t_ms: u64,
+    ) -> Result<(Metadata, Pixels), DecodeError> {
+        span!("jxl_decode_frame_at", t_ms);
+
+        let result = self.frame_durations(data).and_then(|(info, durations)| {
+            let index = if info.have_animation == JxlBool::True {
+                let animation = Animation {
+                    tps_numerator: info.animation.tps_numerator,
+                    tps_denominator: info.animation.tps_denominator,
+                    num_loops: info.animation.num_loops,
+                };
+                let elapsed = Duration::from_millis(t_ms);
+                playback::locate(&animation, durations.iter().copied(), elapsed).0
+            } else {
+                0
+            };
+            event!(index, frames = durations.len(), "seek");
+            self.decode_frame(data, index)
+        });
+
+        unsafe { JxlDecoderReset(self.ptr) };
+        result
+    }
+
+    /// Read the basic info and the duration of every frame, without decoding the pixels
+    fn frame_durations(&self, data: &[u8]) -> Result<(BasicInfo, Vec<u32>), DecodeError> {
+        self.setup_decoder(false, false, true)?;
+        check_dec_status(unsafe {
+            JxlDecoderSubscribeEvents(
+                self.ptr,
+                JxlDecoderStatus::BasicInfo as i32 | JxlDecoderStatus::Frame as i32,
+            )
+        })?;
+        check_dec_status(unsafe { JxlDecoderSetCoalescing(self.ptr, true.into()) })?;
+        Input::from(data).start(self.ptr)?;
+
+        let mut basic_info = MaybeUninit::uninit();
+        let mut durations = vec![];
+        loop {
+            use JxlDecoderStatus as s;
+
+            self.check_cancelled()?;
+            match self.process_input()? {
+                s::BasicInfo => {
+                    check_dec_status(unsafe {
+                        JxlDecoderGetBasicInfo(self.ptr, basic_info.as_mut_ptr())
+                    })?;
+                }
+                s::Frame => {
+                    if let Some(limits) = &self.limits {
+                        limits.check_frames(durations.len() + 1)?;
+                    }
+                    let mut header = MaybeUninit::uninit();
+                    check_dec_status(unsafe {
+                        JxlDecoderGetFrameHeader(self.ptr, header.as_mut_ptr())
+                    })?;
+                    // The blend mode may be unknown to the bindings
+                    durations.push(unsafe { ptr::addr_of!((*header.as_ptr()).duration).read() });
+                }
+                s::Success => break,
+                _ => return Err(DecodeError::GenericError),
+            }
+        }
+
+        Ok((unsafe { basic_info.assume_init() }, durations))
+    }
+
+    /// Decode the frame at `index` after [`JxlDecoder::frame_durations`]
+    fn decode_frame(&self, data: &[u8], index: usize) -> Result<(Metadata, Pixels), DecodeError> {
+        // Rewinding keeps what the decoder learned about the frames, to skip them efficiently
+        unsafe { JxlDecoderRewind(self.ptr) };
+        self.setup_decoder(self.icc_profile, false, false)?;
+        check_dec_status(unsafe { JxlDecoderSetCoalescing(self.ptr, true.into()) })?;
+        unsafe { JxlDecoderSkipFrames(self.ptr, index) };
+        Input::from(data).start(self.ptr)?;
+
+        let mut basic_info = MaybeUninit::uninit();
+        let mut icc = self.icc_profile.then(Vec::new);
+        let mut warnings = vec![];
+        let mut transfer = None;
+        let mut pixel_format = MaybeUninit::uninit();
+        let mut buffer = vec![];
+        let mut width = 0;
+        loop {
+            use JxlDecoderStatus as s;
+
+            self.check_cancelled()?;
+            match self.process_input()? {
+                s::BasicInfo => self.get_basic_info(&mut basic_info, &mut warnings)?,
+                s::ColorEncoding => {
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    transfer = self.get_color_encoding(info, icc.as_mut())?;
+                }
+                // Subscribed with limits, which are checked on the frame headers
+                s::Frame => {}
+                s::Box => self.check_box()?,
+                s::NeedImageOutBuffer => {
+                    let info = unsafe { basic_info.assume_init_ref() };
+                    let format = pixel_format.as_mut_ptr();
+                    let float_only = transfer.is_some();
+                    width = self.output(info, None, None, float_only, format, &mut buffer)?;
+                    check_output(info, unsafe { &*format }, &mut warnings);
+                }
+                s::FullImage => break,
+                _ => return Err(DecodeError::GenericError),
+            }
+        }
+
+        let format = unsafe { pixel_format.assume_init_ref() };
+        if let Some(transfer) = transfer {
+            transfer.apply(format, width, &mut buffer);
+        }
+        let info = unsafe { basic_info.assume_init_ref() };
+        Ok((
+            Metadata::new(info, icc, warnings),
+            Pixels::new(buffer, format),
+        ))
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+
+use jpegxl_sys::common::types::{JxlDataType, JxlPixelFormat};
+
+use crate::{common::BasicInfo, trace::event};
+
+/// A non-fatal issue found while decoding, where the output holds less than the image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeWarning {
+    /// Extra channels, e.g. depth or thermal, are not part of the interleaved output
+    ExtraChannelsIgnored(u32),
+    /// The image has an alpha channel, but the pixel format has no room for it
+    AlphaDropped,
+    /// The pixel type has less precision than the samples of the image
+    PrecisionLoss {
+        /// Significant bits of the samples of the image
+        original_bits: u32,
+        /// Significant bits of the pixel type
+        output_bits: u32,
+    },
+    /// The image has no JPEG reconstruction data, so pixels are decoded instead
+    NoJpegReconstruction,
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExtraChannelsIgnored(n) => write!(f, "{n} extra channels are ignored"),
+            Self::AlphaDropped => write!(f, "The alpha channel is dropped"),
+            Self::PrecisionLoss {
+                original_bits,
+                output_bits,
+            } => write!(
+                f,
+                "{original_bits}-bit samples are reduced to {output_bits} bits"
+            ),
+            Self::NoJpegReconstruction => {
+                write!(f, "No JPEG reconstruction data, decoded to pixels")
+            }
+        }
+    }
+}
+
+/// Add a warning once
+pub(crate) fn warn(warnings: &mut Vec<DecodeWarning>, warning: DecodeWarning) {
+    if !warnings.contains(&warning) {
+        event!(%warning, "warning");
+        warnings.push(warning);
+    }
+}
+
+/// Check what the output pixel format drops from the image
+pub(crate) fn check_output(
+    info: &BasicInfo,
+    format: &JxlPixelFormat,
+    warnings: &mut Vec<DecodeWarning>,
+) {
+    if info.alpha_bits > 0 && format.num_channels % 2 == 1 {
+        warn(warnings, DecodeWarning::AlphaDropped);
+    }
+
+    // Significand bits, including the implicit bit of floats
+    let original_bits = info.bits_per_sample - info.exponent_bits_per_sample;
+    let output_bits = match format.data_type {
+        JxlDataType::Uint8 => 8,
+        JxlDataType::Uint16 => 16,
+        JxlDataType::Float16 => 11,
+        JxlDataType::Float => 24,
+    };
+    if output_bits < original_bits {
+        warn(
+            warnings,
+            DecodeWarning::PrecisionLoss {
+                original_bits,
+                output_bits,
+            },
+        );
+    }
+}
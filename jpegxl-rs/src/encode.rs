@@ -17,24 +17,63 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 
 //! Encoder of JPEG XL format
 
-use std::{marker::PhantomData, mem::MaybeUninit, ops::Deref, ptr::null};
+use std::{
+    ffi::CString,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::Deref,
+    path::Path,
+    ptr::null,
+};
 
 #[allow(clippy::wildcard_imports)]
-use jpegxl_sys::encoder::encode::*;
+use jpegxl_sys::{
+    common::types::{JxlBitDepth, JxlBitDepthType},
+    encoder::encode::*,
+};
 
+#[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+use crate::gain_map::{GainMapBundle, GAIN_MAP_BOX};
 use crate::{
-    common::PixelType, errors::EncodeError, memory::MemoryManager, parallel::ParallelRunner,
+    common::PixelType,
+    errors::EncodeError,
+    memory::MemoryManager,
+    parallel::{ModeRunner, ParallelMode, ParallelRunner},
+    trace::{event, span},
 };
 
 mod options;
 pub use options::*;
 
+#[cfg(feature = "boxes")]
 mod metadata;
+#[cfg(feature = "boxes")]
 pub use metadata::*;
 
 mod frame;
 pub use frame::*;
 
+mod extra_channel;
+pub use extra_channel::*;
+
+#[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+mod stats;
+#[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+pub use stats::*;
+
+pub use crate::{
+    cancel::CancellationToken,
+    common::{Animation, Orientation},
+};
+
+#[cfg(not(feature = "libjxl_0_9"))]
+mod output;
+#[cfg(not(feature = "libjxl_0_9"))]
+use output::OutputProcessor;
+
 // MARK: Utility types
 
 /// Encoder result
@@ -54,6 +93,72 @@ impl<U: PixelType> Deref for EncoderResult<U> {
 
 // MARK: Encoder
 
+/// Effective settings of a [`JxlEncoder`]
+#[derive(Clone, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct EncoderConfig {
+    /// Whether the image has an alpha channel
+    pub has_alpha: bool,
+    /// Whether the color channels are premultiplied by alpha
+    pub alpha_premultiplied: bool,
+    /// Whether the image is encoded losslessly
+    pub lossless: bool,
+    /// Encoder effort
+    pub speed: EncoderSpeed,
+    /// Butteraugli distance of lossy compression
+    pub quality: f32,
+    /// Butteraugli distance of the alpha channel, `None` to follow `quality`
+    pub alpha_quality: Option<f32>,
+    /// Whether the container format is used
+    pub use_container: bool,
+    /// Whether the original color profile is kept
+    pub uses_original_profile: bool,
+    /// Decoding speed tier
+    pub decoding_speed: i64,
+    /// Forced modular (`true`) or `VarDCT` (`false`) mode, `None` to let libjxl choose
+    pub modular: Option<bool>,
+    /// Whether the image is encoded for progressive decoding
+    pub progressive: bool,
+    /// Downsampling factor, `None` for the libjxl default
+    pub resampling: Option<u32>,
+    /// Downsampling factor of extra channels, `None` for the libjxl default
+    pub extra_channel_resampling: Option<u32>,
+    /// Whether the frames are already downsampled
+    pub already_downsampled: bool,
+    /// Upsampling mode for resampled images
+    pub upsampling_mode: UpsamplingMode,
+    /// Initial output buffer size
+    pub init_buffer_size: usize,
+    /// Color encoding, unused if an ICC profile is set
+    pub color_encoding: ColorEncoding,
+    /// Significant bits per sample, `None` for the full range of the pixel type
+    pub bits_per_sample: Option<u32>,
+    /// Upper bound of the intensity level in nits, `None` for the libjxl default
+    pub intensity_target: Option<f32>,
+    /// Lower bound of the intensity level in nits, `None` for the libjxl default
+    pub min_nits: Option<f32>,
+    /// Whether `linear_below` is relative to the maximum display brightness
+    pub relative_to_max_display: bool,
+    /// Intensity level below which tone mapping is skipped, `None` for the libjxl default
+    pub linear_below: Option<f32>,
+    /// Size of the ICC profile in bytes, if one is set
+    pub icc_profile_size: Option<usize>,
+    /// Orientation of the pixels
+    pub orientation: Orientation,
+    /// Animation timing, `None` for a still image
+    pub animation: Option<Animation>,
+    /// Extra channels other than the main alpha channel
+    pub extra_channels: Vec<ExtraChannel>,
+    /// Whether a cancellation token is set
+    pub cancellable: bool,
+    /// Whether a custom parallel runner is set, which takes precedence over `parallel_mode`
+    pub custom_parallel_runner: bool,
+    /// Parallel mode used without a custom runner
+    pub parallel_mode: ParallelMode,
+    /// Whether a custom memory manager is set
+    pub custom_memory_manager: bool,
+}
+
 /// JPEG XL Encoder
 #[derive(Builder)]
 #[builder(build_fn(skip, error = "None"))]
@@ -71,6 +176,11 @@ pub struct JxlEncoder<'prl, 'mm> {
     ///
     /// Default: false
     pub has_alpha: bool,
+
+    /// Set whether the color channels of the input are premultiplied by the alpha channel
+    ///
+    /// Default: false
+    pub alpha_premultiplied: bool,
     /// Set lossless
     ///
     /// Default: false
@@ -88,6 +198,12 @@ pub struct JxlEncoder<'prl, 'mm> {
     ///    Default value: 1.0. <br />
     ///    If `lossless` is set to `true`, this value is unused and implied to be 0.
     pub quality: f32,
+    /// Set quality for lossy compression of the alpha channel, same as `quality`
+    ///
+    /// E.g. 0.0 keeps the alpha channel lossless while the color channels are lossy.
+    ///
+    /// Default: `None`, using the same value as `quality`
+    pub alpha_quality: Option<f32>,
     /// Configure the encoder to use the JPEG XL container format
     ///
     /// Using the JPEG XL container format allows to store metadata such as JPEG reconstruction;
@@ -107,7 +223,34 @@ pub struct JxlEncoder<'prl, 'mm> {
     ///
     /// Minimum is 0 (highest quality), and maximum is 4 (lowest quality). Default is 0.
     pub decoding_speed: i64,
-    /// Set initial output buffer size in bytes.
+    /// Force the modular mode (`true`) or `VarDCT` mode (`false`)
+    ///
+    /// Default: `None`, letting libjxl choose
+    pub modular: Option<bool>,
+    /// Set whether the image is encoded for progressive decoding
+    ///
+    /// Default: `false`
+    pub progressive: bool,
+    /// Set the factor (1, 2, 4 or 8) to downsample the image by before compression,
+    /// which is upsampled back to the original size when decoding
+    ///
+    /// Default: `None`, where libjxl only downsamples for very low quality
+    pub resampling: Option<u32>,
+    /// Set the factor (1, 2, 4 or 8) to downsample extra channels by, like `resampling`
+    ///
+    /// Default: `None`, using the libjxl default
+    pub extra_channel_resampling: Option<u32>,
+    /// Set whether the frames are already downsampled by the `resampling` factor,
+    /// i.e. given at `ceil(width / resampling)` x `ceil(height / resampling)` pixels
+    ///
+    /// Default: `false`
+    pub already_downsampled: bool,
+    /// Set how the decoder upsamples images encoded with `resampling`
+    ///
+    /// Default: [`UpsamplingMode::Smooth`]
+    pub upsampling_mode: UpsamplingMode,
+    /// Set initial output buffer size in bytes, which is also the size of the chunks
+    /// drained from the encoder when writing to an [`io::Write`](std::io::Write).
     /// Anything less than 32 bytes will be rounded up to 32 bytes.
     ///
     /// Default: 512 KiB
@@ -118,26 +261,111 @@ pub struct JxlEncoder<'prl, 'mm> {
     /// Default: SRGB
     pub color_encoding: ColorEncoding,
 
+    /// Set the number of significant bits per sample of integer inputs,
+    /// e.g. 10 or 12 for samples stored in `u16` buffers
+    ///
+    /// The input samples are interpreted in the range of this bit depth instead of the full
+    /// range of the pixel type, and the image is encoded at this precision.
+    ///
+    /// Default: `None`, using the bit depth of the output pixel type
+    pub bits_per_sample: Option<u32>,
+
+    /// Set the upper bound on the intensity level present in the image, in nits
+    ///
+    /// Default: `None`, using the libjxl default (255 nits for SDR)
+    pub intensity_target: Option<f32>,
+    /// Set the lower bound on the intensity level present in the image, in nits
+    ///
+    /// Default: `None`, using the libjxl default (0 nits)
+    pub min_nits: Option<f32>,
+    /// Set whether `linear_below` is a ratio of the maximum display brightness
+    /// instead of an absolute value in nits
+    ///
+    /// Default: `false`
+    pub relative_to_max_display: bool,
+    /// Set the intensity level below which tone mapping should leave the image unchanged
+    ///
+    /// Default: `None`, using the libjxl default (0)
+    pub linear_below: Option<f32>,
+
+    /// Set the ICC profile of the original pixels, which takes precedence over `color_encoding`
+    ///
+    /// The color space of the profile must agree with the number of color channels,
+    /// i.e. a gray profile requires a luma-only `color_encoding`.
+    ///
+    /// Default: `None`
+    pub icc_profile: Option<Vec<u8>>,
+
+    /// Set the orientation of the pixels, for the decoder to apply when displaying the image,
+    /// like the EXIF orientation tag
+    ///
+    /// Default: [`Orientation::Identity`]
+    pub orientation: Orientation,
+
+    /// Set the animation timing, for frames to be played as an animation
+    /// with [`EncoderFrame::duration`]
+    ///
+    /// Default: `None`, a still image
+    pub animation: Option<Animation>,
+
+    /// Add extra channels other than the main alpha channel, such as spot colors
+    ///
+    /// Default: empty
+    #[builder(setter(each(name = "extra_channel")))]
+    pub extra_channels: Vec<ExtraChannel>,
+
+    /// Set a token to cancel encoding from another thread
+    ///
+    /// It is checked between chunks of the output, so an encode may take a while
+    /// to stop, e.g. while the first chunk is being compressed.
+    ///
+    /// Default: `None`
+    pub cancellation: Option<CancellationToken>,
+
     /// Set parallel runner
     ///
     /// Default: `None`, indicating single thread execution
     pub parallel_runner: Option<&'prl dyn ParallelRunner>,
 
+    /// Set the parallel mode, used when no `parallel_runner` is set
+    ///
+    /// Default: [`ParallelMode::Single`]
+    parallel_mode: ParallelMode,
+    /// Runner created for `parallel_mode`
+    #[builder(setter(skip))]
+    threads_runner: Option<ModeRunner<'mm>>,
+
     /// Whether box is used in encoder
     use_box: bool,
 
+    /// Boxes added since the last reset, added again for every encode of a size search
+    #[cfg(feature = "boxes")]
+    #[builder(setter(skip))]
+    boxes: Vec<([u8; 4], Vec<u8>, bool)>,
+
     /// Set memory manager
-    #[allow(dead_code)]
     memory_manager: Option<&'mm dyn MemoryManager>,
+
+    /// Statistics to collect during encoding
+    #[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+    #[builder(setter(skip))]
+    stats: Option<EncoderStats>,
 }
 
 impl<'prl, 'mm> JxlEncoderBuilder<'prl, 'mm> {
     /// Build a [`JxlEncoder`]
     ///
     /// # Errors
-    /// Return [`EncodeError::CannotCreateEncoder`] if it fails to create the encoder
+    /// Return [`EncodeError::InvalidConfiguration`] if a setting is out of range, or
+    /// [`EncodeError::CannotCreateEncoder`] if it fails to create the encoder
     pub fn build(&self) -> Result<JxlEncoder<'prl, 'mm>, EncodeError> {
+        self.validate()?;
+
         let mm = self.memory_manager.flatten();
+        // Create the runner first so a failure doesn't leak the libjxl handle
+        let parallel_mode = self.parallel_mode.unwrap_or_default();
+        let threads_runner = parallel_mode.runner(mm, EncodeError::CannotCreateEncoder)?;
+
         let enc = unsafe {
             mm.map_or_else(
                 || JxlEncoderCreate(null()),
@@ -159,46 +387,155 @@ impl<'prl, 'mm> JxlEncoderBuilder<'prl, 'mm> {
             enc,
             options_ptr,
             has_alpha: self.has_alpha.unwrap_or_default(),
+            alpha_premultiplied: self.alpha_premultiplied.unwrap_or_default(),
             lossless: self.lossless.unwrap_or_default(),
             speed: self.speed.unwrap_or_default(),
             quality: self.quality.unwrap_or(1.0),
+            alpha_quality: self.alpha_quality.flatten(),
             use_container: self.use_container.unwrap_or_default(),
             uses_original_profile: self.uses_original_profile.unwrap_or_default(),
             decoding_speed: self.decoding_speed.unwrap_or_default(),
+            modular: self.modular.flatten(),
+            progressive: self.progressive.unwrap_or_default(),
+            resampling: self.resampling.flatten(),
+            extra_channel_resampling: self.extra_channel_resampling.flatten(),
+            already_downsampled: self.already_downsampled.unwrap_or_default(),
+            upsampling_mode: self.upsampling_mode.unwrap_or_default(),
             init_buffer_size,
             color_encoding: self.color_encoding.unwrap_or(ColorEncoding::Srgb),
+            bits_per_sample: self.bits_per_sample.flatten(),
+            intensity_target: self.intensity_target.flatten(),
+            min_nits: self.min_nits.flatten(),
+            relative_to_max_display: self.relative_to_max_display.unwrap_or_default(),
+            linear_below: self.linear_below.flatten(),
+            icc_profile: self.icc_profile.clone().flatten(),
+            orientation: self.orientation.unwrap_or(Orientation::Identity),
+            animation: self.animation.flatten(),
+            extra_channels: self.extra_channels.clone().unwrap_or_default(),
+            cancellation: self.cancellation.clone().flatten(),
             parallel_runner: self.parallel_runner.flatten(),
+            parallel_mode,
+            threads_runner,
             use_box: self.use_box.unwrap_or_default(),
+            #[cfg(feature = "boxes")]
+            boxes: Vec::new(),
             memory_manager: mm,
+            #[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+            stats: None,
         })
     }
 
+    fn validate(&self) -> Result<(), EncodeError> {
+        let invalid = |msg: String| Err(EncodeError::InvalidConfiguration(msg));
+
+        for (name, quality) in [
+            ("quality", self.quality),
+            ("alpha_quality", self.alpha_quality.flatten()),
+        ] {
+            if let Some(q) = quality.filter(|q| !(0.0..=25.0).contains(q)) {
+                return invalid(format!("`{name}` must be 0.0 to 25.0, got {q}"));
+            }
+        }
+        if let Some(speed) = self.decoding_speed.filter(|s| !(0..=4).contains(s)) {
+            return invalid(format!("`decoding_speed` must be 0 to 4, got {speed}"));
+        }
+        for (name, factor) in [
+            ("resampling", self.resampling.flatten()),
+            (
+                "extra_channel_resampling",
+                self.extra_channel_resampling.flatten(),
+            ),
+        ] {
+            if let Some(f) = factor.filter(|f| ![1, 2, 4, 8].contains(f)) {
+                return invalid(format!("`{name}` must be 1, 2, 4 or 8, got {f}"));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Set the `quality` parameter from a JPEG-style quality factor (0-100, higher is better
     /// quality).
     pub fn jpeg_quality(&mut self, quality: f32) -> &mut Self {
         // SAFETY: the C API has no safety requirements.
-        self.quality = Some(unsafe { JxlEncoderDistanceFromQuality(quality) });
+        #[cfg(not(feature = "libjxl_0_8"))]
+        let distance = unsafe { JxlEncoderDistanceFromQuality(quality) };
+        // Same mapping as `JxlEncoderDistanceFromQuality`, which libjxl 0.8 lacks
+        #[cfg(feature = "libjxl_0_8")]
+        let distance = if quality >= 100.0 {
+            0.0
+        } else if quality >= 30.0 {
+            0.1 + (100.0 - quality) * 0.09
+        } else {
+            53.0 / 3000.0 * quality * quality - 23.0 / 20.0 * quality + 25.0
+        };
+        self.quality = Some(distance);
+        self
+    }
+
+    /// Configure encoding of CMYK images with the given CMYK ICC profile.
+    ///
+    /// Adds a black extra channel before any other extra channel, to be filled with
+    /// [`EncoderFrame::from_cmyk`], and keeps the original profile. Calling it again
+    /// only replaces the profile.
+    pub fn cmyk(&mut self, icc_profile: Vec<u8>) -> &mut Self {
+        self.icc_profile = Some(Some(icc_profile));
+        self.uses_original_profile = Some(true);
+        let channels = self.extra_channels.get_or_insert_with(Vec::new);
+        if channels
+            .first()
+            .map_or(true, |c| c.channel_type != ExtraChannelType::Black)
+        {
+            channels.insert(0, ExtraChannel::black());
+        }
+        self
+    }
+
+    /// Apply the settings of a preset, see [`EncoderPreset`].
+    pub fn preset(&mut self, preset: EncoderPreset) -> &mut Self {
+        if preset.lossless {
+            self.lossless_original();
+        } else {
+            self.lossless = Some(false);
+            self.quality = Some(preset.quality);
+        }
+        self.speed = Some(preset.speed);
+        self.modular = Some(preset.modular);
+        self.progressive = Some(preset.progressive);
+        self
+    }
+
+    /// Configure mathematically lossless encoding which keeps the original color profile.
+    ///
+    /// Sets `lossless` and `uses_original_profile` to `true` and `quality` to 0.0.
+    pub fn lossless_original(&mut self) -> &mut Self {
+        self.lossless = Some(true);
+        self.uses_original_profile = Some(true);
+        self.quality = Some(0.0);
         self
     }
 }
 
 // MARK: Private helper functions
 impl JxlEncoder<'_, '_> {
+    /// Return the parallel runner, or the one created for `parallel_mode`
+    fn runner(&self) -> Option<&dyn ParallelRunner> {
+        self.parallel_runner
+            .or_else(|| self.threads_runner.as_ref().map(|r| r as _))
+    }
+
     /// Error mapping from underlying C const to [`EncodeError`] enum
     #[cfg_attr(coverage_nightly, coverage(off))]
-    fn check_enc_status(&self, status: JxlEncoderStatus) -> Result<(), EncodeError> {
-        match status {
-            JxlEncoderStatus::Success => Ok(()),
-            JxlEncoderStatus::Error => match unsafe { JxlEncoderGetError(self.enc) } {
-                JxlEncoderError::OK => unreachable!(),
-                JxlEncoderError::Generic => Err(EncodeError::GenericError),
-                JxlEncoderError::OutOfMemory => Err(EncodeError::OutOfMemory),
-                JxlEncoderError::Jbrd => Err(EncodeError::Jbrd),
-                JxlEncoderError::BadInput => Err(EncodeError::BadInput),
-                JxlEncoderError::NotSupported => Err(EncodeError::NotSupported),
-                JxlEncoderError::ApiUsage => Err(EncodeError::ApiUsage),
-            },
-            JxlEncoderStatus::NeedMoreOutput => Err(EncodeError::NeedMoreOutput),
+    fn check_enc_status(&self, status: i32) -> Result<(), EncodeError> {
+        match JxlEncoderStatus::try_from(status) {
+            Ok(JxlEncoderStatus::Success) => Ok(()),
+            Ok(JxlEncoderStatus::Error) => {
+                let error = unsafe { JxlEncoderGetError(self.enc) };
+                Err(JxlEncoderError::try_from(error)
+                    .map_or_else(EncodeError::UnknownStatus, Into::into))
+            }
+            Ok(JxlEncoderStatus::NeedMoreOutput) => Err(EncodeError::NeedMoreOutput),
+            _ => Err(EncodeError::UnknownStatus(status)),
         }
     }
 
@@ -225,6 +562,57 @@ impl JxlEncoder<'_, '_> {
                 self.decoding_speed,
             )
         })?;
+        if let Some(modular) = self.modular {
+            self.check_enc_status(unsafe {
+                JxlEncoderFrameSettingsSetOption(
+                    self.options_ptr,
+                    JxlEncoderFrameSettingId::Modular,
+                    modular.into(),
+                )
+            })?;
+        }
+        if self.progressive {
+            for option in [
+                JxlEncoderFrameSettingId::Responsive,
+                JxlEncoderFrameSettingId::ProgressiveDc,
+                JxlEncoderFrameSettingId::QprogressiveAc,
+            ] {
+                self.check_enc_status(unsafe {
+                    JxlEncoderFrameSettingsSetOption(self.options_ptr, option, 1)
+                })?;
+            }
+        }
+        if let Some(factor) = self.resampling {
+            self.check_enc_status(unsafe {
+                JxlEncoderFrameSettingsSetOption(
+                    self.options_ptr,
+                    JxlEncoderFrameSettingId::Resampling,
+                    factor.into(),
+                )
+            })?;
+        }
+        if let Some(factor) = self.extra_channel_resampling {
+            self.check_enc_status(unsafe {
+                JxlEncoderFrameSettingsSetOption(
+                    self.options_ptr,
+                    JxlEncoderFrameSettingId::ExtraChannelResampling,
+                    factor.into(),
+                )
+            })?;
+        }
+        if self.already_downsampled {
+            self.check_enc_status(unsafe {
+                JxlEncoderFrameSettingsSetOption(
+                    self.options_ptr,
+                    JxlEncoderFrameSettingId::AlreadyDownsampled,
+                    1,
+                )
+            })?;
+        }
+        #[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+        if let Some(stats) = &self.stats {
+            unsafe { JxlEncoderCollectStats(self.options_ptr, stats.as_ptr()) };
+        }
 
         Ok(())
     }
@@ -237,7 +625,12 @@ impl JxlEncoder<'_, '_> {
         (bits, exp): (u32, u32),
         has_alpha: bool,
     ) -> Result<(), EncodeError> {
-        if let Some(runner) = self.parallel_runner {
+        let bits = match self.bits_per_sample {
+            Some(custom) if exp == 0 => custom,
+            _ => bits,
+        };
+
+        if let Some(runner) = self.runner() {
             unsafe {
                 self.check_enc_status(JxlEncoderSetParallelRunner(
                     self.enc,
@@ -264,46 +657,194 @@ impl JxlEncoder<'_, '_> {
         basic_info.exponent_bits_per_sample = exp;
 
         if has_alpha {
-            basic_info.num_extra_channels = 1;
             basic_info.alpha_bits = bits;
             basic_info.alpha_exponent_bits = exp;
+            basic_info.alpha_premultiplied = self.alpha_premultiplied.into();
         } else {
-            basic_info.num_extra_channels = 0;
             basic_info.alpha_bits = 0;
             basic_info.alpha_exponent_bits = 0;
         }
+        basic_info.num_extra_channels = u32::from(has_alpha)
+            + u32::try_from(self.extra_channels.len()).map_err(|_| EncodeError::ApiUsage)?;
 
-        match self.color_encoding {
-            ColorEncoding::SrgbLuma | ColorEncoding::LinearSrgbLuma => {
-                basic_info.num_color_channels = 1;
-            }
-            _ => (),
+        if let Some(intensity_target) = self.intensity_target {
+            basic_info.intensity_target = intensity_target;
+        }
+        if let Some(min_nits) = self.min_nits {
+            basic_info.min_nits = min_nits;
         }
+        basic_info.relative_to_max_display = self.relative_to_max_display.into();
+        if let Some(linear_below) = self.linear_below {
+            basic_info.linear_below = linear_below;
+        }
+
+        basic_info.orientation = self.orientation;
 
-        if let Some(pr) = self.parallel_runner {
+        if let Some(animation) = self.animation {
+            basic_info.have_animation = true.into();
+            basic_info.animation.tps_numerator = animation.tps_numerator;
+            basic_info.animation.tps_denominator = animation.tps_denominator;
+            basic_info.animation.num_loops = animation.num_loops;
+        }
+
+        if self.color_encoding.is_gray() {
+            basic_info.num_color_channels = 1;
+        }
+
+        if let Some(pr) = self.runner() {
             pr.callback_basic_info(&basic_info);
         }
 
         self.check_enc_status(unsafe { JxlEncoderSetBasicInfo(self.enc, &basic_info) })?;
+        event!(
+            width,
+            height,
+            bits_per_sample = bits,
+            num_color_channels = basic_info.num_color_channels,
+            alpha_bits = basic_info.alpha_bits,
+            "basic info"
+        );
+
+        for factor in [self.resampling, self.extra_channel_resampling]
+            .into_iter()
+            .flatten()
+        {
+            #[cfg(not(feature = "libjxl_0_8"))]
+            self.check_enc_status(unsafe {
+                JxlEncoderSetUpsamplingMode(self.enc, factor.into(), self.upsampling_mode as _)
+            })?;
+            // libjxl 0.8 only has the default upsampling
+            #[cfg(feature = "libjxl_0_8")]
+            if factor != 1 && self.upsampling_mode != UpsamplingMode::Smooth {
+                return Err(EncodeError::NotSupported);
+            }
+        }
 
-        self.check_enc_status(unsafe {
-            JxlEncoderSetColorEncoding(self.enc, &self.color_encoding.into())
-        })
+        self.setup_extra_channels((bits, exp), has_alpha)?;
+
+        if let Some(icc_profile) = &self.icc_profile {
+            check_icc_profile(icc_profile, basic_info.num_color_channels)?;
+            self.check_enc_status(unsafe {
+                JxlEncoderSetICCProfile(self.enc, icc_profile.as_ptr(), icc_profile.len())
+            })?;
+            event!(icc_bytes = icc_profile.len(), "color encoding");
+        } else {
+            self.check_enc_status(unsafe {
+                JxlEncoderSetColorEncoding(self.enc, &self.color_encoding.into())
+            })?;
+            event!(color_encoding = ?self.color_encoding, "color encoding");
+        }
+        Ok(())
+    }
+
+    // Set up the alpha and extra channels, after the basic info
+    fn setup_extra_channels(
+        &self,
+        (bits, exp): (u32, u32),
+        has_alpha: bool,
+    ) -> Result<(), EncodeError> {
+        if has_alpha && self.alpha_premultiplied {
+            // libjxl doesn't carry `alpha_premultiplied` over from the basic info
+            let mut info = ExtraChannel::new(ExtraChannelType::Alpha).info((bits, exp));
+            info.alpha_premultiplied = true.into();
+            self.check_enc_status(unsafe { JxlEncoderSetExtraChannelInfo(self.enc, 0, &info) })?;
+        }
+
+        for (i, channel) in self.extra_channels.iter().enumerate() {
+            let index = i + usize::from(has_alpha);
+            self.check_enc_status(unsafe {
+                JxlEncoderSetExtraChannelInfo(self.enc, index, &channel.info((bits, exp)))
+            })?;
+            if let Some(name) = &channel.name {
+                self.check_enc_status(unsafe {
+                    JxlEncoderSetExtraChannelName(self.enc, index, name.as_ptr(), name.len())
+                })?;
+            }
+            if let Some(quality) = channel.quality {
+                self.check_enc_status(unsafe {
+                    JxlEncoderSetExtraChannelDistance(self.options_ptr, index, quality)
+                })?;
+            }
+        }
+        if let (true, Some(quality)) = (has_alpha, self.alpha_quality) {
+            self.check_enc_status(unsafe {
+                JxlEncoderSetExtraChannelDistance(self.options_ptr, 0, quality)
+            })?;
+        }
+
+        Ok(())
     }
 
     // Add a frame
     fn add_frame<T: PixelType>(&self, frame: &EncoderFrame<T>) -> Result<(), EncodeError> {
+        if frame.extra_channels.len() != self.extra_channels.len() {
+            return Err(EncodeError::ApiUsage);
+        }
+
+        if let (Some(_), (_, 0)) = (self.bits_per_sample, T::bits_per_sample()) {
+            self.check_enc_status(unsafe {
+                JxlEncoderSetFrameBitDepth(
+                    self.options_ptr,
+                    &JxlBitDepth {
+                        r#type: JxlBitDepthType::FromCodestream,
+                        bits_per_sample: 0,
+                        exponent_bits_per_sample: 0,
+                    },
+                )
+            })?;
+        }
+
+        // Always set the header and name, so they don't carry over from the previous frame
+        self.check_enc_status(unsafe {
+            JxlEncoderSetFrameHeader(self.options_ptr, &frame.header())
+        })?;
+        let blend_info = frame.blend_info();
+        for i in 0..usize::from(self.has_alpha) + self.extra_channels.len() {
+            self.check_enc_status(unsafe {
+                JxlEncoderSetExtraChannelBlendInfo(self.options_ptr, i, &blend_info)
+            })?;
+        }
+
+        let name = CString::new(frame.name.as_deref().unwrap_or_default())
+            .map_err(|_| EncodeError::ApiUsage)?;
+        self.check_enc_status(unsafe {
+            JxlEncoderSetFrameName(self.options_ptr, name.as_ptr().cast())
+        })?;
+
         self.check_enc_status(unsafe {
             JxlEncoderAddImageFrame(
                 self.options_ptr,
                 &frame.pixel_format(),
                 frame.data.as_ptr().cast(),
-                std::mem::size_of_val(frame.data),
+                std::mem::size_of_val(frame.data.as_ref()),
             )
-        })
+        })?;
+
+        let pixel_format = frame.extra_channel_format();
+        for (i, data) in frame.extra_channels.iter().enumerate() {
+            let index =
+                u32::try_from(i).map_err(|_| EncodeError::ApiUsage)? + u32::from(self.has_alpha);
+            self.check_enc_status(unsafe {
+                JxlEncoderSetExtraChannelBuffer(
+                    self.options_ptr,
+                    &pixel_format,
+                    data.as_ptr().cast(),
+                    std::mem::size_of_val(data.as_ref()),
+                    index,
+                )
+            })?;
+        }
+
+        event!(
+            bytes = std::mem::size_of_val(frame.data.as_ref()),
+            extra_channels = frame.extra_channels.len(),
+            "frame input"
+        );
+        Ok(())
     }
 
     // Add a frame from JPEG raw data
+    #[cfg(feature = "jpeg")]
     fn add_jpeg_frame(&self, data: &[u8]) -> Result<(), EncodeError> {
         self.check_enc_status(unsafe {
             JxlEncoderAddJPEGFrame(
@@ -311,47 +852,109 @@ impl JxlEncoder<'_, '_> {
                 data.as_ptr().cast(),
                 std::mem::size_of_val(data),
             )
-        })
+        })?;
+        event!(bytes = data.len(), "JPEG frame input");
+        Ok(())
     }
 
-    fn _internal(&mut self) -> Result<Vec<u8>, EncodeError> {
+    // Drain the output in chunks of `init_buffer_size` into the sink
+    fn process_output(
+        &mut self,
+        mut sink: impl FnMut(&[u8]) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError> {
+        span!("jxl_encode_output");
         unsafe { JxlEncoderCloseInput(self.enc) };
 
         let mut buffer = vec![0; self.init_buffer_size];
-        let mut next_out = buffer.as_mut_ptr().cast();
-        let mut avail_out = buffer.len();
+        #[cfg(feature = "tracing")]
+        let mut total = 0;
+        let result = loop {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                break Err(EncodeError::Cancelled);
+            }
+
+            let mut next_out = buffer.as_mut_ptr();
+            let mut avail_out = buffer.len();
 
-        let mut status;
-        loop {
-            status = unsafe { JxlEncoderProcessOutput(self.enc, &mut next_out, &mut avail_out) };
+            let status =
+                unsafe { JxlEncoderProcessOutput(self.enc, &mut next_out, &mut avail_out) };
 
-            if status != JxlEncoderStatus::NeedMoreOutput {
-                break;
+            let written = buffer.len() - avail_out;
+            #[cfg(feature = "tracing")]
+            {
+                total += written;
+            }
+            if let Err(e) = sink(&buffer[..written]) {
+                break Err(e);
             }
+            if status != JxlEncoderStatus::NeedMoreOutput as i32 {
+                break self.check_enc_status(status);
+            }
+        };
+        event!(bytes = total, "output");
 
-            unsafe {
-                let offset = next_out.offset_from(buffer.as_ptr());
-                debug_assert!(offset >= 0);
+        self.reset();
 
-                buffer.resize(buffer.len() * 2, 0);
-                next_out = buffer.as_mut_ptr().offset(offset);
-                avail_out = buffer.len().wrapping_add_signed(-offset);
-            }
-        }
-        buffer.truncate(next_out as usize - buffer.as_ptr() as usize);
-        self.check_enc_status(status)?;
+        result
+    }
 
-        unsafe { JxlEncoderReset(self.enc) };
-        self.options_ptr = unsafe { JxlEncoderFrameSettingsCreate(self.enc, null()) };
+    fn encode_internal(&mut self) -> Result<Vec<u8>, EncodeError> {
+        let mut buffer = Vec::with_capacity(self.init_buffer_size);
+        self.process_output(|chunk| {
+            buffer.extend_from_slice(chunk);
+            Ok(())
+        })?;
 
         buffer.shrink_to_fit();
         Ok(buffer)
     }
 
+    // Encode into a writer
+    fn encode_to_writer_internal(&mut self, writer: &mut impl Write) -> Result<(), EncodeError> {
+        self.process_output(|chunk| Ok(writer.write_all(chunk)?))?;
+        Ok(writer.flush()?)
+    }
+
+    // Encode the frame through an output processor writing into a seekable writer
+    #[cfg(not(feature = "libjxl_0_9"))]
+    fn encode_to_seekable_internal<T: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        writer: &mut (impl Write + std::io::Seek),
+    ) -> Result<(), EncodeError> {
+        let mut processor =
+            OutputProcessor::new(writer, self.init_buffer_size, self.cancellation.clone())?;
+
+        let result = self
+            .check_enc_status(unsafe { JxlEncoderSetOutputProcessor(self.enc, processor.as_raw()) })
+            .and_then(|()| self.add_frame(frame))
+            .and_then(|()| {
+                unsafe { JxlEncoderCloseInput(self.enc) };
+                self.check_enc_status(unsafe { JxlEncoderFlushInput(self.enc) })
+            });
+
+        self.reset();
+
+        if let Some(payload) = processor.panic.take() {
+            std::panic::resume_unwind(payload);
+        }
+        if processor.cancelled {
+            return Err(EncodeError::Cancelled);
+        }
+        match (result, processor.error.is_some()) {
+            (Err(_), true) | (Ok(()), _) => Ok(processor.finish()?),
+            (Err(e), false) => Err(e),
+        }
+    }
+
     // Start encoding
     fn start_encoding<U: PixelType>(&mut self) -> Result<EncoderResult<U>, EncodeError> {
         Ok(EncoderResult {
-            data: self._internal()?,
+            data: self.encode_internal()?,
             _pixel_type: PhantomData,
         })
     }
@@ -359,13 +962,35 @@ impl JxlEncoder<'_, '_> {
 
 // MARK: Public interface
 impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
-    /// Set a specific encoder frame setting
+    /// Reset the encoder to be reused for another image, keeping the settings,
+    /// parallel runner and memory manager
+    ///
+    /// The encoder is reset automatically after encoding,
+    /// so this is only needed to discard the state after an error.
+    pub fn reset(&mut self) {
+        unsafe { JxlEncoderReset(self.enc) };
+        self.options_ptr = unsafe { JxlEncoderFrameSettingsCreate(self.enc, null()) };
+        self.use_box = false;
+        #[cfg(feature = "boxes")]
+        self.boxes.clear();
+    }
+
+    /// Allow expert options, such as effort 11, which can be extremely slow
+    ///
+    /// This stays enabled for the lifetime of the encoder.
+    pub fn allow_expert_options(&mut self) {
+        unsafe { JxlEncoderAllowExpertOptions(self.enc) };
+    }
+
+    /// Set a specific encoder frame setting, for settings without a dedicated option
+    ///
+    /// The setting applies to the next encode.
     ///
     /// # Errors
     /// Return [`EncodeError`] if it fails to set frame option
     pub fn set_frame_option(
         &mut self,
-        option: JxlEncoderFrameSettingId,
+        option: FrameSetting,
         value: i64,
     ) -> Result<(), EncodeError> {
         self.check_enc_status(unsafe {
@@ -373,6 +998,29 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
         })
     }
 
+    /// Set a specific encoder frame setting which takes a floating point value,
+    /// e.g. [`FrameSetting::PhotonNoise`]
+    ///
+    /// The setting applies to the next encode.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if it fails to set frame option
+    pub fn set_frame_float_option(
+        &mut self,
+        option: FrameSetting,
+        value: f32,
+    ) -> Result<(), EncodeError> {
+        self.check_enc_status(unsafe {
+            JxlEncoderFrameSettingsSetFloatOption(self.options_ptr, option, value)
+        })
+    }
+
+    /// Collect statistics of all following encodes into `stats`
+    #[cfg(all(feature = "stats", not(feature = "libjxl_0_8")))]
+    pub fn collect_stats(&mut self, stats: &EncoderStats) {
+        self.stats = Some(stats.clone());
+    }
+
     /// Return a wrapper type for adding multiple frames to the encoder
     ///
     /// # Errors
@@ -390,6 +1038,7 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
     ///
     /// # Errors
     /// Return [`EncodeError`] if it fails to add metadata
+    #[cfg(feature = "boxes")]
     pub fn add_metadata(&mut self, metadata: &Metadata, compress: bool) -> Result<(), EncodeError> {
         let (&t, &data) = match metadata {
             Metadata::Exif(data) => (b"Exif", data),
@@ -397,6 +1046,33 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
             Metadata::Jumb(data) => (b"jumb", data),
             Metadata::Custom(t, data) => (t, data),
         };
+        self.add_box_internal(t, data, compress)
+    }
+
+    /// Add an arbitrary container box to the encoder, which can optionally be
+    /// Brotli-compressed
+    ///
+    /// Boxes are closed automatically when encoding.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if it fails to add the box
+    #[cfg(feature = "boxes")]
+    pub fn add_box(
+        &mut self,
+        box_type: BoxType,
+        data: &[u8],
+        compress: bool,
+    ) -> Result<(), EncodeError> {
+        self.add_box_internal(*box_type.as_bytes(), data, compress)
+    }
+
+    #[cfg(feature = "boxes")]
+    fn add_box_internal(
+        &mut self,
+        t: [u8; 4],
+        data: &[u8],
+        compress: bool,
+    ) -> Result<(), EncodeError> {
         if !self.use_box {
             self.check_enc_status(unsafe { JxlEncoderUseBoxes(self.enc) })?;
             self.use_box = true;
@@ -409,7 +1085,54 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
                 data.len(),
                 compress.into(),
             )
-        })
+        })?;
+        self.boxes.push((t, data.to_vec(), compress));
+        Ok(())
+    }
+
+    /// Add `boxes` again if the encoder was reset since they were added
+    #[cfg(feature = "boxes")]
+    fn restore_boxes(&mut self, boxes: &[([u8; 4], Vec<u8>, bool)]) -> Result<(), EncodeError> {
+        if self.boxes.is_empty() {
+            for (t, data, compress) in boxes {
+                self.add_box_internal(*t, data, *compress)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add an Exif metadata box to the encoder
+    ///
+    /// `data` is the raw Exif (TIFF) data, optionally starting with the `Exif\0\0` marker
+    /// from a JPEG APP1 segment. The box can optionally be Brotli-compressed.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if it fails to add metadata
+    #[cfg(feature = "boxes")]
+    pub fn add_exif(&mut self, data: &[u8], compress: bool) -> Result<(), EncodeError> {
+        let data = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+        // Prepend the offset of the TIFF header, which immediately follows
+        let data = [&[0; 4], data].concat();
+        self.add_metadata(&Metadata::Exif(&data), compress)
+    }
+
+    /// Add an XMP metadata box to the encoder, which can optionally be Brotli-compressed
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if it fails to add metadata
+    #[cfg(feature = "boxes")]
+    pub fn add_xmp(&mut self, xmp: &str, compress: bool) -> Result<(), EncodeError> {
+        self.add_metadata(&Metadata::Xmp(xmp.as_bytes()), compress)
+    }
+
+    /// Add a gain map bundle to the encoder, stored in a `jhgm` box
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the bundle fails to be serialized or added
+    #[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+    pub fn add_gain_map(&mut self, bundle: &GainMapBundle) -> Result<(), EncodeError> {
+        let data = bundle.to_bytes()?;
+        self.add_metadata(&Metadata::Custom(GAIN_MAP_BOX, &data), false)
     }
 
     /// Encode a JPEG XL image from existing raw JPEG data
@@ -418,8 +1141,9 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
     ///
     /// # Errors
     /// Return [`EncodeError`] if the internal encoder fails to encode
+    #[cfg(feature = "jpeg")]
     pub fn encode_jpeg(&mut self, data: &[u8]) -> Result<EncoderResult<u8>, EncodeError> {
-        if let Some(runner) = self.parallel_runner {
+        if let Some(runner) = self.runner() {
             unsafe {
                 self.check_enc_status(JxlEncoderSetParallelRunner(
                     self.enc,
@@ -438,6 +1162,37 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
         self.start_encoding()
     }
 
+    /// Encode a JPEG XL image from existing raw JPEG data, writing the output to `writer`
+    /// in chunks of `init_buffer_size` bytes.
+    ///
+    /// See [`encode_jpeg`](Self::encode_jpeg) for details.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+    /// be written
+    #[cfg(feature = "jpeg")]
+    pub fn encode_jpeg_to_writer(
+        &mut self,
+        data: &[u8],
+        writer: &mut impl Write,
+    ) -> Result<(), EncodeError> {
+        if let Some(runner) = self.runner() {
+            unsafe {
+                self.check_enc_status(JxlEncoderSetParallelRunner(
+                    self.enc,
+                    runner.runner(),
+                    runner.as_opaque_ptr(),
+                ))?;
+            }
+        }
+
+        self.set_options()?;
+        self.check_enc_status(unsafe { JxlEncoderStoreJPEGMetadata(self.enc, true) })?;
+
+        self.add_jpeg_frame(data)?;
+        self.encode_to_writer_internal(writer)
+    }
+
     /// Encode a JPEG XL image from pixels
     ///
     /// Note: Use RGB(3) channels, native endianness and no alignment.
@@ -471,6 +1226,204 @@ impl<'prl, 'mm> JxlEncoder<'prl, 'mm> {
         self.add_frame(frame)?;
         self.start_encoding::<U>()
     }
+
+    /// Encode a JPEG XL image from pixels, writing the output to `writer`
+    /// in chunks of `init_buffer_size` bytes.
+    ///
+    /// See [`encode`](Self::encode) for details.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+    /// be written
+    pub fn encode_to_writer<T: PixelType, U: PixelType>(
+        &mut self,
+        data: &[T],
+        width: u32,
+        height: u32,
+        writer: &mut impl Write,
+    ) -> Result<(), EncodeError> {
+        self.encode_frame_to_writer::<T, U>(&EncoderFrame::new(data), width, height, writer)
+    }
+
+    /// Encode a JPEG XL image from a frame, writing the output to `writer`
+    /// in chunks of `init_buffer_size` bytes.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+    /// be written
+    pub fn encode_frame_to_writer<T: PixelType, U: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        width: u32,
+        height: u32,
+        writer: &mut impl Write,
+    ) -> Result<(), EncodeError> {
+        self.setup_encoder(width, height, U::bits_per_sample(), self.has_alpha)?;
+        self.add_frame(frame)?;
+        self.encode_to_writer_internal(writer)
+    }
+
+    /// Encode a JPEG XL image from a frame into a seekable writer.
+    ///
+    /// The output is pulled by the encoder through an output processor, which writes it in
+    /// place and may seek back to fill in sizes, so the compressed output is never
+    /// held in memory as a whole.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+    /// be written, or [`EncodeError::Cancelled`] if the encoding is cancelled
+    ///
+    /// Not available with the `libjxl_0_8` and `libjxl_0_9` features, as it requires
+    /// `libjxl` 0.10.
+    #[cfg(not(feature = "libjxl_0_9"))]
+    pub fn encode_frame_to_seekable<T: PixelType, U: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        width: u32,
+        height: u32,
+        writer: &mut (impl Write + std::io::Seek),
+    ) -> Result<(), EncodeError> {
+        self.setup_encoder(width, height, U::bits_per_sample(), self.has_alpha)?;
+        self.encode_to_seekable_internal(frame, writer)
+    }
+
+    /// Encode a JPEG XL image from a frame with the best quality that fits in `target_size`
+    /// bytes.
+    ///
+    /// The distance is found by a binary search over `quality`, encoding the image several
+    /// times. `quality` is restored afterwards, and `lossless` must be disabled.
+    /// If even the lowest quality doesn't fit, that result is returned anyway.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode,
+    /// or [`EncodeError::ApiUsage`] if `lossless` is enabled
+    pub fn encode_to_target_size<T: PixelType, U: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        width: u32,
+        height: u32,
+        target_size: usize,
+    ) -> Result<EncoderResult<U>, EncodeError> {
+        if self.lossless {
+            return Err(EncodeError::ApiUsage);
+        }
+
+        let quality = self.quality;
+        let result = self.search_target_size(frame, width, height, target_size);
+        self.quality = quality;
+        result
+    }
+
+    /// Encode a JPEG XL image from a frame with the best quality that fits in
+    /// `bits_per_pixel` bits per pixel on average.
+    ///
+    /// See [`encode_to_target_size`](Self::encode_to_target_size) for details.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode,
+    /// or [`EncodeError::ApiUsage`] if `lossless` is enabled
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn encode_to_target_bpp<T: PixelType, U: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        width: u32,
+        height: u32,
+        bits_per_pixel: f32,
+    ) -> Result<EncoderResult<U>, EncodeError> {
+        let pixels = f64::from(width) * f64::from(height);
+        let target_size = (pixels * f64::from(bits_per_pixel) / 8.0) as usize;
+        self.encode_to_target_size(frame, width, height, target_size)
+    }
+
+    // Binary search the distance in log scale, keeping the best result which fits
+    fn search_target_size<T: PixelType, U: PixelType>(
+        &mut self,
+        frame: &EncoderFrame<T>,
+        width: u32,
+        height: u32,
+        target_size: usize,
+    ) -> Result<EncoderResult<U>, EncodeError> {
+        const STEPS: usize = 8;
+
+        // Every encode resets the encoder, which drops the boxes
+        #[cfg(feature = "boxes")]
+        let boxes = self.boxes.clone();
+
+        let (mut low, mut high) = (0.1_f32, 25.0_f32);
+        let mut best = None;
+        for _ in 0..STEPS {
+            #[cfg(feature = "boxes")]
+            self.restore_boxes(&boxes)?;
+            self.quality = (low * high).sqrt();
+            let result = self.encode_frame::<T, U>(frame, width, height)?;
+            if result.len() <= target_size {
+                high = self.quality;
+                best = Some(result);
+            } else {
+                low = self.quality;
+            }
+        }
+
+        if let Some(result) = best {
+            Ok(result)
+        } else {
+            #[cfg(feature = "boxes")]
+            self.restore_boxes(&boxes)?;
+            self.quality = 25.0;
+            self.encode_frame(frame, width, height)
+        }
+    }
+}
+
+impl JxlEncoder<'_, '_> {
+    /// Return the effective settings of the encoder
+    #[must_use]
+    pub fn config(&self) -> EncoderConfig {
+        EncoderConfig {
+            has_alpha: self.has_alpha,
+            alpha_premultiplied: self.alpha_premultiplied,
+            lossless: self.lossless,
+            speed: self.speed,
+            quality: self.quality,
+            alpha_quality: self.alpha_quality,
+            use_container: self.use_container,
+            uses_original_profile: self.uses_original_profile,
+            decoding_speed: self.decoding_speed,
+            modular: self.modular,
+            progressive: self.progressive,
+            resampling: self.resampling,
+            extra_channel_resampling: self.extra_channel_resampling,
+            already_downsampled: self.already_downsampled,
+            upsampling_mode: self.upsampling_mode,
+            init_buffer_size: self.init_buffer_size,
+            color_encoding: self.color_encoding,
+            bits_per_sample: self.bits_per_sample,
+            intensity_target: self.intensity_target,
+            min_nits: self.min_nits,
+            relative_to_max_display: self.relative_to_max_display,
+            linear_below: self.linear_below,
+            icc_profile_size: self.icc_profile.as_ref().map(Vec::len),
+            orientation: self.orientation,
+            animation: self.animation,
+            extra_channels: self.extra_channels.clone(),
+            cancellable: self.cancellation.is_some(),
+            custom_parallel_runner: self.parallel_runner.is_some(),
+            parallel_mode: self.parallel_mode,
+            custom_memory_manager: self.memory_manager.is_some(),
+        }
+    }
+}
+
+impl fmt::Debug for JxlEncoder<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JxlEncoder")
+            .field("config", &self.config())
+            .finish_non_exhaustive()
+    }
 }
 
 impl Drop for JxlEncoder<'_, '_> {
@@ -479,16 +1432,119 @@ impl Drop for JxlEncoder<'_, '_> {
     }
 }
 
+/// Check the color space in the ICC profile header against the number of color channels
+fn check_icc_profile(icc_profile: &[u8], num_color_channels: u32) -> Result<(), EncodeError> {
+    let expected = match icc_profile.get(16..20) {
+        Some(b"GRAY") => 1,
+        Some(b"RGB " | b"CMYK") => 3,
+        Some(_) => return Ok(()),
+        None => return Err(EncodeError::BadInput),
+    };
+
+    if expected == num_color_channels {
+        Ok(())
+    } else {
+        Err(EncodeError::IccProfileMismatch)
+    }
+}
+
 /// Return a [`JxlEncoderBuilder`] with default settings
 #[must_use]
 pub fn encoder_builder<'prl, 'mm>() -> JxlEncoderBuilder<'prl, 'mm> {
     JxlEncoderBuilder::default()
 }
 
+/// Encode a frame with default settings, writing the output to `writer` in chunks, e.g. to
+/// [`stdout`](std::io::stdout)
+///
+/// The frame is RGB, or RGBA if it has 4 channels. Use a [`JxlEncoder`] for other
+/// color spaces, see [`JxlEncoder::encode_frame_to_writer`].
+///
+/// # Errors
+/// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+/// be written
+pub fn encode_writer<T: PixelType>(
+    frame: &EncoderFrame<T>,
+    width: u32,
+    height: u32,
+    mut writer: impl Write,
+) -> Result<(), EncodeError> {
+    encoder_builder()
+        .has_alpha(frame.num_channels == Some(4))
+        .build()?
+        .encode_frame_to_writer::<T, T>(frame, width, height, &mut writer)
+}
+
+/// Encode a frame with default settings to a file, running on [`ParallelMode::Auto`]
+///
+/// The frame is RGB, or RGBA if it has 4 channels, like [`encode_writer`].
+///
+/// # Errors
+/// Return [`EncodeError`] if the internal encoder fails to encode or the file fails to
+/// be written
+pub fn encode_file<T: PixelType>(
+    path: impl AsRef<Path>,
+    frame: &EncoderFrame<T>,
+    width: u32,
+    height: u32,
+) -> Result<(), EncodeError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    encoder_builder()
+        .has_alpha(frame.num_channels == Some(4))
+        .parallel_mode(ParallelMode::Auto)
+        .build()?
+        .encode_frame_to_writer::<T, T>(frame, width, height, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Encode an image on the blocking pool of the current [`tokio`] runtime
+///
+/// `configure` sets up the encoder on the blocking thread. Dropping the future cancels the
+/// encoding, see [`JxlEncoder::cancellation`].
+///
+/// # Errors
+/// Return an [`EncodeError`] when internal encoder fails, or [`EncodeError::Cancelled`] if the
+/// runtime is shutting down
+///
+/// # Panics
+/// Panic if not called within a runtime, or if `configure` panics
+#[cfg(feature = "tokio")]
+pub async fn encode_async<T, U, F>(
+    data: Vec<T>,
+    width: u32,
+    height: u32,
+    configure: F,
+) -> Result<EncoderResult<U>, EncodeError>
+where
+    T: PixelType + Send + 'static,
+    U: PixelType + Send + 'static,
+    F: FnOnce(&mut JxlEncoderBuilder<'_, '_>) + Send + 'static,
+{
+    let token = CancellationToken::new();
+    let _guard = token.cancel_on_drop();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut builder = encoder_builder();
+        configure(&mut builder);
+        builder
+            .cancellation(token)
+            .build()?
+            .encode::<T, U>(&data, width, height)
+    })
+    .await;
+
+    match result {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(EncodeError::Cancelled),
+    }
+}
+
 // MARK: Tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "boxes")]
     use testresult::TestResult;
 
     #[test]
@@ -504,6 +1560,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "boxes")]
     fn test_usebox() -> TestResult {
         let mut encoder = encoder_builder().build()?;
         let metadata = Metadata::Exif(&[0, 1, 2, 3]);
@@ -0,0 +1,112 @@
+use std::mem::MaybeUninit;
+
+use jpegxl_sys::{
+    encoder::encode::JxlEncoderInitExtraChannelInfo,
+    metadata::codestream_header::{JxlExtraChannelInfo, JxlExtraChannelType},
+};
+
+/// Type of an extra channel
+pub type ExtraChannelType = JxlExtraChannelType;
+
+/// An extra channel of the image, other than the main alpha channel
+///
+/// The pixels of each extra channel are supplied per frame with
+/// [`EncoderFrame::extra_channel`](super::EncoderFrame::extra_channel),
+/// in the same order as they are added to the encoder.
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ExtraChannel {
+    pub(crate) channel_type: ExtraChannelType,
+    pub(crate) name: Option<String>,
+    pub(crate) spot_color: [f32; 4],
+    pub(crate) cfa_channel: u32,
+    pub(crate) quality: Option<f32>,
+}
+
+impl ExtraChannel {
+    /// Create an unnamed channel of the given type
+    #[must_use]
+    pub fn new(channel_type: ExtraChannelType) -> Self {
+        Self {
+            channel_type,
+            name: None,
+            spot_color: [0.0; 4],
+            cfa_channel: 0,
+            quality: None,
+        }
+    }
+
+    /// Create a depth map channel
+    #[must_use]
+    pub fn depth() -> Self {
+        Self::new(ExtraChannelType::Depth)
+    }
+
+    /// Create a selection mask channel
+    #[must_use]
+    pub fn selection_mask() -> Self {
+        Self::new(ExtraChannelType::SelectionMask)
+    }
+
+    /// Create a black channel, the K channel of CMYK images
+    #[must_use]
+    pub fn black() -> Self {
+        Self::new(ExtraChannelType::Black)
+    }
+
+    /// Create a color filter array channel
+    ///
+    /// `cfa_channel` is the index of the color filter array pattern this channel belongs to.
+    #[must_use]
+    pub fn cfa(cfa_channel: u32) -> Self {
+        Self {
+            cfa_channel,
+            ..Self::new(ExtraChannelType::Cfa)
+        }
+    }
+
+    /// Create a spot color channel
+    ///
+    /// `color` is the linear RGBA tint of the ink, where the alpha component is its solidity.
+    /// The channel samples are the amount of ink at each pixel.
+    pub fn spot_color(name: impl Into<String>, color: [f32; 4]) -> Self {
+        Self {
+            spot_color: color,
+            ..Self::new(ExtraChannelType::SpotColor)
+        }
+        .name(name)
+    }
+
+    /// Set the name of the channel
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set quality for lossy compression of this channel, same as [`JxlEncoder::quality`]
+    ///
+    /// Default: the quality of the main image
+    ///
+    /// [`JxlEncoder::quality`]: super::JxlEncoder::quality
+    #[must_use]
+    pub fn quality(mut self, quality: f32) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    pub(crate) fn info(&self, (bits, exp): (u32, u32)) -> JxlExtraChannelInfo {
+        let mut info = unsafe {
+            let mut info = MaybeUninit::uninit();
+            JxlEncoderInitExtraChannelInfo(self.channel_type, info.as_mut_ptr());
+            info.assume_init()
+        };
+
+        info.bits_per_sample = bits;
+        info.exponent_bits_per_sample = exp;
+        info.spot_color = self.spot_color;
+        info.cfa_channel = self.cfa_channel;
+
+        info
+    }
+}
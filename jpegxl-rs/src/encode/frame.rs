@@ -1,16 +1,30 @@
-use std::marker::PhantomData;
+use std::{borrow::Cow, io::Write, marker::PhantomData, mem::MaybeUninit};
 
-use jpegxl_sys::common::types::{JxlEndianness, JxlPixelFormat};
+use jpegxl_sys::{
+    common::types::{JxlEndianness, JxlPixelFormat},
+    encoder::encode::JxlEncoderInitFrameHeader,
+    metadata::codestream_header::{JxlBlendInfo, JxlBlendMode, JxlFrameHeader},
+};
 
 use crate::{common::PixelType, EncodeError};
 
 use super::{EncoderResult, JxlEncoder};
 
+/// Blend mode of a frame onto the frames before it
+pub type BlendMode = JxlBlendMode;
+
 /// A frame for the encoder, consisting of the pixels and its options
 #[allow(clippy::module_name_repetitions)]
 pub struct EncoderFrame<'data, T: PixelType> {
-    pub(crate) data: &'data [T],
-    num_channels: Option<u32>,
+    pub(crate) data: Cow<'data, [T]>,
+    pub(crate) extra_channels: Vec<Cow<'data, [T]>>,
+    pub(crate) name: Option<String>,
+    crop: Option<(i32, i32, u32, u32)>,
+    blend_mode: Option<BlendMode>,
+    blend_source: u32,
+    save_as_reference: u32,
+    duration: u32,
+    pub(crate) num_channels: Option<u32>,
     endianness: Option<JxlEndianness>,
     align: Option<usize>,
 }
@@ -19,15 +33,91 @@ impl<'data, T: PixelType> EncoderFrame<'data, T> {
     /// Create a default frame from the data.
     ///
     /// Use RGB(3) channels, native endianness and no alignment.
+    ///
+    /// Float samples (`f32` and [`half::f16`]) are nominally in the range of 0.0 to 1.0,
+    /// and may exceed it for HDR or wide gamut content.
     pub fn new(data: &'data [T]) -> Self {
         Self {
-            data,
+            data: Cow::Borrowed(data),
+            extra_channels: Vec::new(),
+            name: None,
+            crop: None,
+            blend_mode: None,
+            blend_source: 0,
+            save_as_reference: 0,
+            duration: 0,
             num_channels: None,
             endianness: None,
             align: None,
         }
     }
 
+    /// Create a frame from separate channel planes, e.g. `&[r, g, b]` or `&[r, g, b, a]`.
+    ///
+    /// The planes are interleaved into a new buffer, and the number of channels
+    /// is set to the number of planes.
+    ///
+    /// # Panics
+    /// Panics if the planes have different lengths
+    #[must_use]
+    pub fn from_planes(planes: &[&[T]]) -> Self {
+        let len = planes.first().map_or(0, |p| p.len());
+        assert!(
+            planes.iter().all(|p| p.len() == len),
+            "All planes must have the same length"
+        );
+
+        let mut data = Vec::with_capacity(len * planes.len());
+        for i in 0..len {
+            data.extend(planes.iter().map(|p| p[i]));
+        }
+
+        Self {
+            data: Cow::Owned(data),
+            extra_channels: Vec::new(),
+            name: None,
+            crop: None,
+            blend_mode: None,
+            blend_source: 0,
+            save_as_reference: 0,
+            duration: 0,
+            num_channels: u32::try_from(planes.len()).ok(),
+            endianness: None,
+            align: None,
+        }
+    }
+
+    /// Create a frame from interleaved CMYK data in native endianness, where 0 is no ink and
+    /// the maximum value is full ink, as in most applications.
+    ///
+    /// JPEG XL stores CMYK with 0 as full ink, so every sample is inverted. The CMY channels
+    /// are used as the color channels, and K is split into the black extra channel set up by
+    /// [`JxlEncoderBuilder::cmyk`](super::JxlEncoderBuilder::cmyk).
+    #[must_use]
+    pub fn from_cmyk(data: &[T]) -> Self {
+        let invert = |v: T| T::from_normalized(1.0 - v.to_normalized());
+        let mut cmy = Vec::with_capacity(data.len() / 4 * 3);
+        let mut k = Vec::with_capacity(data.len() / 4);
+        for pixel in data.chunks_exact(4) {
+            cmy.extend(pixel[..3].iter().map(|&v| invert(v)));
+            k.push(invert(pixel[3]));
+        }
+
+        Self {
+            data: Cow::Owned(cmy),
+            extra_channels: vec![Cow::Owned(k)],
+            name: None,
+            crop: None,
+            blend_mode: None,
+            blend_source: 0,
+            save_as_reference: 0,
+            duration: 0,
+            num_channels: Some(3),
+            endianness: None,
+            align: None,
+        }
+    }
+
     /// Set the number of channels of the source.
     ///
     /// _Note_: If you want to use alpha channel, add here
@@ -52,6 +142,110 @@ impl<'data, T: PixelType> EncoderFrame<'data, T> {
         self
     }
 
+    /// Add the pixels of the next extra channel, with one sample per pixel.
+    ///
+    /// Must be called once for each of [`JxlEncoder::extra_channels`], in the same order.
+    #[must_use]
+    pub fn extra_channel(mut self, data: &'data [T]) -> Self {
+        self.extra_channels.push(Cow::Borrowed(data));
+        self
+    }
+
+    /// Set the name of the frame, e.g. the layer name from an image editor.
+    ///
+    /// The name must be shorter than 1072 bytes when encoded as UTF-8.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Place the frame as a layer of `width` x `height` pixels at offset (`x0`, `y0`)
+    /// of the image, instead of covering the full canvas.
+    ///
+    /// The offsets may be negative, or place the layer partly outside of the image.
+    #[must_use]
+    pub fn crop(mut self, x0: i32, y0: i32, width: u32, height: u32) -> Self {
+        self.crop = Some((x0, y0, width, height));
+        self
+    }
+
+    /// Set how the frame is blended onto the frames before it.
+    ///
+    /// [`BlendMode::Blend`] and [`BlendMode::MULADD`] use the alpha channel.
+    ///
+    /// Default: [`BlendMode::Replace`]
+    #[must_use]
+    pub fn blend_mode(mut self, mode: BlendMode) -> Self {
+        self.blend_mode = Some(mode);
+        self
+    }
+
+    /// Set the reference slot (0-3) to blend onto, see [`Self::save_as_reference`].
+    ///
+    /// Default: 0
+    #[must_use]
+    pub fn blend_source(mut self, source: u32) -> Self {
+        self.blend_source = source;
+        self
+    }
+
+    /// Save the frame into a reference slot (0-3), for later frames to blend onto.
+    ///
+    /// Together with [`Self::crop`] and [`Self::blend_source`], this allows animations to
+    /// only encode the regions that change between frames.
+    ///
+    /// Default: 0
+    #[must_use]
+    pub fn save_as_reference(mut self, slot: u32) -> Self {
+        self.save_as_reference = slot;
+        self
+    }
+
+    /// Set the duration of the frame in ticks of [`JxlEncoder::animation`].
+    ///
+    /// A zero-duration frame is not shown on its own, but merged with the next frame,
+    /// e.g. to only fill a reference slot.
+    ///
+    /// Default: 0
+    #[must_use]
+    pub fn duration(mut self, ticks: u32) -> Self {
+        self.duration = ticks;
+        self
+    }
+
+    pub(crate) fn blend_info(&self) -> JxlBlendInfo {
+        JxlBlendInfo {
+            blendmode: self.blend_mode.unwrap_or(BlendMode::Replace),
+            source: self.blend_source,
+            alpha: 0,
+            clamp: false.into(),
+        }
+    }
+
+    pub(crate) fn header(&self) -> JxlFrameHeader {
+        let mut header = unsafe {
+            let mut header = MaybeUninit::uninit();
+            JxlEncoderInitFrameHeader(header.as_mut_ptr());
+            header.assume_init()
+        };
+
+        header.duration = self.duration;
+
+        let layer = &mut header.layer_info;
+        if let Some((x0, y0, width, height)) = self.crop {
+            layer.have_crop = true.into();
+            layer.crop_x0 = x0;
+            layer.crop_y0 = y0;
+            layer.xsize = width;
+            layer.ysize = height;
+        }
+        layer.blend_info = self.blend_info();
+        layer.save_as_reference = self.save_as_reference;
+
+        header
+    }
+
     pub(crate) fn pixel_format(&self) -> JxlPixelFormat {
         JxlPixelFormat {
             num_channels: self.num_channels.unwrap_or(3),
@@ -60,6 +254,13 @@ impl<'data, T: PixelType> EncoderFrame<'data, T> {
             align: self.align.unwrap_or(0),
         }
     }
+
+    pub(crate) fn extra_channel_format(&self) -> JxlPixelFormat {
+        JxlPixelFormat {
+            num_channels: 1,
+            ..self.pixel_format()
+        }
+    }
 }
 
 /// A wrapper type for encoding multiple frames
@@ -83,15 +284,46 @@ impl<U: PixelType> MultiFrames<'_, '_, '_, U> {
     /// Add a JPEG raw frame to the encoder
     /// # Errors
     /// Return [`EncodeError`] if the internal encoder fails to add a jpeg frame
+    #[cfg(feature = "jpeg")]
     pub fn add_jpeg_frame(self, data: &[u8]) -> Result<Self, EncodeError> {
         self.0.add_jpeg_frame(data)?;
         Ok(self)
     }
 
+    /// Push a frame to the encoder, for feeding frames incrementally as they are produced
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to add a frame
+    pub fn push_frame<T: PixelType>(&mut self, frame: &EncoderFrame<T>) -> Result<(), EncodeError> {
+        self.0.add_frame(frame)
+    }
+
+    /// Push a JPEG raw frame to the encoder, for feeding frames incrementally
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to add a jpeg frame
+    #[cfg(feature = "jpeg")]
+    pub fn push_jpeg_frame(&mut self, data: &[u8]) -> Result<(), EncodeError> {
+        self.0.add_jpeg_frame(data)
+    }
+
+    /// Close the input and encode a JPEG XL image from the pushed frames
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode
+    pub fn finish(self) -> Result<EncoderResult<U>, EncodeError> {
+        self.encode()
+    }
+
     /// Encode a JPEG XL image from the frames
     /// # Errors
     /// Return [`EncodeError`] if the internal encoder fails to encode
     pub fn encode(self) -> Result<EncoderResult<U>, EncodeError> {
         self.0.start_encoding()
     }
+
+    /// Encode a JPEG XL image from the frames, writing the output to `writer`
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode or the output fails to
+    /// be written
+    pub fn encode_to_writer(self, writer: &mut impl Write) -> Result<(), EncodeError> {
+        self.0.encode_to_writer_internal(writer)
+    }
 }
@@ -1,5 +1,7 @@
 use jpegxl_sys::common::types::JxlBoxType;
 
+use crate::EncodeError;
+
 /// Metadata box
 pub enum Metadata<'d> {
     /// EXIF
@@ -22,3 +24,63 @@ impl Metadata<'_> {
         JxlBoxType(unsafe { std::mem::transmute::<[u8; 4], [std::ffi::c_char; 4]>(t) })
     }
 }
+
+/// Type of a container box, 4 printable ASCII characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoxType([u8; 4]);
+
+impl BoxType {
+    /// EXIF box
+    pub const EXIF: Self = Self(*b"Exif");
+    /// XMP/IPTC metadata box
+    pub const XMP: Self = Self(*b"xml ");
+    /// JUMBF superbox, e.g. for C2PA manifests
+    pub const JUMBF: Self = Self(*b"jumb");
+
+    /// Box types reserved for the codestream and container structure
+    const RESERVED: [&'static [u8; 4]; 4] = [b"ftyp", b"jbrd", b"brob", b"jhgm"];
+
+    /// Create a box type
+    ///
+    /// # Errors
+    /// Return [`EncodeError::InvalidBoxType`] if the type is not printable ASCII, starts with
+    /// `jxl`/`JXL`, or is reserved by the container format
+    pub fn new(t: [u8; 4]) -> Result<Self, EncodeError> {
+        if !t.iter().all(|c| c.is_ascii_graphic() || *c == b' ')
+            || t.starts_with(b"jxl")
+            || t.starts_with(b"JXL")
+            || Self::RESERVED.contains(&&t)
+        {
+            return Err(EncodeError::InvalidBoxType(t));
+        }
+        Ok(Self(t))
+    }
+
+    /// Return the 4 characters of the box type
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 4] {
+        &self.0
+    }
+}
+
+impl TryFrom<&[u8; 4]> for BoxType {
+    type Error = EncodeError;
+
+    fn try_from(t: &[u8; 4]) -> Result<Self, Self::Error> {
+        Self::new(*t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_type() {
+        assert!(BoxType::new(*b"c2pa").is_ok());
+        assert_eq!(BoxType::try_from(b"Exif").ok(), Some(BoxType::EXIF));
+        assert!(BoxType::new(*b"jxlc").is_err());
+        assert!(BoxType::new(*b"brob").is_err());
+        assert!(BoxType::new([0, b'a', b'b', b'c']).is_err());
+    }
+}
@@ -1,9 +1,17 @@
 use std::mem::MaybeUninit;
 
-use jpegxl_sys::{color::color_encoding::JxlColorEncoding, encoder::encode as api};
+use jpegxl_sys::{
+    color::color_encoding::{
+        JxlColorEncoding, JxlPrimaries, JxlRenderingIntent, JxlTransferFunction,
+    },
+    encoder::encode as api,
+};
+
+/// Setting of the encoder frames, see [`JxlEncoder::set_frame_option`](super::JxlEncoder::set_frame_option)
+pub type FrameSetting = api::JxlEncoderFrameSettingId;
 
 /// Encoding speed
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum EncoderSpeed {
     /// Fastest, 1
     Lightning = 1,
@@ -18,6 +26,7 @@ pub enum EncoderSpeed {
     /// 6
     Wombat,
     /// 7, default
+    #[default]
     Squirrel,
     /// 8
     Kitten,
@@ -27,12 +36,73 @@ pub enum EncoderSpeed {
     Glacier,
 }
 
-impl std::default::Default for EncoderSpeed {
-    fn default() -> Self {
-        Self::Squirrel
+/// A bundle of settings for a typical use case, applied with
+/// [`JxlEncoderBuilder::preset`](super::JxlEncoderBuilder::preset)
+#[derive(Debug, Clone, Copy)]
+pub struct EncoderPreset {
+    /// Encoding speed
+    pub speed: EncoderSpeed,
+    /// Quality for lossy compression, see [`JxlEncoder::quality`](super::JxlEncoder::quality)
+    pub quality: f32,
+    /// Mathematically lossless, keeping the original color profile
+    pub lossless: bool,
+    /// Force the modular mode (`true`) or `VarDCT` mode (`false`)
+    pub modular: Option<bool>,
+    /// Progressive decoding
+    pub progressive: bool,
+}
+
+impl EncoderPreset {
+    /// Photographs for the web: visually lossless, progressive `VarDCT`
+    #[must_use]
+    pub const fn photo_web() -> Self {
+        Self {
+            speed: EncoderSpeed::Squirrel,
+            quality: 1.0,
+            lossless: false,
+            modular: Some(false),
+            progressive: true,
+        }
+    }
+
+    /// Long-term archival: lossless at a high effort
+    #[must_use]
+    pub const fn lossless_archive() -> Self {
+        Self {
+            speed: EncoderSpeed::Tortoise,
+            quality: 0.0,
+            lossless: true,
+            modular: Some(true),
+            progressive: false,
+        }
+    }
+
+    /// Screenshots and other synthetic images with sharp edges and flat areas: lossless
+    #[must_use]
+    pub const fn screenshot() -> Self {
+        Self {
+            speed: EncoderSpeed::Squirrel,
+            quality: 0.0,
+            lossless: true,
+            modular: Some(true),
+            progressive: false,
+        }
     }
 }
 
+/// Upsampling done by the decoder for images encoded with
+/// [`JxlEncoder::resampling`](super::JxlEncoder::resampling)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpsamplingMode {
+    /// Smooth upsampling, good for photographic images
+    #[default]
+    Smooth = -1,
+    /// Nearest neighbor, good for pixel art
+    NearestNeighbor = 0,
+    /// Same as nearest neighbor for 2x, diamond-shaped "pixel dots" for 4x and 8x
+    PixelDots = 1,
+}
+
 /// Encoding color profile
 #[derive(Debug, Clone, Copy)]
 pub enum ColorEncoding {
@@ -44,26 +114,90 @@ pub enum ColorEncoding {
     SrgbLuma,
     /// Linear SRGB with only luma channel
     LinearSrgbLuma,
+    /// Display P3 primaries with SRGB transfer function
+    DisplayP3,
+    /// Rec. 2100 primaries with PQ transfer function, for HDR images
+    Rec2020Pq,
+    /// Gray with gamma 2.2 transfer function, images with only luma channel
+    GrayGamma22,
+}
+
+impl ColorEncoding {
+    /// SRGB preset
+    #[must_use]
+    pub const fn srgb() -> Self {
+        Self::Srgb
+    }
+
+    /// Linear SRGB preset
+    #[must_use]
+    pub const fn linear_srgb() -> Self {
+        Self::LinearSrgb
+    }
+
+    /// Display P3 preset
+    #[must_use]
+    pub const fn display_p3() -> Self {
+        Self::DisplayP3
+    }
+
+    /// Rec. 2020 / 2100 PQ preset
+    #[must_use]
+    pub const fn rec2020_pq() -> Self {
+        Self::Rec2020Pq
+    }
+
+    /// Gray gamma 2.2 preset
+    #[must_use]
+    pub const fn gray_gamma22() -> Self {
+        Self::GrayGamma22
+    }
+
+    /// Whether the color encoding only has a luma channel
+    #[must_use]
+    pub const fn is_gray(self) -> bool {
+        matches!(
+            self,
+            Self::SrgbLuma | Self::LinearSrgbLuma | Self::GrayGamma22
+        )
+    }
 }
 
 impl From<ColorEncoding> for JxlColorEncoding {
     fn from(val: ColorEncoding) -> Self {
-        use ColorEncoding::{LinearSrgb, LinearSrgbLuma, Srgb, SrgbLuma};
+        use ColorEncoding::{
+            DisplayP3, GrayGamma22, LinearSrgb, LinearSrgbLuma, Rec2020Pq, Srgb, SrgbLuma,
+        };
 
         let mut color_encoding = MaybeUninit::uninit();
 
-        unsafe {
+        let mut color_encoding = unsafe {
             match val {
-                Srgb => api::JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), false),
-                LinearSrgb => {
-                    api::JxlColorEncodingSetToLinearSRGB(color_encoding.as_mut_ptr(), false);
-                }
-                SrgbLuma => api::JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), true),
-                LinearSrgbLuma => {
-                    api::JxlColorEncodingSetToLinearSRGB(color_encoding.as_mut_ptr(), true);
+                LinearSrgb | LinearSrgbLuma => {
+                    api::JxlColorEncodingSetToLinearSRGB(
+                        color_encoding.as_mut_ptr(),
+                        val.is_gray(),
+                    );
                 }
+                _ => api::JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), val.is_gray()),
             }
             color_encoding.assume_init()
+        };
+
+        match val {
+            Srgb | LinearSrgb | SrgbLuma | LinearSrgbLuma => {}
+            DisplayP3 => color_encoding.primaries = JxlPrimaries::P3,
+            Rec2020Pq => {
+                color_encoding.primaries = JxlPrimaries::Rec2100;
+                color_encoding.transfer_function = JxlTransferFunction::PQ;
+                color_encoding.rendering_intent = JxlRenderingIntent::Relative;
+            }
+            GrayGamma22 => {
+                color_encoding.transfer_function = JxlTransferFunction::Gamma;
+                color_encoding.gamma = 1.0 / 2.2;
+            }
         }
+
+        color_encoding
     }
 }
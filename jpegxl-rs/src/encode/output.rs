@@ -0,0 +1,116 @@
+use std::{
+    any::Any,
+    ffi::c_void,
+    io::{Seek, SeekFrom, Write},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::null_mut,
+};
+
+use jpegxl_sys::encoder::encode::JxlEncoderOutputProcessor;
+
+use crate::cancel::CancellationToken;
+
+/// State of an output processor writing into a seekable writer
+pub(crate) struct OutputProcessor<'w, W: Write + Seek> {
+    writer: &'w mut W,
+    start: u64,
+    position: u64,
+    end: u64,
+    buffer: Vec<u8>,
+    cancellation: Option<CancellationToken>,
+    pub(crate) cancelled: bool,
+    pub(crate) error: Option<std::io::Error>,
+    /// Panic raised by the writer, resumed once the encoder returns
+    pub(crate) panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<'w, W: Write + Seek> OutputProcessor<'w, W> {
+    pub(crate) fn new(
+        writer: &'w mut W,
+        buffer_size: usize,
+        cancellation: Option<CancellationToken>,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            start: writer.stream_position()?,
+            position: 0,
+            end: 0,
+            writer,
+            buffer: vec![0; buffer_size],
+            cancellation,
+            cancelled: false,
+            error: None,
+            panic: None,
+        })
+    }
+
+    /// Move the writer to the end of the output and flush it
+    pub(crate) fn finish(self) -> std::io::Result<()> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        self.writer.seek(SeekFrom::Start(self.start + self.end))?;
+        self.writer.flush()
+    }
+
+    /// Return the callbacks for the encoder, which must not outlive `self`
+    pub(crate) fn as_raw(&mut self) -> JxlEncoderOutputProcessor {
+        JxlEncoderOutputProcessor {
+            opaque: (self as *mut Self).cast(),
+            get_buffer: Self::get_buffer,
+            release_buffer: Self::release_buffer,
+            seek: Some(Self::seek),
+            set_finalized_position: Self::set_finalized_position,
+        }
+    }
+
+    extern "C-unwind" fn get_buffer(opaque: *mut c_void, size: *mut usize) -> *mut c_void {
+        let this = unsafe { &mut *opaque.cast::<Self>() };
+        let size = unsafe { &mut *size };
+
+        this.cancelled |= this
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled);
+        if this.error.is_some() || this.panic.is_some() || this.cancelled {
+            // Ask the encoder to stop
+            *size = 0;
+            return null_mut();
+        }
+
+        if this.buffer.len() < *size {
+            this.buffer.resize(*size, 0);
+        }
+        *size = this.buffer.len();
+        this.buffer.as_mut_ptr().cast()
+    }
+
+    extern "C-unwind" fn release_buffer(opaque: *mut c_void, written_bytes: usize) {
+        let this = unsafe { &mut *opaque.cast::<Self>() };
+
+        match catch_unwind(AssertUnwindSafe(|| {
+            this.writer.write_all(&this.buffer[..written_bytes])
+        })) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => _ = this.error.get_or_insert(e),
+            Err(payload) => _ = this.panic.get_or_insert(payload),
+        }
+        this.position += written_bytes as u64;
+        this.end = this.end.max(this.position);
+    }
+
+    extern "C-unwind" fn seek(opaque: *mut c_void, position: u64) {
+        let this = unsafe { &mut *opaque.cast::<Self>() };
+
+        let start = this.start;
+        match catch_unwind(AssertUnwindSafe(|| {
+            this.writer.seek(SeekFrom::Start(start + position))
+        })) {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => _ = this.error.get_or_insert(e),
+            Err(payload) => _ = this.panic.get_or_insert(payload),
+        }
+        this.position = position;
+    }
+
+    extern "C-unwind" fn set_finalized_position(_opaque: *mut c_void, _finalized_position: u64) {}
+}
@@ -0,0 +1,100 @@
+use std::{fmt, ptr::NonNull, rc::Rc};
+
+use jpegxl_sys::encoder::stats as api;
+
+/// Statistic to query from [`EncoderStats`], such as the bits spent on AC coefficients
+pub type StatsKey = api::JxlEncoderStatsKey;
+
+struct StatsHandle(NonNull<api::JxlEncoderStats>);
+
+impl Drop for StatsHandle {
+    fn drop(&mut self) {
+        unsafe { api::JxlEncoderStatsDestroy(self.0.as_ptr()) };
+    }
+}
+
+/// Statistics gathered by the encoder, e.g. to see where the bits of an image are spent
+///
+/// Pass it to [`JxlEncoder::collect_stats`](super::JxlEncoder::collect_stats), the values
+/// accumulate over all following encodes. Clones share the same statistics.
+#[derive(Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct EncoderStats {
+    handle: Rc<StatsHandle>,
+}
+
+impl EncoderStats {
+    /// Create an empty set of statistics
+    ///
+    /// # Panics
+    /// Panics if libjxl fails to allocate the statistics
+    #[must_use]
+    pub fn new() -> Self {
+        let ptr = NonNull::new(unsafe { api::JxlEncoderStatsCreate() })
+            .expect("failed to allocate encoder statistics");
+        Self {
+            handle: Rc::new(StatsHandle(ptr)),
+        }
+    }
+
+    /// Return the value of a statistic
+    #[must_use]
+    pub fn get(&self, key: StatsKey) -> usize {
+        unsafe { api::JxlEncoderStatsGet(self.as_ptr(), key) }
+    }
+
+    /// Add the values of `other` to these statistics
+    pub fn merge(&self, other: &Self) {
+        unsafe { api::JxlEncoderStatsMerge(self.as_ptr(), other.as_ptr()) };
+    }
+
+    pub(crate) fn as_ptr(&self) -> *mut api::JxlEncoderStats {
+        self.handle.0.as_ptr()
+    }
+}
+
+impl Default for EncoderStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EncoderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncoderStats")
+            .field("header_bits", &self.get(StatsKey::HeaderBits))
+            .field("ac_bits", &self.get(StatsKey::AcBits))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{encoder_builder, tests::SAMPLE_PNG};
+
+    #[test]
+    fn collect() -> TestResult {
+        let sample =
+            image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png)?.to_rgb8();
+        let stats = EncoderStats::new();
+        let mut encoder = encoder_builder().build()?;
+        encoder.collect_stats(&stats);
+
+        encoder.encode::<u8, u8>(sample.as_raw(), sample.width(), sample.height())?;
+        let ac_bits = stats.get(StatsKey::AcBits);
+        assert!(ac_bits > 0);
+        assert!(stats.get(StatsKey::HeaderBits) > 0);
+
+        // Statistics accumulate over encodes
+        encoder.encode::<u8, u8>(sample.as_raw(), sample.width(), sample.height())?;
+        assert!(stats.get(StatsKey::AcBits) > ac_bits);
+
+        let total = EncoderStats::new();
+        total.merge(&stats);
+        assert_eq!(total.get(StatsKey::AcBits), stats.get(StatsKey::AcBits));
+        Ok(())
+    }
+}
@@ -19,10 +19,33 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 
 use thiserror::Error;
 
-use jpegxl_sys::{decode::JxlDecoderStatus, encoder::encode::JxlEncoderError};
+#[cfg(feature = "decoder")]
+use jpegxl_sys::decode::JxlDecoderStatus;
+use jpegxl_sys::encoder::encode::JxlEncoderError;
 
-/// Errors derived from [`JxlDecoderStatus`]
+/// Category of an error, to handle errors without matching every variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input is invalid or corrupted
+    InvalidInput,
+    /// The input or the requested output is not supported
+    Unsupported,
+    /// Memory allocation failed, including creating a decoder or an encoder
+    OutOfMemory,
+    /// Reading the input or writing the output failed
+    Io,
+    /// The operation was cancelled
+    Cancelled,
+    /// The `libjxl` API is used in an incorrect way
+    InvalidUsage,
+    /// Generic or unknown errors reported by `libjxl`
+    Other,
+}
+
+/// Errors derived from [`JxlDecoderStatus`](jpegxl_sys::decode::JxlDecoderStatus)
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum DecodeError {
     /// Cannot create a decoder
     #[error("Cannot create a decoder")]
@@ -42,14 +65,56 @@ pub enum DecodeError {
     /// Internal error, usually invalid usages of the `libjxl` library
     #[error("Internal error, please file an issus: {0}")]
     InternalError(&'static str),
-    /// Unknown status
-    #[error("Unknown status: `{0:?}`")]
-    UnknownStatus(JxlDecoderStatus),
+    /// The decoder builder has an invalid setting
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    /// The decoded pixels cannot be represented in the requested form
+    #[error("Unsupported pixel format: {0} channels")]
+    UnsupportedPixelFormat(u32),
+    /// Failed to read the input
+    #[error("Failed to read the input: {0}")]
+    Io(#[from] std::io::Error),
+    /// Decoding was cancelled with a [`CancellationToken`](crate::encode::CancellationToken)
+    #[error("Decoding was cancelled")]
+    Cancelled,
+    /// The ICC profile is missing or cannot be used for color conversion
+    #[error("Cannot convert the color profile")]
+    ColorConversion,
+    /// A buffer size overflows `usize` on this platform
+    #[error("Buffer size overflows the address space")]
+    SizeOverflow,
+    /// The image exceeds a [`DecodeLimits`](crate::decode::DecodeLimits) setting
+    #[error("The image exceeds the limit on the {0}")]
+    LimitExceeded(&'static str),
+    /// Unknown status, with the raw value of
+    /// [`JxlDecoderStatus`](jpegxl_sys::decode::JxlDecoderStatus)
+    #[error("Unknown status: `{0}`")]
+    UnknownStatus(i32),
+}
+
+impl DecodeError {
+    /// Return the category of the error
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::CannotCreateDecoder => ErrorKind::OutOfMemory,
+            Self::InvalidInput | Self::LimitExceeded(_) => ErrorKind::InvalidInput,
+            Self::UnsupportedBitWidth(_)
+            | Self::UnsupportedPixelFormat(_)
+            | Self::ColorConversion
+            | Self::SizeOverflow => ErrorKind::Unsupported,
+            Self::InternalError(_) | Self::InvalidConfiguration(_) => ErrorKind::InvalidUsage,
+            Self::Io(_) => ErrorKind::Io,
+            Self::Cancelled => ErrorKind::Cancelled,
+            Self::GenericError | Self::UnknownStatus(_) => ErrorKind::Other,
+        }
+    }
 }
 
 /// Errors derived from [`JxlEncoderStatus`][jpegxl_sys::encoder::encode::JxlEncoderStatus]
 /// and [`JxlEncoderError`]
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum EncodeError {
     /// Cannot create an encoder
     #[error("Cannot create an encoder")]
@@ -79,16 +144,154 @@ pub enum EncodeError {
     /// a debug build of libjxl should output a specific error message
     #[error("The encoder API is used in an incorrect way")]
     ApiUsage,
-    /// Unknown status
-    #[error("Unknown status: `{0:?}`")]
-    UnknownStatus(JxlEncoderError),
+    /// The encoder builder has an invalid setting
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    /// Failed to write the output
+    #[error("Failed to write the output: {0}")]
+    Io(#[from] std::io::Error),
+    /// The box type is invalid or reserved
+    #[error("Invalid box type: `{0:?}`")]
+    InvalidBoxType([u8; 4]),
+    /// The color space of the ICC profile does not match the number of color channels
+    #[error("ICC profile color space does not match the number of color channels")]
+    IccProfileMismatch,
+    /// Encoding was cancelled with a [`CancellationToken`](crate::encode::CancellationToken)
+    #[error("Encoding was cancelled")]
+    Cancelled,
+    /// Unknown status, with the raw value of [`JxlEncoderError`]
+    #[error("Unknown status: `{0}`")]
+    UnknownStatus(i32),
+}
+
+impl EncodeError {
+    /// Return the category of the error
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::CannotCreateEncoder | Self::OutOfMemory => ErrorKind::OutOfMemory,
+            Self::BadInput | Self::InvalidBoxType(_) | Self::IccProfileMismatch => {
+                ErrorKind::InvalidInput
+            }
+            Self::NotSupported | Self::Jbrd => ErrorKind::Unsupported,
+            Self::ApiUsage | Self::NeedMoreOutput | Self::InvalidConfiguration(_) => {
+                ErrorKind::InvalidUsage
+            }
+            Self::Io(_) => ErrorKind::Io,
+            Self::Cancelled => ErrorKind::Cancelled,
+            Self::GenericError | Self::UnknownStatus(_) => ErrorKind::Other,
+        }
+    }
+}
+
+/// Errors from [`transcode`](crate::transcode)
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum TranscodeError {
+    /// Failed to decode the JPEG XL image
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    /// Failed to encode the JPEG image
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+    /// The JPEG XL image does not contain JPEG bitstream reconstruction data
+    #[error("No JPEG reconstruction data")]
+    NoReconstructionData,
+    /// The reconstructed JPEG does not match the original bit-exactly
+    #[error("The reconstructed JPEG does not match the original")]
+    VerificationFailed,
+}
+
+impl TranscodeError {
+    /// Return the category of the error
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Decode(e) => e.kind(),
+            Self::Encode(e) => e.kind(),
+            Self::NoReconstructionData => ErrorKind::Unsupported,
+            Self::VerificationFailed => ErrorKind::Other,
+        }
+    }
+}
+
+/// Errors from [`conformance`](crate::conformance)
+#[cfg(feature = "conformance")]
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ConformanceError {
+    /// Failed to decode the input
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    /// Failed to read a file of the suite
+    #[error("Failed to read the test case: {0}")]
+    Io(#[from] std::io::Error),
+    /// `test.json` or the reference image cannot be parsed
+    #[error("Invalid test case: {0}")]
+    InvalidTestCase(String),
+    /// A file does not match its checksum in `test.json`
+    #[error("Checksum mismatch of `{0}`")]
+    ChecksumMismatch(String),
+    /// The number of decoded frames does not match the reference
+    #[error("Expected {expected} frames, decoded {actual}")]
+    FrameCount {
+        /// Number of frames of the reference
+        expected: usize,
+        /// Number of decoded frames
+        actual: usize,
+    },
+    /// A decoded frame does not match the dimensions of the reference
+    #[error("Frame {0} does not match the dimensions of the reference")]
+    DimensionMismatch(usize),
+    /// A decoded frame exceeds the tolerance of the test case
+    #[error("Frame {index} exceeds the tolerance: {report:?}")]
+    Mismatch {
+        /// Index of the frame
+        index: usize,
+        /// Errors of the frame
+        report: crate::conformance::FrameReport,
+    },
+}
+
+#[cfg(feature = "conformance")]
+impl ConformanceError {
+    /// Return the category of the error
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Decode(e) => e.kind(),
+            Self::Io(_) => ErrorKind::Io,
+            Self::InvalidTestCase(_) | Self::ChecksumMismatch(_) => ErrorKind::InvalidInput,
+            Self::FrameCount { .. } | Self::DimensionMismatch(_) | Self::Mismatch { .. } => {
+                ErrorKind::Other
+            }
+        }
+    }
+}
+
+impl From<JxlEncoderError> for EncodeError {
+    /// Map the error reported by `JxlEncoderGetError`
+    fn from(error: JxlEncoderError) -> Self {
+        match error {
+            JxlEncoderError::Generic => Self::GenericError,
+            JxlEncoderError::OutOfMemory => Self::OutOfMemory,
+            JxlEncoderError::Jbrd => Self::Jbrd,
+            JxlEncoderError::BadInput => Self::BadInput,
+            JxlEncoderError::NotSupported => Self::NotSupported,
+            JxlEncoderError::ApiUsage => Self::ApiUsage,
+            // A failed call without an error code should not happen, and newer error codes are not
+            // known yet
+            _ => Self::UnknownStatus(error as i32),
+        }
+    }
 }
 
 /// Error mapping from underlying C const to [`DecodeError`] enum
-pub(crate) fn check_dec_status(status: JxlDecoderStatus) -> Result<(), DecodeError> {
-    match status {
-        JxlDecoderStatus::Success => Ok(()),
-        JxlDecoderStatus::Error => Err(DecodeError::GenericError),
+#[cfg(feature = "decoder")]
+pub(crate) fn check_dec_status(status: i32) -> Result<(), DecodeError> {
+    match JxlDecoderStatus::try_from(status) {
+        Ok(JxlDecoderStatus::Success) => Ok(()),
+        Ok(JxlDecoderStatus::Error) => Err(DecodeError::GenericError),
         _ => Err(DecodeError::UnknownStatus(status)),
     }
 }
@@ -100,6 +303,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "decoder")]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn decode_invalid_data() -> TestResult {
         let decoder = crate::decoder_builder().build()?;
@@ -117,19 +321,31 @@ mod tests {
         ));
 
         assert!(matches!(
-            check_dec_status(JxlDecoderStatus::Error),
+            check_dec_status(JxlDecoderStatus::Error as i32),
             Err(DecodeError::GenericError)
         ));
+        assert!(matches!(
+            check_dec_status(JxlDecoderStatus::BasicInfo as i32),
+            Err(DecodeError::UnknownStatus(0x40))
+        ));
+        // A status unknown to the bindings, e.g. from a newer libjxl
+        assert!(matches!(
+            check_dec_status(0x20000),
+            Err(DecodeError::UnknownStatus(0x20000))
+        ));
+        assert_eq!(DecodeError::InvalidInput.kind(), ErrorKind::InvalidInput);
+        assert_eq!(DecodeError::UnknownStatus(0x40).kind(), ErrorKind::Other);
 
         println!(
             "{x}, {x:?}",
-            x = check_dec_status(JxlDecoderStatus::BasicInfo).unwrap_err()
+            x = check_dec_status(JxlDecoderStatus::BasicInfo as i32).unwrap_err()
         );
 
         Ok(())
     }
 
     #[test]
+    #[cfg(feature = "encoder")]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn encode_invalid_data() -> TestResult {
         let mut encoder = crate::encoder_builder().has_alpha(true).build()?;
@@ -143,11 +359,22 @@ mod tests {
             Err(EncodeError::ApiUsage)
         ));
 
-        println!(
-            "{x}, {x:?}",
-            x = EncodeError::UnknownStatus(JxlEncoderError::OK)
+        assert!(matches!(
+            JxlEncoderError::OutOfMemory.into(),
+            EncodeError::OutOfMemory
+        ));
+        assert!(matches!(
+            JxlEncoderError::OK.into(),
+            EncodeError::UnknownStatus(0)
+        ));
+        assert_eq!(EncodeError::ApiUsage.kind(), ErrorKind::InvalidUsage);
+        assert_eq!(
+            TranscodeError::from(EncodeError::OutOfMemory).kind(),
+            ErrorKind::OutOfMemory
         );
 
+        println!("{x}, {x:?}", x = EncodeError::UnknownStatus(0));
+
         Ok(())
     }
 }
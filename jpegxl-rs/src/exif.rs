@@ -0,0 +1,119 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parsed Exif metadata with `kamadak-exif`
+
+use exif::{Exif, Field, In, Reader, Tag, Value};
+
+use crate::{decode::JxlDecoder, DecodeError};
+
+/// Commonly used Exif fields
+pub struct ExifInfo {
+    /// Orientation, from 1 to 8
+    pub orientation: Option<u32>,
+    /// Date and time the file was changed
+    pub date_time: Option<String>,
+    /// Date and time the original image was taken
+    pub date_time_original: Option<String>,
+    /// Camera manufacturer
+    pub make: Option<String>,
+    /// Camera model
+    pub model: Option<String>,
+    /// All parsed fields
+    pub exif: Exif,
+}
+
+impl ExifInfo {
+    /// Parse raw Exif (TIFF) data
+    ///
+    /// # Errors
+    /// Return [`DecodeError::InvalidInput`] if the data is not valid Exif
+    pub fn from_tiff(data: Vec<u8>) -> Result<Self, DecodeError> {
+        let exif = Reader::new()
+            .read_raw(data)
+            .map_err(|_| DecodeError::InvalidInput)?;
+        let field = |tag| exif.get_field(tag, In::PRIMARY);
+
+        Ok(Self {
+            orientation: field(Tag::Orientation).and_then(|f| f.value.get_uint(0)),
+            date_time: field(Tag::DateTime).and_then(ascii),
+            date_time_original: field(Tag::DateTimeOriginal).and_then(ascii),
+            make: field(Tag::Make).and_then(ascii),
+            model: field(Tag::Model).and_then(ascii),
+            exif,
+        })
+    }
+}
+
+/// Return the first string of an ASCII field
+fn ascii(field: &Field) -> Option<String> {
+    match &field.value {
+        Value::Ascii(v) => v
+            .first()
+            .map(|s| String::from_utf8_lossy(s).trim_end().to_owned()),
+        _ => None,
+    }
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Read and parse the Exif metadata, if there is any
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoder fails or the Exif data is invalid
+    pub fn parsed_exif(&self, data: &[u8]) -> Result<Option<ExifInfo>, DecodeError> {
+        self.exif(data)?.map(ExifInfo::from_tiff).transpose()
+    }
+}
+
+#[cfg(all(test, feature = "encoder"))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        decoder_builder, encoder_builder,
+        tests::{SAMPLE_EXIF, SAMPLE_JXL},
+    };
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn parsed_exif() -> TestResult {
+        let decoder = decoder_builder().build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(SAMPLE_JXL)?;
+
+        let mut encoder = encoder_builder().build()?;
+        encoder.add_exif(SAMPLE_EXIF, false)?;
+        let result = encoder.encode::<u8, u8>(&pixels, metadata.width, metadata.height)?;
+
+        let info = decoder.parsed_exif(&result)?.expect("Failed to get Exif");
+        assert!(info.exif.fields().len() > 0);
+        assert_eq!(
+            info.orientation,
+            info.exif
+                .get_field(Tag::Orientation, In::PRIMARY)
+                .and_then(|f| f.value.get_uint(0))
+        );
+
+        assert!(decoder.parsed_exif(SAMPLE_JXL)?.is_none());
+        assert!(matches!(
+            ExifInfo::from_tiff(vec![0; 8]),
+            Err(DecodeError::InvalidInput)
+        ));
+
+        Ok(())
+    }
+}
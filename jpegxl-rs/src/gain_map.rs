@@ -0,0 +1,191 @@
+/*
+This file is part of jpegxl-rs.
+
+jpegxl-rs is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-rs is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Gain map bundle, stored in a `jhgm` box for HDR/SDR gain map workflows
+
+#[cfg(feature = "decoder")]
+use std::ptr::null;
+
+#[cfg(feature = "decoder")]
+use jpegxl_sys::encoder::encode::JxlColorEncodingSetToSRGB;
+use jpegxl_sys::{
+    color::color_encoding::JxlColorEncoding, common::types::JxlBool, metadata::gain_map as api,
+};
+
+#[cfg(feature = "decoder")]
+use crate::DecodeError;
+#[cfg(feature = "encoder")]
+use crate::{encode::ColorEncoding, EncodeError};
+
+/// Box type of the gain map bundle
+pub(crate) const GAIN_MAP_BOX: [u8; 4] = *b"jhgm";
+
+/// Gain map bundle
+#[derive(Debug, Clone, Default)]
+pub struct GainMapBundle {
+    /// Version of the bundle
+    pub jhgm_version: u8,
+    /// Gain map metadata, a binary blob following ISO 21496-1
+    pub metadata: Vec<u8>,
+    /// Color encoding of the alternate rendition
+    pub color_encoding: Option<JxlColorEncoding>,
+    /// Compressed ICC profile of the alternate rendition
+    pub alt_icc: Vec<u8>,
+    /// Gain map image as a naked JPEG XL codestream
+    pub gain_map: Vec<u8>,
+}
+
+impl GainMapBundle {
+    /// Serialize the bundle into the content of a `jhgm` box
+    ///
+    /// # Errors
+    /// Return [`EncodeError::BadInput`] if any part of the bundle is too large or fails to be
+    /// serialized
+    #[cfg(feature = "encoder")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let bundle = api::JxlGainMapBundle {
+            jhgm_version: self.jhgm_version,
+            gain_map_metadata_size: self
+                .metadata
+                .len()
+                .try_into()
+                .map_err(|_| EncodeError::BadInput)?,
+            gain_map_metadata: self.metadata.as_ptr(),
+            has_color_encoding: self.color_encoding.is_some().into(),
+            color_encoding: self
+                .color_encoding
+                .clone()
+                .unwrap_or_else(|| ColorEncoding::Srgb.into()),
+            alt_icc_size: self
+                .alt_icc
+                .len()
+                .try_into()
+                .map_err(|_| EncodeError::BadInput)?,
+            alt_icc: self.alt_icc.as_ptr(),
+            gain_map_size: self
+                .gain_map
+                .len()
+                .try_into()
+                .map_err(|_| EncodeError::BadInput)?,
+            gain_map: self.gain_map.as_ptr(),
+        };
+
+        let mut size = 0;
+        if unsafe { api::JxlGainMapGetBundleSize(&bundle, &mut size) } != JxlBool::True {
+            return Err(EncodeError::BadInput);
+        }
+
+        let mut buffer = vec![0; size];
+        let mut written = 0;
+        if unsafe {
+            api::JxlGainMapWriteBundle(&bundle, buffer.as_mut_ptr(), buffer.len(), &mut written)
+        } != JxlBool::True
+        {
+            return Err(EncodeError::BadInput);
+        }
+        buffer.truncate(written);
+
+        Ok(buffer)
+    }
+
+    /// Deserialize the bundle from the content of a `jhgm` box
+    ///
+    /// # Errors
+    /// Return [`DecodeError::InvalidInput`] if the data is not a valid bundle
+    #[cfg(feature = "decoder")]
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut color_encoding = std::mem::MaybeUninit::uninit();
+        // Safety: the placeholder is only overwritten when the bundle has a color encoding
+        let color_encoding = unsafe {
+            JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), false);
+            color_encoding.assume_init()
+        };
+        let mut bundle = api::JxlGainMapBundle {
+            jhgm_version: 0,
+            gain_map_metadata_size: 0,
+            gain_map_metadata: null(),
+            has_color_encoding: false.into(),
+            color_encoding,
+            alt_icc_size: 0,
+            alt_icc: null(),
+            gain_map_size: 0,
+            gain_map: null(),
+        };
+
+        let mut read = 0;
+        if unsafe { api::JxlGainMapReadBundle(&mut bundle, data.as_ptr(), data.len(), &mut read) }
+            != JxlBool::True
+        {
+            return Err(DecodeError::InvalidInput);
+        }
+
+        // Safety: the pointers reference sections within `data` after a successful read
+        let copy = |ptr: *const u8, len: usize| {
+            if ptr.is_null() || len == 0 {
+                vec![]
+            } else {
+                unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec()
+            }
+        };
+
+        Ok(Self {
+            jhgm_version: bundle.jhgm_version,
+            metadata: copy(
+                bundle.gain_map_metadata,
+                bundle.gain_map_metadata_size.into(),
+            ),
+            color_encoding: (bundle.has_color_encoding == JxlBool::True)
+                .then_some(bundle.color_encoding),
+            alt_icc: copy(
+                bundle.alt_icc,
+                usize::try_from(bundle.alt_icc_size).map_err(|_| DecodeError::SizeOverflow)?,
+            ),
+            gain_map: copy(
+                bundle.gain_map,
+                usize::try_from(bundle.gain_map_size).map_err(|_| DecodeError::SizeOverflow)?,
+            ),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "decoder", feature = "encoder"))]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    #[test]
+    fn test_roundtrip() -> TestResult {
+        let bundle = GainMapBundle {
+            metadata: vec![1, 2, 3],
+            color_encoding: Some(ColorEncoding::DisplayP3.into()),
+            alt_icc: vec![4, 5],
+            gain_map: vec![6, 7, 8, 9],
+            ..Default::default()
+        };
+
+        let parsed = GainMapBundle::from_bytes(&bundle.to_bytes()?)?;
+        assert_eq!(parsed.metadata, bundle.metadata);
+        assert!(parsed.color_encoding.is_some());
+        assert_eq!(parsed.alt_icc, bundle.alt_icc);
+        assert_eq!(parsed.gain_map, bundle.gain_map);
+
+        assert!(GainMapBundle::from_bytes(&[]).is_err());
+        Ok(())
+    }
+}
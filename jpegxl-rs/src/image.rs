@@ -17,15 +17,22 @@
 
 //! `image` crate integration
 
-use std::mem::MaybeUninit;
+use std::{io::Write, mem::MaybeUninit, ops::Deref, time::Duration};
 
-use image::{DynamicImage, ImageBuffer};
+use image::{
+    error::{
+        DecodingError, EncodingError, ImageFormatHint, UnsupportedError, UnsupportedErrorKind,
+    },
+    ColorType, Delay, DynamicImage, ExtendedColorType, Frame, Frames, ImageBuffer, ImageDecoder,
+    ImageEncoder, ImageError, ImageResult, Pixel,
+};
 use jpegxl_sys::common::types::{JxlDataType, JxlPixelFormat};
 
 use crate::{
     common::PixelType,
-    decode::{JxlDecoder, Metadata},
-    DecodeError,
+    decode::{DecoderResult, JxlDecoder},
+    encode::{Animation, ColorEncoding, EncoderFrame, EncoderResult, JxlEncoder},
+    DecodeError, EncodeError,
 };
 
 /// Extension trait for [`JxlDecoder`]
@@ -46,23 +53,42 @@ pub trait ToDynamic {
         &self,
         data: &[u8],
     ) -> Result<Option<DynamicImage>, DecodeError>;
+
+    /// Decode every frame of a JPEG XL animation to RGBA8 [`Frames`], e.g. to convert it to
+    /// GIF or APNG
+    ///
+    /// Frames are coalesced to the full image size unless coalescing is disabled, in which
+    /// case each frame keeps its own size and offset, cropped to the canvas.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding fails, or
+    /// [`DecodeError::UnsupportedPixelFormat`] when the requested number of channels is not
+    /// supported by [`DynamicImage`]
+    fn decode_to_frames(&self, data: &[u8]) -> Result<Frames<'static>, DecodeError>;
 }
 
-impl<'pr, 'mm> ToDynamic for JxlDecoder<'pr, 'mm> {
+impl ToDynamic for JxlDecoder<'_, '_> {
     fn decode_to_image(&self, data: &[u8]) -> Result<Option<DynamicImage>, DecodeError> {
         let mut buffer = vec![];
         let mut pixel_format = MaybeUninit::uninit();
         let metadata = self.decode_internal(
             data,
             None,
+            None,
             false,
             None,
+            None,
             pixel_format.as_mut_ptr(),
             &mut buffer,
         )?;
 
         let pixel_format = unsafe { pixel_format.assume_init() };
-        Ok(to_image(metadata, &pixel_format, buffer))
+        Ok(to_image(
+            metadata.width,
+            metadata.height,
+            &pixel_format,
+            buffer,
+        ))
     }
 
     fn decode_to_image_with<T: PixelType>(
@@ -74,19 +100,442 @@ impl<'pr, 'mm> ToDynamic for JxlDecoder<'pr, 'mm> {
         let metadata = self.decode_internal(
             data,
             Some(T::pixel_type()),
+            None,
+            false,
+            None,
+            None,
+            pixel_format.as_mut_ptr(),
+            &mut buffer,
+        )?;
+
+        let pixel_format = unsafe { pixel_format.assume_init() };
+        Ok(to_image(
+            metadata.width,
+            metadata.height,
+            &pixel_format,
+            buffer,
+        ))
+    }
+
+    fn decode_to_frames(&self, data: &[u8]) -> Result<Frames<'static>, DecodeError> {
+        let mut buffer = vec![];
+        let mut frames = vec![];
+        let mut pixel_format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(JxlDataType::Uint8),
+            None,
             false,
             None,
+            Some(&mut frames),
             pixel_format.as_mut_ptr(),
             &mut buffer,
         )?;
 
         let pixel_format = unsafe { pixel_format.assume_init() };
-        Ok(to_image(metadata, &pixel_format, buffer))
+        let animation = metadata.animation.unwrap_or_default();
+        let frames = frames
+            .into_iter()
+            .map(|frame| {
+                let image = to_image(frame.width, frame.height, &pixel_format, frame.pixels)
+                    .ok_or(DecodeError::UnsupportedPixelFormat(
+                        pixel_format.num_channels,
+                    ))?;
+                let delay = Delay::from_saturating_duration(Duration::from_secs_f64(
+                    f64::from(frame.duration) * f64::from(animation.tps_denominator)
+                        / f64::from(animation.tps_numerator),
+                ));
+                // `Frame` can't hold a negative offset, so drop the part left of or above
+                // the canvas
+                let (dx, dy) = (
+                    frame.x0.min(0).unsigned_abs(),
+                    frame.y0.min(0).unsigned_abs(),
+                );
+                let image = image::imageops::crop_imm(
+                    &image.into_rgba8(),
+                    dx,
+                    dy,
+                    frame.width.saturating_sub(dx),
+                    frame.height.saturating_sub(dy),
+                )
+                .to_image();
+                let (left, top) = (
+                    frame.x0.max(0).unsigned_abs(),
+                    frame.y0.max(0).unsigned_abs(),
+                );
+                Ok(Frame::from_parts(image, left, top, delay))
+            })
+            .collect::<Result<Vec<_>, DecodeError>>()?;
+
+        Ok(Frames::new(Box::new(frames.into_iter().map(Ok))))
+    }
+}
+
+/// Decoder implementing [`ImageDecoder`], to open JPEG XL images in `image` crate workflows
+///
+/// The image is decoded when constructed. Images with a pixel type not supported by
+/// [`DynamicImage`] are decoded to `f32` or `u16` instead.
+///
+/// # Example
+/// ```
+/// # || -> Result<(), Box<dyn std::error::Error>> {
+/// use image::DynamicImage;
+/// use jpegxl_rs::{decoder_builder, image::JxlImageDecoder};
+///
+/// # let data = std::fs::read("../samples/sample.jxl")?;
+/// let decoder = decoder_builder().build()?;
+/// let img = DynamicImage::from_decoder(JxlImageDecoder::new(&decoder, &data)?)?;
+/// # Ok(())
+/// # };
+/// ```
+pub struct JxlImageDecoder {
+    image: DynamicImage,
+    icc_profile: Option<Vec<u8>>,
+}
+
+impl<T: PixelType + image::Primitive> DecoderResult<T> {
+    /// Convert the result to an [`ImageBuffer`] of pixel type `P`
+    ///
+    /// Return `None` if `P` has a different number of channels
+    #[must_use]
+    pub fn into_image_buffer<P: Pixel<Subpixel = T>>(self) -> Option<ImageBuffer<P, Vec<T>>> {
+        if usize::from(P::CHANNEL_COUNT) != self.num_channels {
+            return None;
+        }
+        ImageBuffer::from_raw(self.metadata.width, self.metadata.height, self.data)
+    }
+}
+
+impl JxlImageDecoder {
+    /// Decode `data` with the decoder
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding fails, or
+    /// [`DecodeError::UnsupportedPixelFormat`] when the requested number of channels is not
+    /// supported by [`DynamicImage`].
+    pub fn new(decoder: &JxlDecoder, data: &[u8]) -> Result<Self, DecodeError> {
+        let mut data_type = None;
+        loop {
+            let mut buffer = vec![];
+            let mut pixel_format = MaybeUninit::uninit();
+            let mut metadata = decoder.decode_internal(
+                data,
+                data_type,
+                None,
+                true,
+                None,
+                None,
+                pixel_format.as_mut_ptr(),
+                &mut buffer,
+            )?;
+
+            let pixel_format = unsafe { pixel_format.assume_init() };
+            let icc_profile = metadata.icc_profile.take();
+            if let Some(image) = to_image(metadata.width, metadata.height, &pixel_format, buffer) {
+                return Ok(Self { image, icc_profile });
+            }
+
+            // Retry once with a pixel type supported for the number of channels
+            data_type = match (data_type, pixel_format.num_channels) {
+                (None, 3 | 4) => Some(JxlDataType::Float),
+                (None, 1 | 2) => Some(JxlDataType::Uint16),
+                (_, n) => return Err(DecodeError::UnsupportedPixelFormat(n)),
+            };
+        }
+    }
+
+    /// Return the decoded image
+    #[must_use]
+    pub fn into_image(self) -> DynamicImage {
+        self.image
+    }
+}
+
+impl ImageDecoder for JxlImageDecoder {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.image.width(), self.image.height())
+    }
+
+    fn color_type(&self) -> ColorType {
+        self.image.color()
+    }
+
+    fn icc_profile(&mut self) -> ImageResult<Option<Vec<u8>>> {
+        Ok(self.icc_profile.clone())
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        buf.copy_from_slice(self.image.as_bytes());
+        Ok(())
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+impl From<DecodeError> for ImageError {
+    fn from(err: DecodeError) -> Self {
+        ImageError::Decoding(DecodingError::new(format_hint(), err))
+    }
+}
+
+/// Encoder implementing [`ImageEncoder`], to write JPEG XL images in `image` crate workflows
+///
+/// The alpha and color encoding settings of the encoder are adjusted to the color type of
+/// each image, and restored afterwards.
+///
+/// # Example
+/// ```
+/// # || -> Result<(), Box<dyn std::error::Error>> {
+/// use jpegxl_rs::{encoder_builder, image::JxlImageEncoder};
+///
+/// # let img = image::open("../samples/sample.png")?;
+/// let mut encoder = encoder_builder().build()?;
+/// let mut output = Vec::new();
+/// img.write_with_encoder(JxlImageEncoder::new(&mut encoder, &mut output))?;
+/// # Ok(())
+/// # };
+/// ```
+pub struct JxlImageEncoder<'enc, 'prl, 'mm, W: Write> {
+    encoder: &'enc mut JxlEncoder<'prl, 'mm>,
+    writer: W,
+    icc_profile: Option<Vec<u8>>,
+}
+
+impl<'enc, 'prl, 'mm, W: Write> JxlImageEncoder<'enc, 'prl, 'mm, W> {
+    /// Construct with an encoder and the writer of the output
+    pub fn new(encoder: &'enc mut JxlEncoder<'prl, 'mm>, writer: W) -> Self {
+        Self {
+            encoder,
+            writer,
+            icc_profile: None,
+        }
+    }
+}
+
+impl<W: Write> ImageEncoder for JxlImageEncoder<'_, '_, '_, W> {
+    fn write_image(
+        self,
+        buf: &[u8],
+        width: u32,
+        height: u32,
+        color_type: ExtendedColorType,
+    ) -> ImageResult<()> {
+        let Self {
+            encoder,
+            mut writer,
+            icc_profile,
+        } = self;
+        let num_channels = num_channels(color_type).ok_or_else(|| {
+            ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                format_hint(),
+                UnsupportedErrorKind::Color(color_type),
+            ))
+        })?;
+
+        with_channels(encoder, num_channels, icc_profile, |encoder| {
+            use ExtendedColorType as E;
+
+            match color_type {
+                E::L16 | E::La16 | E::Rgb16 | E::Rgba16 => {
+                    let data: Vec<_> = buf
+                        .chunks_exact(2)
+                        .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+                        .collect();
+                    let frame = EncoderFrame::new(&data).num_channels(num_channels);
+                    encoder.encode_frame_to_writer::<u16, u16>(&frame, width, height, &mut writer)
+                }
+                E::Rgb32F | E::Rgba32F => {
+                    let data: Vec<_> = buf
+                        .chunks_exact(4)
+                        .map(|c| f32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                        .collect();
+                    let frame = EncoderFrame::new(&data).num_channels(num_channels);
+                    encoder.encode_frame_to_writer::<f32, f32>(&frame, width, height, &mut writer)
+                }
+                _ => {
+                    let frame = EncoderFrame::new(buf).num_channels(num_channels);
+                    encoder.encode_frame_to_writer::<u8, u8>(&frame, width, height, &mut writer)
+                }
+            }
+        })?;
+        Ok(())
+    }
+
+    fn set_icc_profile(&mut self, icc_profile: Vec<u8>) -> Result<(), UnsupportedError> {
+        self.icc_profile = Some(icc_profile);
+        Ok(())
+    }
+}
+
+impl JxlEncoder<'_, '_> {
+    /// Encode a [`DynamicImage`], with the number of channels, alpha and color encoding
+    /// matching the image
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode, or
+    /// [`EncodeError::NotSupported`] for an unknown [`DynamicImage`] variant
+    pub fn encode_image<U: PixelType>(
+        &mut self,
+        image: &DynamicImage,
+    ) -> Result<EncoderResult<U>, EncodeError> {
+        match image {
+            DynamicImage::ImageLuma8(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageLumaA8(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgb8(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgba8(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageLuma16(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageLumaA16(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgb16(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgba16(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgb32F(img) => self.encode_image_buffer(img),
+            DynamicImage::ImageRgba32F(img) => self.encode_image_buffer(img),
+            _ => Err(EncodeError::NotSupported),
+        }
+    }
+
+    /// Encode RGBA8 [`Frame`]s as an animation, e.g. collected from a GIF or APNG decoder
+    ///
+    /// Frame delays are converted to ticks of `animation`, which is set to 1000 ticks per
+    /// second and looping forever if `None`. Frames smaller than the canvas are placed at their
+    /// offsets.
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode, or
+    /// [`EncodeError::ApiUsage`] if there are no frames
+    pub fn encode_animation<U: PixelType>(
+        &mut self,
+        frames: &[Frame],
+    ) -> Result<EncoderResult<U>, EncodeError> {
+        if frames.is_empty() {
+            return Err(EncodeError::ApiUsage);
+        }
+        let (width, height) = frames.iter().fold((0, 0), |(w, h), f| {
+            (
+                w.max(f.left() + f.buffer().width()),
+                h.max(f.top() + f.buffer().height()),
+            )
+        });
+
+        let original_animation = self.animation;
+        let animation = *self.animation.get_or_insert(Animation {
+            tps_numerator: 1000,
+            tps_denominator: 1,
+            num_loops: 0,
+        });
+
+        let result = with_channels(self, 4, None, |encoder| {
+            let mut multiple = encoder.multiple::<U>(width, height)?;
+            for frame in frames {
+                let buffer = frame.buffer();
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let scale = u64::from(denom) * u64::from(animation.tps_denominator) * 1000;
+                let ticks =
+                    (u64::from(numer) * u64::from(animation.tps_numerator) + scale / 2) / scale;
+
+                let mut encoder_frame = EncoderFrame::new(buffer.as_raw())
+                    .num_channels(4)
+                    .duration(u32::try_from(ticks).unwrap_or(u32::MAX));
+                if buffer.dimensions() != (width, height) {
+                    encoder_frame = encoder_frame.crop(
+                        i32::try_from(frame.left()).map_err(|_| EncodeError::ApiUsage)?,
+                        i32::try_from(frame.top()).map_err(|_| EncodeError::ApiUsage)?,
+                        buffer.width(),
+                        buffer.height(),
+                    );
+                }
+                multiple.push_frame(&encoder_frame)?;
+            }
+            multiple.encode()
+        });
+
+        self.animation = original_animation;
+        result
+    }
+
+    /// Encode an [`ImageBuffer`], with the number of channels, alpha and color encoding
+    /// matching the pixel type
+    ///
+    /// # Errors
+    /// Return [`EncodeError`] if the internal encoder fails to encode
+    pub fn encode_image_buffer<P, C, U>(
+        &mut self,
+        image: &ImageBuffer<P, C>,
+    ) -> Result<EncoderResult<U>, EncodeError>
+    where
+        P: Pixel,
+        P::Subpixel: PixelType,
+        C: Deref<Target = [P::Subpixel]>,
+        U: PixelType,
+    {
+        let num_channels = u32::from(P::CHANNEL_COUNT);
+        with_channels(self, num_channels, None, |encoder| {
+            encoder.encode_frame(
+                &EncoderFrame::new(image.as_raw()).num_channels(num_channels),
+                image.width(),
+                image.height(),
+            )
+        })
+    }
+}
+
+/// Return the number of channels of a color type supported by the encoder
+fn num_channels(color_type: ExtendedColorType) -> Option<u32> {
+    use ExtendedColorType as E;
+
+    match color_type {
+        E::L8 | E::L16 => Some(1),
+        E::La8 | E::La16 => Some(2),
+        E::Rgb8 | E::Rgb16 | E::Rgb32F => Some(3),
+        E::Rgba8 | E::Rgba16 | E::Rgba32F => Some(4),
+        _ => None,
+    }
+}
+
+/// Run `f` with the alpha, color encoding and ICC profile settings of the encoder matching
+/// the image, and restore them afterwards
+fn with_channels<R>(
+    encoder: &mut JxlEncoder,
+    num_channels: u32,
+    icc_profile: Option<Vec<u8>>,
+    f: impl FnOnce(&mut JxlEncoder) -> R,
+) -> R {
+    let has_alpha = encoder.has_alpha;
+    let color_encoding = encoder.color_encoding;
+    let original_icc_profile = encoder.icc_profile.take();
+
+    encoder.has_alpha = num_channels % 2 == 0;
+    encoder.color_encoding = match (num_channels <= 2, color_encoding) {
+        (true, ColorEncoding::LinearSrgb) => ColorEncoding::LinearSrgbLuma,
+        (true, c) if !c.is_gray() => ColorEncoding::SrgbLuma,
+        (false, ColorEncoding::LinearSrgbLuma) => ColorEncoding::LinearSrgb,
+        (false, c) if c.is_gray() => ColorEncoding::Srgb,
+        (_, c) => c,
+    };
+    encoder.icc_profile = icc_profile.or_else(|| original_icc_profile.clone());
+
+    let result = f(encoder);
+
+    encoder.has_alpha = has_alpha;
+    encoder.color_encoding = color_encoding;
+    encoder.icc_profile = original_icc_profile;
+    result
+}
+
+fn format_hint() -> ImageFormatHint {
+    ImageFormatHint::Name("JPEG XL".to_string())
+}
+
+impl From<EncodeError> for ImageError {
+    fn from(err: EncodeError) -> Self {
+        ImageError::Encoding(EncodingError::new(format_hint(), err))
     }
 }
 
 fn to_image(
-    Metadata { width, height, .. }: Metadata,
+    width: u32,
+    height: u32,
     pixel_format: &JxlPixelFormat,
     buffer: Vec<u8>,
 ) -> Option<DynamicImage> {
@@ -136,7 +585,7 @@ mod tests {
     use super::*;
     use crate::{
         decode::PixelFormat,
-        decoder_builder,
+        decoder_builder, encoder_builder,
         tests::{SAMPLE_JXL, SAMPLE_JXL_GRAY, SAMPLE_PNG},
         ThreadsRunner,
     };
@@ -171,6 +620,178 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decoder_result() -> TestResult {
+        let decoder = decoder_builder().build()?;
+        let result = decoder.decode_to_result::<u16>(SAMPLE_JXL)?;
+        assert_eq!(result.num_channels, 4);
+        assert_eq!(
+            result.pixels::<4>().len(),
+            (result.metadata.width * result.metadata.height) as usize
+        );
+
+        let img = result
+            .into_image_buffer::<image::Rgba<u16>>()
+            .expect("Failed to create ImageBuffer");
+        let sample_png = image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png)?;
+        assert_eq!(img, sample_png.to_rgba16());
+
+        let result = decoder.decode_to_result::<u8>(SAMPLE_JXL)?;
+        assert!(result.into_image_buffer::<image::Rgb<u8>>().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn image_decoder() -> TestResult {
+        let decoder = decoder_builder().build()?;
+
+        let mut jxl = JxlImageDecoder::new(&decoder, SAMPLE_JXL)?;
+        assert_eq!(jxl.dimensions(), (40, 50));
+        assert_eq!(jxl.color_type(), image::ColorType::Rgba16);
+        assert!(jxl.icc_profile()?.is_some());
+
+        let img = DynamicImage::from_decoder(jxl)?;
+        let sample_png = image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png)?;
+        assert_eq!(img.to_rgba16(), sample_png.to_rgba16());
+
+        assert!(matches!(
+            JxlImageDecoder::new(&decoder, &[]).map_err(ImageError::from),
+            Err(ImageError::Decoding(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn image_encoder() -> TestResult {
+        let mut encoder = encoder_builder().lossless_original().build()?;
+        let decoder = decoder_builder().build()?;
+        let sample = image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png)?;
+
+        for img in [
+            DynamicImage::ImageLuma8(sample.to_luma8()),
+            DynamicImage::ImageLumaA8(sample.to_luma_alpha8()),
+            DynamicImage::ImageRgb8(sample.to_rgb8()),
+            DynamicImage::ImageRgba16(sample.to_rgba16()),
+        ] {
+            let mut output = Vec::new();
+            img.write_with_encoder(JxlImageEncoder::new(&mut encoder, &mut output))?;
+
+            let output_image = decoder
+                .decode_to_image(&output)?
+                .expect("Failed to create DynamicImage");
+            assert_eq!(output_image.color(), img.color());
+            assert_eq!(output_image, img);
+        }
+        assert!(!encoder.has_alpha);
+
+        let mut output = Vec::new();
+        DynamicImage::ImageRgb32F(sample.to_rgb32f())
+            .write_with_encoder(JxlImageEncoder::new(&mut encoder, &mut output))?;
+        assert!(!output.is_empty());
+
+        let result = JxlImageEncoder::new(&mut encoder, Vec::new()).write_image(
+            &[0; 3],
+            1,
+            1,
+            ExtendedColorType::Bgr8,
+        );
+        assert!(matches!(result, Err(ImageError::Unsupported(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn encode_image() -> TestResult {
+        let mut encoder = encoder_builder().lossless_original().build()?;
+        let decoder = decoder_builder().build()?;
+        let sample = image::load_from_memory_with_format(SAMPLE_PNG, image::ImageFormat::Png)?;
+
+        let gray = sample.to_luma_alpha16();
+        let result: EncoderResult<u16> = encoder.encode_image_buffer(&gray)?;
+        let (metadata, pixels) = decoder.decode_with::<u16>(&result)?;
+        assert_eq!(metadata.num_color_channels, 1);
+        assert!(metadata.has_alpha_channel);
+        assert_eq!(pixels, gray.as_raw().as_slice());
+
+        let result: EncoderResult<u8> = encoder.encode_image(&sample)?;
+        let output_image = decoder
+            .decode_to_image(&result)?
+            .expect("Failed to create DynamicImage");
+        assert_eq!(output_image.to_rgba8(), sample.to_rgba8());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn animation() -> TestResult {
+        let frames: Vec<_> = [
+            ([255, 0, 0, 255], 100),
+            ([0, 255, 0, 128], 50),
+            ([0, 0, 255, 0], 20),
+        ]
+        .into_iter()
+        .map(|(color, ms)| {
+            image::Frame::from_parts(
+                image::RgbaImage::from_pixel(8, 6, image::Rgba(color)),
+                0,
+                0,
+                Delay::from_numer_denom_ms(ms, 1),
+            )
+        })
+        .collect();
+
+        let mut encoder = encoder_builder().lossless_original().build()?;
+        let result: EncoderResult<u8> = encoder.encode_animation(&frames)?;
+        assert!(encoder.animation.is_none());
+        assert!(encoder.encode_animation::<u8>(&[]).is_err());
+
+        let decoder = decoder_builder().build()?;
+        let output_frames = decoder.decode_to_frames(&result)?.collect_frames()?;
+        assert_eq!(output_frames.len(), frames.len());
+        for (output, input) in output_frames.iter().zip(&frames) {
+            assert_eq!(output.buffer(), input.buffer());
+            assert_eq!(output.delay(), input.delay());
+        }
+
+        let (metadata, _) = decoder.decode(&result)?;
+        let animation = metadata.animation.expect("Animation not found");
+        assert_eq!(animation.tps_numerator, 1000);
+
+        let still = decoder.decode_to_frames(SAMPLE_JXL)?.collect_frames()?;
+        assert_eq!(still.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn layers() -> TestResult {
+        let background = [255_u8, 0, 0].repeat(16);
+        let layer = [0_u8, 255, 0].repeat(4);
+        let mut encoder = encoder_builder().lossless_original().build()?;
+        let result: EncoderResult<u8> = encoder
+            .multiple(4, 4)?
+            .add_frame(&EncoderFrame::new(&background))?
+            .add_frame(&EncoderFrame::new(&layer).crop(1, 1, 2, 2))?
+            .encode()?;
+
+        let decoder = decoder_builder().coalescing(false).build()?;
+        let frames = decoder.decode_to_frames(&result)?.collect_frames()?;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].buffer().dimensions(), (2, 2));
+        assert_eq!((frames[1].left(), frames[1].top()), (1, 1));
+        assert_eq!(frames[1].buffer().get_pixel(0, 0).0, [0, 255, 0, 255]);
+
+        Ok(())
+    }
+
     #[test]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn pixel_type() -> TestResult {
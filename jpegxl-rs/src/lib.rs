@@ -17,29 +17,89 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
-#![doc = include_str!("../README.md")]
+// The examples of the README use both the decoder and the encoder
+#![cfg_attr(all(feature = "decoder", feature = "encoder"), doc = include_str!("../README.md"))]
+#![cfg_attr(
+    not(all(feature = "decoder", feature = "encoder")),
+    doc = "A safe JPEGXL wrapper over `libjxl` library"
+)]
 
+#[cfg(any(feature = "decoder", feature = "encoder"))]
 #[macro_use]
 extern crate derive_builder;
 
+#[cfg_attr(not(any(feature = "decoder", feature = "encoder")), allow(dead_code))]
+mod cancel;
+#[cfg_attr(not(any(feature = "decoder", feature = "encoder")), allow(dead_code))]
 mod common;
+#[cfg(all(
+    any(feature = "decoder", feature = "encoder"),
+    not(feature = "libjxl_0_10")
+))]
+pub mod compressed_icc;
+#[cfg(feature = "decoder")]
 pub mod decode;
+#[cfg(feature = "encoder")]
 pub mod encode;
 mod errors;
+#[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+pub mod gain_map;
 pub mod memory;
+#[cfg_attr(not(any(feature = "decoder", feature = "encoder")), allow(dead_code))]
 pub mod parallel;
+pub mod prelude;
+#[cfg(feature = "decoder")]
+pub mod texture;
+#[cfg(any(feature = "decoder", feature = "encoder"))]
+mod trace;
+#[cfg(all(feature = "jpeg", feature = "decoder", feature = "encoder"))]
+pub mod transcode;
 pub mod utils;
 
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "qcms")]
+mod cms;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "convert")]
+pub mod convert;
+#[cfg(feature = "exif")]
+pub mod exif;
 #[cfg(feature = "image")]
 pub mod image;
+#[cfg(feature = "ndarray")]
+pub mod ndarray;
+#[cfg(feature = "rgb")]
+pub mod rgb;
 
 #[cfg(test)]
 mod tests;
 
-pub use common::Endianness;
-pub use decode::decoder_builder;
-pub use encode::encoder_builder;
-pub use errors::{DecodeError, EncodeError};
+pub use common::{convert_pixels, Endianness, PixelType};
+#[cfg(all(feature = "decoder", feature = "tokio"))]
+pub use decode::decode_async;
+#[cfg(feature = "stream")]
+pub use decode::decode_progressive_stream;
+#[cfg(feature = "decoder")]
+pub use decode::{decode_bytes, decode_file, decode_reader, decoder_builder};
+#[cfg(all(feature = "encoder", feature = "tokio"))]
+pub use encode::encode_async;
+#[cfg(feature = "encoder")]
+pub use encode::{encode_file, encode_writer, encoder_builder};
+#[cfg(feature = "conformance")]
+pub use errors::ConformanceError;
+pub use errors::{DecodeError, EncodeError, ErrorKind, TranscodeError};
+pub use utils::{capabilities, encoder_version, version};
 
+pub use parallel::instrumented_runner::InstrumentedRunner;
+#[cfg(feature = "threads")]
 pub use parallel::resizable_runner::ResizableRunner;
+pub use parallel::sequential_runner::SequentialRunner;
+#[cfg(feature = "threads")]
 pub use parallel::threads_runner::ThreadsRunner;
+
+#[cfg(feature = "rayon")]
+pub use parallel::rayon_runner::RayonRunner;
+#[cfg(feature = "tokio")]
+pub use parallel::tokio_runner::TokioRunner;
@@ -17,10 +17,25 @@
 
 //! Memory manager interface
 
+use std::{
+    alloc::Layout,
+    ffi::c_void,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr::{null_mut, NonNull},
+};
+
 use jpegxl_sys::common::memory_manager::{JpegxlAllocFunc, JpegxlFreeFunc, JxlMemoryManager};
 
-/// General trait for a memory manager
+mod global_alloc;
+mod tracking;
+mod zeroing;
+pub use global_alloc::{Global, GlobalAllocManager};
+pub use tracking::*;
+pub use zeroing::*;
 
+/// General trait for a memory manager
+///
+/// Implement [`MemoryAllocator`] instead to avoid writing the C callbacks by hand.
 #[allow(clippy::module_name_repetitions)]
 pub trait MemoryManager {
     /// Return a custom allocating function
@@ -39,8 +54,85 @@ pub trait MemoryManager {
     }
 }
 
+/// Alignment of the allocations, matching `malloc`
+const ALIGN: usize = 16;
+/// Size of the header in front of each allocation storing its size
+const HEADER: usize = ALIGN;
+
+/// Memory manager written in safe Rust
+///
+/// Every type implementing this trait is also a [`MemoryManager`]. The size of each allocation
+/// is stored in front of the memory handed to libjxl, so it is known when freeing, and panics
+/// are caught before reaching libjxl, which sees them as a failed allocation.
+pub trait MemoryAllocator {
+    /// Allocate memory for `layout`, return `None` on failure
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>>;
+
+    /// Free memory returned by [`MemoryAllocator::allocate`]
+    ///
+    /// # Safety
+    /// `ptr` must have been returned by `allocate` on the same allocator with the same
+    /// `layout`, and not freed yet.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+}
+
+impl<T: MemoryAllocator> MemoryManager for T {
+    fn alloc(&self) -> JpegxlAllocFunc {
+        alloc_with::<T>
+    }
+
+    fn free(&self) -> JpegxlFreeFunc {
+        free_with::<T>
+    }
+}
+
+unsafe extern "C-unwind" fn alloc_with<T: MemoryAllocator>(
+    opaque: *mut c_void,
+    size: usize,
+) -> *mut c_void {
+    let allocator = unsafe { &*opaque.cast::<T>() };
+    let Some(layout) = size
+        .checked_add(HEADER)
+        .and_then(|total| Layout::from_size_align(total, ALIGN).ok())
+    else {
+        return null_mut();
+    };
+
+    match catch_unwind(AssertUnwindSafe(|| allocator.allocate(layout))) {
+        Ok(Some(base)) => unsafe {
+            base.as_ptr().cast::<usize>().write_unaligned(size);
+            base.as_ptr().add(HEADER).cast()
+        },
+        _ => null_mut(),
+    }
+}
+
+unsafe extern "C-unwind" fn free_with<T: MemoryAllocator>(
+    opaque: *mut c_void,
+    address: *mut c_void,
+) {
+    let Some(address) = NonNull::new(address.cast::<u8>()) else {
+        return;
+    };
+    let allocator = unsafe { &*opaque.cast::<T>() };
+
+    unsafe {
+        let base = NonNull::new_unchecked(address.as_ptr().sub(HEADER));
+        let size = base.as_ptr().cast::<usize>().read_unaligned();
+        let layout = Layout::from_size_align_unchecked(size + HEADER, ALIGN);
+        // The memory is leaked if the allocator panics
+        let _ = catch_unwind(AssertUnwindSafe(|| allocator.deallocate(base, layout)));
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
+    // The managers are shared with the tests of other modules and features
+    #![cfg_attr(
+        not(all(feature = "decoder", feature = "encoder")),
+        allow(dead_code, unused_imports)
+    )]
+
     use std::{
         ffi::c_void,
         ptr::null_mut,
@@ -49,7 +141,10 @@ pub(crate) mod tests {
 
     use testresult::TestResult;
 
-    use crate::{decoder_builder, encoder_builder};
+    #[cfg(feature = "decoder")]
+    use crate::decoder_builder;
+    #[cfg(all(feature = "decoder", feature = "encoder"))]
+    use crate::encoder_builder;
 
     use super::*;
     /// Example implementation of [`MemoryManager`] of a fixed size allocator
@@ -127,6 +222,7 @@ pub(crate) mod tests {
     }
 
     #[test]
+    #[cfg(all(feature = "decoder", feature = "encoder"))]
     fn test_mm() -> TestResult {
         let mm = BumpManager::new(1024 * 1024 * 50);
         let dec = decoder_builder().memory_manager(&mm).build()?;
@@ -138,7 +234,30 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    struct PanicAllocator;
+
+    impl MemoryAllocator for PanicAllocator {
+        fn allocate(&self, _layout: Layout) -> Option<NonNull<u8>> {
+            panic!("Allocation failed")
+        }
+
+        unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+            unreachable!("Nothing is allocated")
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "decoder")]
+    fn test_allocator_panic() {
+        let mm = PanicAllocator;
+        assert!(matches!(
+            decoder_builder().memory_manager(&mm).build(),
+            Err(crate::DecodeError::CannotCreateDecoder)
+        ));
+    }
+
     #[test]
+    #[cfg(feature = "decoder")]
     #[should_panic = "Stack unwind test"]
     fn test_unwind() {
         let mm = PanicManager {};
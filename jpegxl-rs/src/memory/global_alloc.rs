@@ -0,0 +1,126 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Memory manager routing allocations through a Rust allocator
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+};
+
+use super::MemoryAllocator;
+
+/// The allocator registered with `#[global_allocator]`, or the system allocator by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+unsafe impl GlobalAlloc for Global {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        unsafe { std::alloc::alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        unsafe { std::alloc::alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        unsafe { std::alloc::realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Memory manager routing libjxl allocations through a Rust [`GlobalAlloc`]
+///
+/// With the default [`Global`], libjxl is served by the global allocator of the program,
+/// e.g. jemalloc or mimalloc. Allocations are aligned to 16 bytes like `malloc`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalAllocManager<A: GlobalAlloc = Global> {
+    allocator: A,
+}
+
+impl<A: GlobalAlloc> GlobalAllocManager<A> {
+    /// Construct with an allocator
+    pub fn new(allocator: A) -> Self {
+        Self { allocator }
+    }
+}
+
+impl<A: GlobalAlloc> MemoryAllocator for GlobalAllocManager<A> {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        NonNull::new(unsafe { self.allocator.alloc(layout) })
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.allocator.dealloc(ptr.as_ptr(), layout) };
+    }
+}
+
+#[cfg(all(test, feature = "decoder", feature = "encoder"))]
+mod tests {
+    use std::{
+        alloc::System,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, encoder_builder};
+
+    #[derive(Default)]
+    struct CountingAlloc {
+        live: AtomicUsize,
+        total: AtomicUsize,
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            assert_eq!(layout.align(), 16);
+            self.live.fetch_add(1, Ordering::Relaxed);
+            self.total.fetch_add(1, Ordering::Relaxed);
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.live.fetch_sub(1, Ordering::Relaxed);
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn test_global_alloc_manager() -> TestResult {
+        let mm = GlobalAllocManager::new(CountingAlloc::default());
+        {
+            let decoder = decoder_builder().memory_manager(&mm).build()?;
+            let (metadata, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+            let mut encoder = encoder_builder().memory_manager(&mm).build()?;
+            encoder.encode::<u8, u8>(&pixels, metadata.width, metadata.height)?;
+        }
+        assert!(mm.allocator.total.load(Ordering::Relaxed) > 0);
+        assert_eq!(mm.allocator.live.load(Ordering::Relaxed), 0);
+
+        let mm = GlobalAllocManager::<Global>::default();
+        let decoder = decoder_builder().memory_manager(&mm).build()?;
+        decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+        Ok(())
+    }
+}
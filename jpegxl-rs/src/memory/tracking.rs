@@ -0,0 +1,217 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Memory manager counting and limiting allocations
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{Global, MemoryAllocator};
+
+/// Allocation statistics collected by a [`TrackingManager`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Number of bytes currently allocated
+    pub live_bytes: usize,
+    /// Number of allocations not yet freed
+    pub live_allocations: usize,
+    /// Highest number of bytes allocated at the same time
+    pub peak_bytes: usize,
+    /// Number of successful allocations
+    pub allocations: usize,
+    /// Total number of bytes allocated
+    pub total_bytes: usize,
+    /// Number of allocations refused because of the cap or failed in the allocator
+    pub failed_allocations: usize,
+}
+
+/// Memory manager counting live allocations and bytes, and refusing allocations beyond a cap
+///
+/// When the cap is reached libjxl fails to create the decoder or encoder, or fails to decode
+/// or encode with an error, which limits the damage of untrusted files in sandboxed services.
+#[derive(Debug, Default)]
+pub struct TrackingManager {
+    limit: Option<usize>,
+    live_bytes: AtomicUsize,
+    live_allocations: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    allocations: AtomicUsize,
+    total_bytes: AtomicUsize,
+    failed_allocations: AtomicUsize,
+}
+
+impl TrackingManager {
+    /// Construct without a cap
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct with a cap on the number of live bytes
+    #[must_use]
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Self::default()
+        }
+    }
+
+    /// Return the cap on the number of live bytes
+    #[must_use]
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Return the number of bytes currently allocated
+    #[must_use]
+    pub fn live_bytes(&self) -> usize {
+        self.live_bytes.load(Ordering::Acquire)
+    }
+
+    /// Return the number of allocations not yet freed
+    #[must_use]
+    pub fn live_allocations(&self) -> usize {
+        self.live_allocations.load(Ordering::Acquire)
+    }
+
+    /// Return the statistics collected since construction or the last
+    /// [`TrackingManager::reset_report`]
+    ///
+    /// Reset the report before each decode or encode to get per-image statistics.
+    #[must_use]
+    pub fn report(&self) -> MemoryReport {
+        MemoryReport {
+            live_bytes: self.live_bytes(),
+            live_allocations: self.live_allocations(),
+            peak_bytes: self.peak_bytes.load(Ordering::Acquire),
+            allocations: self.allocations.load(Ordering::Acquire),
+            total_bytes: self.total_bytes.load(Ordering::Acquire),
+            failed_allocations: self.failed_allocations.load(Ordering::Acquire),
+        }
+    }
+
+    /// Reset the peak to the current live bytes and clear the counters
+    pub fn reset_report(&self) {
+        self.peak_bytes.store(self.live_bytes(), Ordering::Release);
+        self.allocations.store(0, Ordering::Release);
+        self.total_bytes.store(0, Ordering::Release);
+        self.failed_allocations.store(0, Ordering::Release);
+    }
+
+    /// Reserve `size` bytes, return `false` if it would exceed the cap
+    fn reserve(&self, size: usize) -> bool {
+        let limit = self.limit.unwrap_or(usize::MAX);
+        match self
+            .live_bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |live| {
+                live.checked_add(size).filter(|&new| new <= limit)
+            }) {
+            Ok(live) => {
+                self.peak_bytes.fetch_max(live + size, Ordering::AcqRel);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.live_bytes.fetch_sub(size, Ordering::AcqRel);
+    }
+}
+
+impl MemoryAllocator for TrackingManager {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let size = layout.size();
+        if !self.reserve(size) {
+            self.failed_allocations.fetch_add(1, Ordering::AcqRel);
+            return None;
+        }
+
+        let Some(ptr) = NonNull::new(unsafe { Global.alloc(layout) }) else {
+            self.release(size);
+            self.failed_allocations.fetch_add(1, Ordering::AcqRel);
+            return None;
+        };
+        self.live_allocations.fetch_add(1, Ordering::AcqRel);
+        self.allocations.fetch_add(1, Ordering::AcqRel);
+        self.total_bytes.fetch_add(size, Ordering::AcqRel);
+        Some(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Global.dealloc(ptr.as_ptr(), layout) };
+        self.release(layout.size());
+        self.live_allocations.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(all(test, feature = "decoder"))]
+mod tests {
+    #[cfg(feature = "encoder")]
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::decoder_builder;
+    #[cfg(feature = "encoder")]
+    use crate::encoder_builder;
+
+    #[test]
+    #[cfg(feature = "encoder")]
+    fn test_tracking_manager() -> TestResult {
+        let mm = TrackingManager::new();
+        {
+            let decoder = decoder_builder().memory_manager(&mm).build()?;
+            let (metadata, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+            assert!(mm.live_allocations() > 0);
+
+            let mut encoder = encoder_builder().memory_manager(&mm).build()?;
+            encoder.encode::<u8, u8>(&pixels, metadata.width, metadata.height)?;
+        }
+        assert_eq!(mm.live_bytes(), 0);
+        assert_eq!(mm.live_allocations(), 0);
+
+        let report = mm.report();
+        assert!(report.allocations > 0);
+        assert!(report.peak_bytes > 0 && report.peak_bytes <= report.total_bytes);
+        assert_eq!(report.failed_allocations, 0);
+
+        mm.reset_report();
+        assert_eq!(mm.report(), MemoryReport::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit() {
+        let mm = TrackingManager::with_limit(64 * 1024);
+        assert_eq!(mm.limit(), Some(64 * 1024));
+
+        let result = decoder_builder()
+            .memory_manager(&mm)
+            .build()
+            .and_then(|decoder| decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL));
+        assert!(result.is_err());
+        assert_eq!(mm.live_bytes(), 0);
+
+        let report = mm.report();
+        assert!(report.failed_allocations > 0);
+        assert!(report.peak_bytes <= 64 * 1024);
+    }
+}
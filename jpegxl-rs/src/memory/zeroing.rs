@@ -0,0 +1,110 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Memory manager zeroing buffers on free
+
+use std::{alloc::Layout, ptr::NonNull, sync::atomic};
+
+use super::{GlobalAllocManager, MemoryAllocator};
+
+/// Memory manager overwriting every buffer with zeros before freeing it, to limit data
+/// remanence when decoding sensitive images
+///
+/// Only memory allocated by libjxl is cleared. Pixel buffers returned by the decoder are owned
+/// by the caller, who is responsible for clearing them.
+#[derive(Debug, Default)]
+pub struct ZeroingManager<A: MemoryAllocator = GlobalAllocManager> {
+    allocator: A,
+}
+
+impl<A: MemoryAllocator> ZeroingManager<A> {
+    /// Wrap another allocator
+    pub fn new(allocator: A) -> Self {
+        Self { allocator }
+    }
+
+    /// Return the wrapped allocator
+    pub fn inner(&self) -> &A {
+        &self.allocator
+    }
+}
+
+impl<A: MemoryAllocator> MemoryAllocator for ZeroingManager<A> {
+    fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        self.allocator.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // Volatile writes are not optimized away even though the memory is freed afterwards
+        for i in 0..layout.size() {
+            unsafe { ptr.as_ptr().add(i).write_volatile(0) };
+        }
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+
+        unsafe { self.allocator.deallocate(ptr, layout) };
+    }
+}
+
+#[cfg(all(test, feature = "decoder"))]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, memory::TrackingManager};
+
+    #[derive(Default)]
+    struct CheckingAllocator {
+        inner: TrackingManager,
+        dirty: AtomicBool,
+        freed: AtomicUsize,
+    }
+
+    impl MemoryAllocator for CheckingAllocator {
+        fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+            self.inner.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            let bytes = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), layout.size()) };
+            if bytes.iter().any(|&b| b != 0) {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            self.freed.fetch_add(1, Ordering::Relaxed);
+            unsafe { self.inner.deallocate(ptr, layout) };
+        }
+    }
+
+    #[test]
+    fn test_zeroing_manager() -> TestResult {
+        let mm = ZeroingManager::new(CheckingAllocator::default());
+        {
+            let decoder = decoder_builder().memory_manager(&mm).build()?;
+            decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        }
+        assert!(mm.inner().freed.load(Ordering::Relaxed) > 0);
+        assert!(!mm.inner().dirty.load(Ordering::Relaxed));
+        assert_eq!(mm.inner().inner.live_bytes(), 0);
+
+        let mm = ZeroingManager::<GlobalAllocManager>::default();
+        let decoder = decoder_builder().memory_manager(&mm).build()?;
+        decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+        Ok(())
+    }
+}
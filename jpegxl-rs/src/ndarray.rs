@@ -0,0 +1,143 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `ndarray` crate integration
+
+use std::mem::{size_of, MaybeUninit};
+
+use ndarray::{Array3, ShapeBuilder};
+
+use crate::{
+    common::PixelType,
+    decode::{JxlDecoder, Metadata},
+    DecodeError,
+};
+
+/// Extension trait for [`JxlDecoder`]
+pub trait ToNdarray {
+    /// Decode the JPEG XL image to an [`Array3`] of shape (height, width, channels)
+    ///
+    /// When `align` is set in the pixel format, the row stride of the array includes the
+    /// padding. Use [`Array3::as_standard_layout`] for a contiguous array.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding fails
+    fn decode_to_array<T: PixelType>(
+        &self,
+        data: &[u8],
+    ) -> Result<(Metadata, Array3<T>), DecodeError>;
+}
+
+impl ToNdarray for JxlDecoder<'_, '_> {
+    fn decode_to_array<T: PixelType>(
+        &self,
+        data: &[u8],
+    ) -> Result<(Metadata, Array3<T>), DecodeError> {
+        let mut buffer = vec![];
+        let mut pixel_format = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(T::pixel_type()),
+            None,
+            self.icc_profile,
+            None,
+            None,
+            pixel_format.as_mut_ptr(),
+            &mut buffer,
+        )?;
+        let pixel_format = unsafe { pixel_format.assume_init() };
+
+        let overflow = |_| DecodeError::SizeOverflow;
+        let height = usize::try_from(metadata.height).map_err(overflow)?;
+        let width = usize::try_from(metadata.width).map_err(overflow)?;
+        let channels = usize::try_from(pixel_format.num_channels).map_err(overflow)?;
+        let row_bytes = width
+            .checked_mul(channels * size_of::<T>())
+            .ok_or(DecodeError::SizeOverflow)?;
+        let stride_bytes = match pixel_format.align {
+            0 => Some(row_bytes),
+            align => row_bytes.div_ceil(align).checked_mul(align),
+        }
+        .ok_or(DecodeError::SizeOverflow)?;
+
+        let array = if stride_bytes % size_of::<T>() == 0 {
+            let pixels = T::convert(&buffer, &pixel_format);
+            Array3::from_shape_vec(
+                (height, width, channels).strides((stride_bytes / size_of::<T>(), channels, 1)),
+                pixels,
+            )
+        } else {
+            // The padding is not a whole number of samples, drop it
+            let rows: Vec<u8> = buffer
+                .chunks(stride_bytes)
+                .flat_map(|row| &row[..row_bytes])
+                .copied()
+                .collect();
+            Array3::from_shape_vec((height, width, channels), T::convert(&rows, &pixel_format))
+        }
+        .map_err(|_| DecodeError::InternalError("Unexpected size of the output buffer"))?;
+
+        Ok((metadata, array))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decode::PixelFormat, decoder_builder, tests::SAMPLE_JXL};
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decode_to_array() -> TestResult {
+        let decoder = decoder_builder().build()?;
+        let (metadata, pixels) = decoder.decode_with::<u16>(SAMPLE_JXL)?;
+        let (_, array) = decoder.decode_to_array::<u16>(SAMPLE_JXL)?;
+        assert_eq!(
+            array.dim(),
+            (metadata.height as usize, metadata.width as usize, 4)
+        );
+        assert_eq!(array.as_slice(), Some(pixels.as_slice()));
+
+        let mut decoder = decoder_builder()
+            .pixel_format(PixelFormat {
+                num_channels: 3,
+                align: 64,
+                ..PixelFormat::default()
+            })
+            .build()?;
+        let (_, aligned) = decoder.decode_to_array::<u16>(SAMPLE_JXL)?;
+        assert_eq!(aligned.strides(), &[128, 3, 1]);
+        assert_eq!(
+            aligned.slice(ndarray::s![.., .., ..3]),
+            array.slice(ndarray::s![.., .., ..3])
+        );
+
+        decoder.pixel_format = Some(PixelFormat {
+            num_channels: 3,
+            align: 5,
+            ..PixelFormat::default()
+        });
+        let (_, odd) = decoder.decode_to_array::<u16>(SAMPLE_JXL)?;
+        assert!(odd.is_standard_layout());
+        assert_eq!(odd, aligned);
+
+        Ok(())
+    }
+}
@@ -29,18 +29,35 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 //! ```
 //!
 
-use std::ffi::c_void;
+use std::{
+    ffi::c_void,
+    ops::Range,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
 
+pub mod instrumented_runner;
+#[cfg(feature = "rayon")]
+pub mod rayon_runner;
+#[cfg(feature = "threads")]
 pub mod resizable_runner;
+pub mod sequential_runner;
+#[cfg(feature = "threads")]
 pub mod threads_runner;
+#[cfg(feature = "tokio")]
+pub mod tokio_runner;
 
-use jpegxl_sys::threads::parallel_runner::JxlParallelRunner;
+use jpegxl_sys::threads::parallel_runner::{
+    JxlParallelRunner, JXL_PARALLEL_RET_RUNNER_ERROR, JXL_PARALLEL_RET_SUCCESS,
+};
 
 pub use jpegxl_sys::threads::parallel_runner::{
     JxlParallelRetCode, JxlParallelRunFunction, JxlParallelRunInit,
 };
 
-use crate::decode::BasicInfo;
+use crate::{common::BasicInfo, memory::MemoryManager};
+
+#[cfg(feature = "threads")]
+use self::threads_runner::ThreadsRunner;
 
 /// JPEG XL Parallel Runner
 #[allow(clippy::module_name_repetitions)]
@@ -55,3 +72,228 @@ pub trait ParallelRunner {
     #[allow(unused_variables)]
     fn callback_basic_info(&self, basic_info: &BasicInfo) {}
 }
+
+/// Parallel runner written in safe Rust
+///
+/// Every type implementing this trait is also a [`ParallelRunner`], so it can be passed to
+/// the `parallel_runner` option of the encoder and decoder builders. Panics are caught before
+/// reaching libjxl, which then fails with a generic error.
+pub trait ParallelExecutor {
+    /// Maximum number of threads running tasks concurrently
+    fn num_threads(&self) -> usize;
+
+    /// Call `task(value, thread_id)` for every value in `range`, possibly in parallel,
+    /// and return after all calls have finished
+    ///
+    /// `thread_id` must be less than [`ParallelExecutor::num_threads`], and must not be used
+    /// by two concurrent calls.
+    fn run(&self, range: Range<u32>, task: &(dyn Fn(u32, usize) + Sync));
+
+    /// Callback function after getting basic info
+    #[allow(unused_variables)]
+    fn callback_basic_info(&self, basic_info: &BasicInfo) {}
+}
+
+impl<T: ParallelExecutor> ParallelRunner for T {
+    fn runner(&self) -> JxlParallelRunner {
+        run_executor::<T>
+    }
+
+    fn as_opaque_ptr(&self) -> *mut c_void {
+        (self as *const Self).cast_mut().cast()
+    }
+
+    fn callback_basic_info(&self, basic_info: &BasicInfo) {
+        ParallelExecutor::callback_basic_info(self, basic_info);
+    }
+}
+
+unsafe extern "C-unwind" fn run_executor<T: ParallelExecutor>(
+    runner_opaque: *mut c_void,
+    jpegxl_opaque: *mut c_void,
+    init: JxlParallelRunInit,
+    func: JxlParallelRunFunction,
+    start_range: u32,
+    end_range: u32,
+) -> JxlParallelRetCode {
+    let executor = unsafe { &*runner_opaque.cast::<T>() };
+
+    catch_unwind(AssertUnwindSafe(|| {
+        let num_threads = executor.num_threads();
+        let ret = unsafe { init(jpegxl_opaque, num_threads) };
+        if ret != JXL_PARALLEL_RET_SUCCESS {
+            return ret;
+        }
+
+        // Raw pointers are not `Sync`, but libjxl expects `func` to be called concurrently
+        let opaque = jpegxl_opaque as usize;
+        executor.run(start_range..end_range, &|value, thread_id| {
+            assert!(
+                thread_id < num_threads,
+                "thread id {thread_id} out of range, the executor has {num_threads} threads"
+            );
+            unsafe { func(opaque as *mut c_void, value, thread_id) };
+        });
+        JXL_PARALLEL_RET_SUCCESS
+    }))
+    .unwrap_or(JXL_PARALLEL_RET_RUNNER_ERROR)
+}
+
+/// How the encoder or decoder runs in parallel when no `parallel_runner` is set
+///
+/// Without the `threads` feature, e.g. on WebAssembly, every mode runs on the calling thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum ParallelMode {
+    /// Run on the calling thread
+    #[default]
+    Single,
+    /// Run on a [`ThreadsRunner`] with [`default_num_threads`] threads
+    Auto,
+    /// Run on a [`ThreadsRunner`] with the given number of threads
+    Threads(usize),
+}
+
+impl ParallelMode {
+    /// Return the number of threads of this mode
+    #[must_use]
+    pub fn num_threads(self) -> usize {
+        match self {
+            Self::Single => 1,
+            Self::Auto => default_num_threads(),
+            Self::Threads(num) => num,
+        }
+    }
+
+    /// Create the runner for this mode, `None` for [`ParallelMode::Single`]
+    #[cfg(feature = "threads")]
+    pub(crate) fn runner<E>(
+        self,
+        memory_manager: Option<&dyn MemoryManager>,
+        error: E,
+    ) -> Result<Option<ModeRunner<'_>>, E> {
+        match self {
+            Self::Single => Ok(None),
+            mode => ThreadsRunner::new(memory_manager, Some(mode.num_threads()))
+                .map(Some)
+                .ok_or(error),
+        }
+    }
+
+    /// Without threads, every mode runs on the calling thread
+    #[cfg(not(feature = "threads"))]
+    #[allow(clippy::unnecessary_wraps, clippy::unused_self)]
+    pub(crate) fn runner<E>(
+        self,
+        _memory_manager: Option<&dyn MemoryManager>,
+        _error: E,
+    ) -> Result<Option<ModeRunner<'_>>, E> {
+        Ok(None)
+    }
+}
+
+/// Runner created for a [`ParallelMode`]
+#[cfg(feature = "threads")]
+pub(crate) type ModeRunner<'mm> = ThreadsRunner<'mm>;
+
+/// Runner created for a [`ParallelMode`], which is never constructed without threads
+#[cfg(not(feature = "threads"))]
+pub(crate) enum ModeRunner<'mm> {
+    #[allow(dead_code)]
+    Never(std::convert::Infallible, std::marker::PhantomData<&'mm ()>),
+}
+
+#[cfg(not(feature = "threads"))]
+impl ParallelExecutor for ModeRunner<'_> {
+    fn num_threads(&self) -> usize {
+        match *self {
+            Self::Never(never, _) => match never {},
+        }
+    }
+
+    fn run(&self, _range: Range<u32>, _task: &(dyn Fn(u32, usize) + Sync)) {
+        match *self {
+            Self::Never(never, _) => match never {},
+        }
+    }
+}
+
+/// Return the default number of worker threads, based on the available hardware concurrency
+#[cfg(feature = "threads")]
+#[must_use]
+pub fn default_num_threads() -> usize {
+    use jpegxl_sys::threads::thread_parallel_runner::JxlThreadParallelRunnerDefaultNumWorkerThreads;
+
+    unsafe { JxlThreadParallelRunnerDefaultNumWorkerThreads() }
+}
+
+/// Return the default number of worker threads, based on the available hardware concurrency
+#[cfg(not(feature = "threads"))]
+#[must_use]
+pub fn default_num_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+}
+
+#[cfg(all(test, feature = "decoder"))]
+mod tests {
+    use std::thread;
+
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, DecodeError};
+
+    struct ScopedExecutor(usize);
+
+    impl ParallelExecutor for ScopedExecutor {
+        fn num_threads(&self) -> usize {
+            self.0
+        }
+
+        fn run(&self, range: Range<u32>, task: &(dyn Fn(u32, usize) + Sync)) {
+            thread::scope(|s| {
+                for thread_id in 0..self.0 {
+                    let range = range.clone();
+                    s.spawn(move || {
+                        range
+                            .skip(thread_id)
+                            .step_by(self.0)
+                            .for_each(|value| task(value, thread_id));
+                    });
+                }
+            });
+        }
+    }
+
+    struct PanickingExecutor;
+
+    impl ParallelExecutor for PanickingExecutor {
+        fn num_threads(&self) -> usize {
+            1
+        }
+
+        fn run(&self, _range: Range<u32>, _task: &(dyn Fn(u32, usize) + Sync)) {
+            panic!("executor failed");
+        }
+    }
+
+    #[test]
+    fn test_executor() -> TestResult {
+        let executor = ScopedExecutor(3);
+        let decoder = decoder_builder().parallel_runner(&executor).build()?;
+        let (_, reference) = decoder_builder()
+            .build()?
+            .decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        let (_, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        assert_eq!(pixels, reference);
+
+        let executor = PanickingExecutor;
+        let decoder = decoder_builder().parallel_runner(&executor).build()?;
+        assert!(matches!(
+            decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL),
+            Err(DecodeError::GenericError)
+        ));
+
+        Ok(())
+    }
+}
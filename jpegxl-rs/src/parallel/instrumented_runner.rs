@@ -0,0 +1,260 @@
+/*
+This file is part of jpegxl-rs.
+
+jpegxl-rs is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-rs is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parallel runner wrapper recording timing statistics
+
+use std::{
+    ffi::c_void,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{
+    JxlParallelRetCode, JxlParallelRunFunction, JxlParallelRunInit, JxlParallelRunner,
+    ParallelRunner,
+};
+
+use crate::common::BasicInfo;
+
+/// Timing statistics of one thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    /// Number of tasks run on the thread
+    pub tasks: u64,
+    /// Total time spent running tasks
+    pub busy_time: Duration,
+    /// Longest time spent on a single task
+    pub max_task_time: Duration,
+}
+
+/// Timing statistics collected by an [`InstrumentedRunner`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunnerStats {
+    /// Number of parallel runs requested by libjxl
+    pub runs: u64,
+    /// Total time from the start to the end of all runs
+    pub wall_time: Duration,
+    /// Statistics of each thread, indexed by the thread id given by the runner
+    pub threads: Vec<ThreadStats>,
+}
+
+impl RunnerStats {
+    /// Total number of tasks run
+    #[must_use]
+    pub fn tasks(&self) -> u64 {
+        self.threads.iter().map(|t| t.tasks).sum()
+    }
+
+    /// Total time spent running tasks over all threads
+    #[must_use]
+    pub fn busy_time(&self) -> Duration {
+        self.threads.iter().map(|t| t.busy_time).sum()
+    }
+
+    /// Ratio of the time spent running tasks to the time all threads were available,
+    /// between 0 and 1
+    #[must_use]
+    pub fn efficiency(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let available = self.wall_time.as_secs_f64() * self.threads.len() as f64;
+        if available > 0.0 {
+            self.busy_time().as_secs_f64() / available
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Parallel runner wrapper recording per-task and per-thread timing of another runner
+///
+/// Statistics accumulate over every encode or decode using the runner, until
+/// [`InstrumentedRunner::reset`] is called.
+pub struct InstrumentedRunner<R> {
+    inner: R,
+    stats: Mutex<RunnerStats>,
+}
+
+/// State of a single parallel run, passed to the wrapped runner as the opaque pointer
+struct RunState {
+    jpegxl_opaque: *mut c_void,
+    init: JxlParallelRunInit,
+    func: JxlParallelRunFunction,
+    threads: Vec<ThreadSlot>,
+}
+
+#[derive(Default)]
+struct ThreadSlot {
+    tasks: AtomicU64,
+    busy_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl<R: ParallelRunner> InstrumentedRunner<R> {
+    /// Wrap a runner
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            stats: Mutex::default(),
+        }
+    }
+
+    /// Return the statistics collected so far
+    pub fn stats(&self) -> RunnerStats {
+        self.stats
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Clear the collected statistics
+    pub fn reset(&self) {
+        *self.stats.lock().unwrap_or_else(PoisonError::into_inner) = RunnerStats::default();
+    }
+
+    /// Return the wrapped runner
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Unwrap the runner
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    unsafe extern "C-unwind" fn run(
+        runner_opaque: *mut c_void,
+        jpegxl_opaque: *mut c_void,
+        init: JxlParallelRunInit,
+        func: JxlParallelRunFunction,
+        start_range: u32,
+        end_range: u32,
+    ) -> JxlParallelRetCode {
+        let this = unsafe { &*runner_opaque.cast::<Self>() };
+        let mut state = RunState {
+            jpegxl_opaque,
+            init,
+            func,
+            threads: Vec::new(),
+        };
+
+        let started = Instant::now();
+        let ret = unsafe {
+            (this.inner.runner())(
+                this.inner.as_opaque_ptr(),
+                std::ptr::addr_of_mut!(state).cast(),
+                Self::init,
+                Self::func,
+                start_range,
+                end_range,
+            )
+        };
+        let wall_time = started.elapsed();
+
+        let mut totals = this.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        totals.runs += 1;
+        totals.wall_time += wall_time;
+        if totals.threads.len() < state.threads.len() {
+            totals
+                .threads
+                .resize(state.threads.len(), ThreadStats::default());
+        }
+        for (total, slot) in totals.threads.iter_mut().zip(state.threads) {
+            let max_task_time = Duration::from_nanos(slot.max_nanos.into_inner());
+            total.tasks += slot.tasks.into_inner();
+            total.busy_time += Duration::from_nanos(slot.busy_nanos.into_inner());
+            total.max_task_time = total.max_task_time.max(max_task_time);
+        }
+
+        ret
+    }
+
+    unsafe extern "C-unwind" fn init(
+        opaque: *mut c_void,
+        num_threads: usize,
+    ) -> JxlParallelRetCode {
+        // Called once before any task, so there is no concurrent access to the state
+        let state = unsafe { &mut *opaque.cast::<RunState>() };
+        state.threads = (0..num_threads).map(|_| ThreadSlot::default()).collect();
+        unsafe { (state.init)(state.jpegxl_opaque, num_threads) }
+    }
+
+    unsafe extern "C-unwind" fn func(opaque: *mut c_void, value: u32, thread_id: usize) {
+        let state = unsafe { &*opaque.cast::<RunState>() };
+
+        let start = Instant::now();
+        unsafe { (state.func)(state.jpegxl_opaque, value, thread_id) };
+        #[allow(clippy::cast_possible_truncation)]
+        let nanos = start.elapsed().as_nanos() as u64;
+
+        if let Some(slot) = state.threads.get(thread_id) {
+            slot.tasks.fetch_add(1, Ordering::Relaxed);
+            slot.busy_nanos.fetch_add(nanos, Ordering::Relaxed);
+            slot.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<R: ParallelRunner> ParallelRunner for InstrumentedRunner<R> {
+    fn runner(&self) -> JxlParallelRunner {
+        Self::run
+    }
+
+    fn as_opaque_ptr(&self) -> *mut c_void {
+        (self as *const Self).cast_mut().cast()
+    }
+
+    fn callback_basic_info(&self, basic_info: &BasicInfo) {
+        self.inner.callback_basic_info(basic_info);
+    }
+}
+
+#[cfg(all(test, feature = "threads", feature = "decoder", feature = "encoder"))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        decoder_builder, encode::EncoderResult, encoder_builder, ResizableRunner, ThreadsRunner,
+    };
+
+    #[test]
+    fn test_instrumented_runner() -> TestResult {
+        let runner = InstrumentedRunner::new(ThreadsRunner::with_threads(2).expect("runner"));
+        let decoder = decoder_builder().parallel_runner(&runner).build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+        let stats = runner.stats();
+        assert!(stats.runs > 0);
+        assert_eq!(stats.threads.len(), 2);
+        assert!(stats.tasks() >= stats.runs);
+        assert!(stats.busy_time() <= stats.wall_time * 2);
+        assert!((0.0..=1.0).contains(&stats.efficiency()));
+
+        runner.reset();
+        assert_eq!(runner.stats(), RunnerStats::default());
+
+        let runner = InstrumentedRunner::new(ResizableRunner::default());
+        let mut encoder = encoder_builder().parallel_runner(&runner).build()?;
+        let _: EncoderResult<u8> = encoder.encode(&pixels, metadata.width, metadata.height)?;
+        assert!(runner.stats().tasks() > 0);
+
+        Ok(())
+    }
+}
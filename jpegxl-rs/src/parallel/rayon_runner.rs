@@ -0,0 +1,83 @@
+/*
+This file is part of jpegxl-rs.
+
+jpegxl-rs is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-rs is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parallel runner on top of a [`rayon`] thread pool
+
+use std::ops::Range;
+
+use rayon::{prelude::*, ThreadPool};
+
+use super::ParallelExecutor;
+
+/// Parallel runner using a [`rayon`] thread pool, to share the threads of an application
+/// which already uses rayon
+#[derive(Default)]
+pub struct RayonRunner<'pool> {
+    pool: Option<&'pool ThreadPool>,
+}
+
+impl<'pool> RayonRunner<'pool> {
+    /// Construct with a thread pool
+    ///
+    /// Use [`RayonRunner::default`] for the global thread pool.
+    #[must_use]
+    pub fn new(pool: &'pool ThreadPool) -> Self {
+        Self { pool: Some(pool) }
+    }
+}
+
+impl ParallelExecutor for RayonRunner<'_> {
+    fn num_threads(&self) -> usize {
+        self.pool
+            .map_or_else(rayon::current_num_threads, ThreadPool::current_num_threads)
+    }
+
+    fn run(&self, range: Range<u32>, task: &(dyn Fn(u32, usize) + Sync)) {
+        let job = || {
+            range.into_par_iter().for_each(|value| {
+                task(value, rayon::current_thread_index().unwrap_or(0));
+            });
+        };
+        match self.pool {
+            Some(pool) => pool.install(job),
+            None => job(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decoder", feature = "encoder"))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, encode::EncoderResult, encoder_builder};
+
+    #[test]
+    fn test_rayon_runner() -> TestResult {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build()?;
+        let runner = RayonRunner::new(&pool);
+
+        let decoder = decoder_builder().parallel_runner(&runner).build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+        let global = RayonRunner::default();
+        let mut encoder = encoder_builder().parallel_runner(&global).build()?;
+        let _: EncoderResult<u8> = encoder.encode(&pixels, metadata.width, metadata.height)?;
+
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@ use jpegxl_sys::threads::resizable_parallel_runner as api;
 
 use super::{JxlParallelRunner, ParallelRunner};
 
-use crate::{decode::BasicInfo, memory::MemoryManager};
+use crate::{common::BasicInfo, memory::MemoryManager};
 
 /// Wrapper for resizable thread pool implementation with C++ standard library
 pub struct ResizableRunner<'mm> {
@@ -50,10 +50,23 @@ impl<'mm> ResizableRunner<'mm> {
         }
     }
 
+    /// Return the suggested number of threads for an image of the given size
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn suggested_num_threads(width: u64, height: u64) -> usize {
+        unsafe { api::JxlResizableParallelRunnerSuggestThreads(width, height) as usize }
+    }
+
+    /// Set number of threads
+    pub fn set_threads(&self, num_threads: usize) {
+        unsafe { api::JxlResizableParallelRunnerSetThreads(self.runner_ptr, num_threads) };
+    }
+
     /// Set number of threads depending on the size of the image
+    ///
+    /// This is done automatically when the basic info of the image is decoded.
     pub fn set_num_threads(&self, width: u64, height: u64) {
-        let num = unsafe { api::JxlResizableParallelRunnerSuggestThreads(width, height) };
-        unsafe { api::JxlResizableParallelRunnerSetThreads(self.runner_ptr, num as usize) };
+        self.set_threads(Self::suggested_num_threads(width, height));
     }
 }
 
@@ -99,5 +112,8 @@ mod tests {
         let memory_manager = BumpManager::new(1024);
         let parallel_runner = ResizableRunner::new(Some(&memory_manager));
         assert!(parallel_runner.is_some());
+
+        assert!(ResizableRunner::suggested_num_threads(4096, 4096) >= 1);
+        ResizableRunner::default().set_threads(2);
     }
 }
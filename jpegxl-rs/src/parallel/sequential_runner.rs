@@ -0,0 +1,61 @@
+/*
+This file is part of jpegxl-rs.
+
+jpegxl-rs is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-rs is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Deterministic runner without threads
+
+use std::ops::Range;
+
+use super::ParallelExecutor;
+
+/// Parallel runner running every task in order on the calling thread
+///
+/// Results and task order do not depend on the parallelism of the machine, which makes it
+/// suitable for golden-image tests and fuzzing. Unlike [`ParallelMode::Single`](super::ParallelMode::Single),
+/// it can be wrapped by other runners such as
+/// [`InstrumentedRunner`](super::instrumented_runner::InstrumentedRunner).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SequentialRunner;
+
+impl ParallelExecutor for SequentialRunner {
+    fn num_threads(&self) -> usize {
+        1
+    }
+
+    fn run(&self, range: Range<u32>, task: &(dyn Fn(u32, usize) + Sync)) {
+        range.for_each(|value| task(value, 0));
+    }
+}
+
+#[cfg(all(test, feature = "decoder"))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, InstrumentedRunner};
+
+    #[test]
+    fn test_sequential_runner() -> TestResult {
+        let runner = InstrumentedRunner::new(SequentialRunner);
+        let decoder = decoder_builder().parallel_runner(&runner).build()?;
+        let (_, first) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        let (_, second) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        assert_eq!(first, second);
+        assert_eq!(runner.stats().threads.len(), 1);
+
+        Ok(())
+    }
+}
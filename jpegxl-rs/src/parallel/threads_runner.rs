@@ -56,6 +56,12 @@ impl<'mm> ThreadsRunner<'mm> {
             })
         }
     }
+
+    /// Construct with number of threads, using the default memory manager
+    #[must_use]
+    pub fn with_threads(num_threads: usize) -> Option<Self> {
+        Self::new(None, Some(num_threads))
+    }
 }
 
 impl Default for ThreadsRunner<'_> {
@@ -99,5 +105,7 @@ mod tests {
         let memory_manager = BumpManager::new(1024);
         let parallel_runner = ThreadsRunner::new(Some(&memory_manager), Some(10));
         assert!(parallel_runner.is_some());
+
+        assert!(ThreadsRunner::with_threads(2).is_some());
     }
 }
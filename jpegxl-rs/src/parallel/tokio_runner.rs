@@ -0,0 +1,145 @@
+/*
+This file is part of jpegxl-rs.
+
+jpegxl-rs is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-rs is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Parallel runner on top of the blocking pool of a [`tokio`] runtime
+
+use std::{
+    num::NonZeroUsize,
+    ops::Range,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use tokio::runtime::Handle;
+
+use super::ParallelExecutor;
+
+/// Parallel runner dispatching work onto the blocking pool of a [`tokio`] runtime, so async
+/// services can decode or encode without managing another thread pool
+///
+/// The calling thread works on the tasks as well, so the runner makes progress even when the
+/// blocking pool is saturated. Encoding or decoding still blocks the calling thread, so it
+/// should be done inside [`tokio::task::spawn_blocking`]. Use the handle of a dedicated runtime
+/// to keep libjxl work away from other blocking tasks.
+#[derive(Debug, Clone)]
+pub struct TokioRunner {
+    handle: Handle,
+    num_threads: usize,
+}
+
+impl TokioRunner {
+    /// Construct with a runtime handle and the maximum number of threads to use
+    #[must_use]
+    pub fn new(handle: Handle, num_threads: usize) -> Self {
+        Self {
+            handle,
+            num_threads: num_threads.max(1),
+        }
+    }
+
+    /// Construct with the handle of the current runtime and one thread per available core
+    ///
+    /// Return `None` if not called within a runtime.
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        let num_threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Handle::try_current()
+            .ok()
+            .map(|handle| Self::new(handle, num_threads))
+    }
+}
+
+impl ParallelExecutor for TokioRunner {
+    fn num_threads(&self) -> usize {
+        self.num_threads
+    }
+
+    fn run(&self, range: Range<u32>, task: &(dyn Fn(u32, usize) + Sync)) {
+        let next = AtomicU32::new(range.start);
+        let work = |thread_id| loop {
+            let value = next.fetch_add(1, Ordering::Relaxed);
+            if value >= range.end {
+                break;
+            }
+            task(value, thread_id);
+        };
+        let work: &(dyn Fn(usize) + Sync) = &work;
+        // SAFETY: every spawned closure either runs to completion or is dropped before its
+        // sender is, and all senders are gone before this function returns, so `work` is never
+        // used after it goes out of scope.
+        let work: &'static (dyn Fn(usize) + Sync) = unsafe { std::mem::transmute(work) };
+
+        let (tx, rx) = mpsc::channel();
+        for thread_id in 1..self.num_threads {
+            let tx = tx.clone();
+            self.handle.spawn_blocking(move || {
+                let result = catch_unwind(AssertUnwindSafe(|| work(thread_id)));
+                let _ = tx.send(result);
+            });
+        }
+        drop(tx);
+
+        let result = catch_unwind(AssertUnwindSafe(|| work(0)));
+        // Wait for all spawned tasks, even those which never ran because the runtime shut down
+        let results: Vec<_> = rx.iter().collect();
+
+        for result in std::iter::once(result).chain(results) {
+            if let Err(e) = result {
+                resume_unwind(e);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "decoder", feature = "encoder"))]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, encode::EncoderResult, encoder_builder};
+
+    #[test]
+    fn test_tokio_runner() -> TestResult {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        assert!(TokioRunner::current().is_none());
+
+        let runner = TokioRunner::new(runtime.handle().clone(), 3);
+        let decoder = decoder_builder().parallel_runner(&runner).build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+
+        let (_, reference) = decoder_builder()
+            .build()?
+            .decode_with::<u8>(crate::tests::SAMPLE_JXL)?;
+        assert_eq!(pixels, reference);
+
+        let result = runtime.block_on(async move {
+            tokio::task::spawn_blocking(move || {
+                let runner = TokioRunner::current().expect("inside a runtime");
+                let mut encoder = encoder_builder().parallel_runner(&runner).build()?;
+                encoder.encode::<u8, u8>(&pixels, metadata.width, metadata.height)
+            })
+            .await
+        })??;
+        let _: EncoderResult<u8> = result;
+
+        Ok(())
+    }
+}
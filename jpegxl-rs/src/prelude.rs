@@ -0,0 +1,66 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Commonly used builders, runners, pixel types and errors in a single `use`
+//!
+//! ```
+//! use jpegxl_rs::prelude::*;
+//!
+//! # #[cfg(not(feature = "decoder"))]
+//! # fn main() {}
+//! # #[cfg(feature = "decoder")]
+//! # fn main() -> Result<(), DecodeError> {
+//! # let sample = std::fs::read("../samples/sample.jxl")?;
+//! let decoder = decoder_builder()
+//!     .parallel_mode(ParallelMode::Auto)
+//!     .build()?;
+//! let (metadata, pixels): (Metadata, Vec<f16>) = decoder.decode_with(&sample)?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use half::f16;
+
+pub use crate::{
+    common::{convert_pixels, Endianness, PixelType},
+    errors::{DecodeError, EncodeError, ErrorKind},
+    memory::MemoryManager,
+    parallel::{ParallelMode, ParallelRunner},
+    InstrumentedRunner, SequentialRunner,
+};
+
+#[cfg(all(feature = "jpeg", feature = "decoder"))]
+pub use crate::decode::Data;
+#[cfg(feature = "decoder")]
+pub use crate::decode::{
+    decoder_builder, DecoderResult, JxlDecoder, JxlDecoderBuilder, Metadata, PixelFormat, Pixels,
+};
+#[cfg(feature = "encoder")]
+pub use crate::encode::{
+    encoder_builder, ColorEncoding, EncoderFrame, EncoderResult, EncoderSpeed, JxlEncoder,
+    JxlEncoderBuilder,
+};
+#[cfg(all(feature = "jpeg", feature = "decoder", feature = "encoder"))]
+pub use crate::errors::TranscodeError;
+#[cfg(feature = "image")]
+pub use crate::image::ToDynamic;
+#[cfg(feature = "rayon")]
+pub use crate::RayonRunner;
+#[cfg(feature = "tokio")]
+pub use crate::TokioRunner;
+#[cfg(feature = "threads")]
+pub use crate::{ResizableRunner, ThreadsRunner};
@@ -0,0 +1,144 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `rgb` crate integration
+
+use rgb::{
+    alt::{Gray, GrayAlpha},
+    RGB, RGBA,
+};
+
+use crate::{
+    common::PixelType,
+    decode::{JxlDecoder, Metadata, PixelFormat},
+    DecodeError,
+};
+
+/// Pixel type with a fixed number of channels, which the decoder outputs
+pub trait RgbPixel: Copy {
+    /// Type of each channel
+    type Component: PixelType;
+
+    /// Number of channels
+    const CHANNELS: u32;
+
+    /// Construct from `CHANNELS` components
+    fn from_components(components: &[Self::Component]) -> Self;
+}
+
+impl<T: PixelType> RgbPixel for Gray<T> {
+    type Component = T;
+    const CHANNELS: u32 = 1;
+
+    fn from_components(c: &[T]) -> Self {
+        Gray(c[0])
+    }
+}
+
+impl<T: PixelType> RgbPixel for GrayAlpha<T> {
+    type Component = T;
+    const CHANNELS: u32 = 2;
+
+    fn from_components(c: &[T]) -> Self {
+        GrayAlpha(c[0], c[1])
+    }
+}
+
+impl<T: PixelType> RgbPixel for RGB<T> {
+    type Component = T;
+    const CHANNELS: u32 = 3;
+
+    fn from_components(c: &[T]) -> Self {
+        RGB::new(c[0], c[1], c[2])
+    }
+}
+
+impl<T: PixelType> RgbPixel for RGBA<T> {
+    type Component = T;
+    const CHANNELS: u32 = 4;
+
+    fn from_components(c: &[T]) -> Self {
+        RGBA::new(c[0], c[1], c[2], c[3])
+    }
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Decode the JPEG XL image to typed pixels, e.g. [`rgb::RGBA8`]
+    ///
+    /// The number of channels is given by the pixel type, overriding `pixel_format` for this
+    /// call. Rows are never padded.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding fails
+    pub fn decode_rgb<P: RgbPixel>(&self, data: &[u8]) -> Result<(Metadata, Vec<P>), DecodeError> {
+        let format = PixelFormat {
+            num_channels: P::CHANNELS,
+            align: 0,
+            ..self.pixel_format.unwrap_or_default()
+        };
+        let (metadata, components) = self.decode_with_format::<P::Component>(data, Some(format))?;
+        let pixels = components
+            .chunks_exact(P::CHANNELS as usize)
+            .map(P::from_components)
+            .collect();
+        Ok((metadata, pixels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use rgb::{RGB16, RGB8, RGBA8};
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{
+        decoder_builder,
+        tests::{SAMPLE_JXL, SAMPLE_JXL_GRAY},
+    };
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decode_rgb() -> TestResult {
+        let decoder = decoder_builder().build()?;
+        let (metadata, components) = decoder.decode_with::<u8>(SAMPLE_JXL)?;
+
+        let (_, pixels) = decoder.decode_rgb::<RGBA8>(SAMPLE_JXL)?;
+        assert_eq!(pixels.len(), (metadata.width * metadata.height) as usize);
+        assert_eq!(
+            pixels[0],
+            RGBA8::new(components[0], components[1], components[2], 255)
+        );
+
+        let (_, pixels) = decoder.decode_rgb::<RGB8>(SAMPLE_JXL)?;
+        assert_eq!(
+            pixels[0],
+            RGB8::new(components[0], components[1], components[2])
+        );
+        assert!(decoder.pixel_format.is_none());
+
+        let (_, pixels) = decoder.decode_rgb::<RGB16>(SAMPLE_JXL)?;
+        assert_eq!(pixels.len(), (metadata.width * metadata.height) as usize);
+
+        let (_, pixels) = decoder.decode_rgb::<Gray<u8>>(SAMPLE_JXL_GRAY)?;
+        let (_, components) = decoder.decode_with::<u8>(SAMPLE_JXL_GRAY)?;
+        assert_eq!(pixels.len(), components.len());
+        decoder.decode_rgb::<GrayAlpha<f32>>(SAMPLE_JXL_GRAY)?;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,22 @@
+// `TestError` is uninhabited, so newer toolchains flag `return Err(..)` as unreachable
+#![allow(unreachable_code)]
+// Samples are shared by the tests of both features
+#![cfg_attr(not(all(feature = "decoder", feature = "encoder")), allow(dead_code))]
+
+#[cfg(feature = "decoder")]
 mod decode;
+#[cfg(all(feature = "decoder", feature = "encoder"))]
 mod encode;
 
 pub const SAMPLE_PNG: &[u8] = include_bytes!("../../samples/sample.png");
-const SAMPLE_JPEG: &[u8] = include_bytes!("../../samples/sample.jpg");
-const SAMPLE_EXIF: &[u8] = include_bytes!("../../samples/sample.exif");
+#[cfg(feature = "jpeg")]
+pub const SAMPLE_JPEG: &[u8] = include_bytes!("../../samples/sample.jpg");
+#[cfg(feature = "boxes")]
+pub const SAMPLE_EXIF: &[u8] = include_bytes!("../../samples/sample.exif");
+#[cfg(feature = "boxes")]
 const SAMPLE_XMP: &[u8] = include_bytes!("../../samples/sample.xmp");
 pub const SAMPLE_JXL: &[u8] = include_bytes!("../../samples/sample.jxl");
-const SAMPLE_JXL_JPEG: &[u8] = include_bytes!("../../samples/sample_jpg.jxl");
+#[cfg(feature = "jpeg")]
+pub const SAMPLE_JXL_JPEG: &[u8] = include_bytes!("../../samples/sample_jpg.jxl");
 pub const SAMPLE_JXL_GRAY: &[u8] = include_bytes!("../../samples/sample_grey.jxl");
 const SAMPLE_JXL_2BIT: &[u8] = include_bytes!("../../samples/2bit.jxl");
@@ -15,18 +15,21 @@
  * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use std::io::Cursor;
-
 use half::f16;
+#[cfg(feature = "jpeg")]
 use image::ImageDecoder;
 use pretty_assertions::assert_eq;
 use testresult::TestResult;
 
+#[cfg(feature = "jpeg")]
+use crate::decode::Data;
+use crate::parallel::ParallelMode;
 use crate::{
     common::Endianness,
-    decode::{Data, Metadata, PixelFormat, Pixels},
+    decode::{Metadata, PixelFormat, Pixels},
     decoder_builder, DecodeError,
 };
+#[cfg(feature = "threads")]
 use crate::{ResizableRunner, ThreadsRunner};
 
 #[test]
@@ -41,6 +44,22 @@ fn invalid() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn reuse_after_error() -> TestResult {
+    let decoder = decoder_builder().build()?;
+
+    let truncated = &super::SAMPLE_JXL[..super::SAMPLE_JXL.len() / 2];
+    assert!(decoder.decode(truncated).is_err());
+    decoder.decode(super::SAMPLE_JXL)?;
+
+    let mut corrupted = super::SAMPLE_JXL.to_vec();
+    corrupted[16..].fill(0xff);
+    assert!(decoder.decode(&corrupted).is_err());
+    decoder.decode(super::SAMPLE_JXL)?;
+
+    Ok(())
+}
+
 #[test]
 fn simple() -> TestResult {
     let decoder = decoder_builder().icc_profile(true).build()?;
@@ -120,6 +139,7 @@ fn pixel_types() -> TestResult {
 }
 
 #[test]
+#[cfg(feature = "jpeg")]
 fn jpeg() -> TestResult {
     let decoder = decoder_builder().init_jpeg_buffer(512).build()?;
 
@@ -128,7 +148,7 @@ fn jpeg() -> TestResult {
         return Err("Failed to reconstruct".into());
     };
 
-    let jpeg = image::codecs::jpeg::JpegDecoder::new(Cursor::new(data))?;
+    let jpeg = image::codecs::jpeg::JpegDecoder::new(std::io::Cursor::new(data))?;
     let mut v = vec![0; jpeg.total_bytes().try_into().unwrap()];
     jpeg.read_image(&mut v)?;
 
@@ -139,6 +159,7 @@ fn jpeg() -> TestResult {
 }
 
 #[test]
+#[cfg(feature = "threads")]
 fn builder() -> TestResult {
     use crate::decode::ProgressiveDetail;
 
@@ -148,7 +169,7 @@ fn builder() -> TestResult {
         .pixel_format(PixelFormat {
             num_channels: 3,
             endianness: Endianness::Big,
-            align: 10,
+            align: 16,
         })
         .desired_intensity_target(0.5)
         .coalescing(false)
@@ -178,3 +199,335 @@ fn builder() -> TestResult {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "encoder")]
+fn parallel_mode() -> TestResult {
+    assert!(crate::parallel::default_num_threads() >= 1);
+    assert_eq!(ParallelMode::Threads(3).num_threads(), 3);
+
+    for mode in [ParallelMode::Auto, ParallelMode::Threads(2)] {
+        let decoder = decoder_builder().parallel_mode(mode).build()?;
+        let (metadata, pixels) = decoder.decode_with::<u8>(super::SAMPLE_JXL)?;
+
+        let mut encoder = crate::encoder_builder().parallel_mode(mode).build()?;
+        let _: crate::encode::EncoderResult<u8> =
+            encoder.encode(&pixels, metadata.width, metadata.height)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn cancellation() -> TestResult {
+    let token = crate::decode::CancellationToken::new();
+    let decoder = decoder_builder().cancellation(token.clone()).build()?;
+
+    token.cancel();
+    assert!(matches!(
+        decoder.decode(super::SAMPLE_JXL),
+        Err(DecodeError::Cancelled)
+    ));
+
+    token.reset();
+    decoder.decode(super::SAMPLE_JXL)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "encoder", feature = "tokio"))]
+fn decode_async() -> TestResult {
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let (metadata, pixels) =
+        runtime.block_on(crate::decode_async(super::SAMPLE_JXL.to_vec(), |builder| {
+            builder.pixel_format(PixelFormat {
+                num_channels: 3,
+                ..PixelFormat::default()
+            });
+        }))?;
+    let Pixels::Uint16(pixels) = pixels else {
+        panic!("Expected u16 pixels");
+    };
+    assert_eq!(
+        pixels.len(),
+        (metadata.width * metadata.height * 3) as usize
+    );
+
+    let result: crate::encode::EncoderResult<u16> = runtime.block_on(crate::encode_async(
+        pixels,
+        metadata.width,
+        metadata.height,
+        |_| {},
+    ))?;
+    decoder_builder().build()?.decode(&result)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn reader() -> TestResult {
+    /// Reader returning a few bytes at a time, like a pipe
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(1000);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let (metadata, Pixels::Uint16(reference)) =
+        decoder_builder().build()?.decode(super::SAMPLE_JXL)?
+    else {
+        panic!("Failed to decode");
+    };
+    let (_, pixels) = crate::decode_reader(Trickle(super::SAMPLE_JXL))?;
+    assert!(matches!(pixels, Pixels::Uint16(p) if p == reference));
+
+    let mut output = vec![];
+    crate::encode_writer(
+        &crate::encode::EncoderFrame::new(&reference).num_channels(4),
+        metadata.width,
+        metadata.height,
+        &mut output,
+    )?;
+    crate::decode_reader(std::io::Cursor::new(output))?;
+
+    let truncated = &super::SAMPLE_JXL[..super::SAMPLE_JXL.len() / 2];
+    assert!(matches!(
+        crate::decode_reader(Trickle(truncated)),
+        Err(DecodeError::GenericError)
+    ));
+    assert!(matches!(
+        crate::decode_reader(Trickle(&[0; 64])),
+        Err(DecodeError::InvalidInput)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_configuration() {
+    for format in [
+        PixelFormat {
+            num_channels: 5,
+            ..PixelFormat::default()
+        },
+        PixelFormat {
+            align: 3,
+            ..PixelFormat::default()
+        },
+    ] {
+        assert!(matches!(
+            decoder_builder().pixel_format(format).build(),
+            Err(DecodeError::InvalidConfiguration(_))
+        ));
+    }
+    assert!(matches!(
+        decoder_builder().init_jpeg_buffer(0).build(),
+        Err(DecodeError::InvalidConfiguration(_))
+    ));
+    assert!(decoder_builder()
+        .pixel_format(PixelFormat {
+            num_channels: 4,
+            align: 64,
+            ..PixelFormat::default()
+        })
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn config() -> TestResult {
+    let decoder = decoder_builder()
+        .coalescing(false)
+        .parallel_mode(ParallelMode::Auto)
+        .build()?;
+
+    let config = decoder.config();
+    assert!(!config.coalescing);
+    assert!(config.render_spotcolors);
+    assert_eq!(config.init_jpeg_buffer, 512 * 1024);
+    assert_eq!(config.parallel_mode, ParallelMode::Auto);
+    assert!(!config.custom_parallel_runner);
+    assert!(format!("{decoder:?}").contains("coalescing: false"));
+
+    Ok(())
+}
+
+#[test]
+fn strict() -> TestResult {
+    use crate::decode::DecodeLimits;
+
+    let decoder = decoder_builder().strict().build()?;
+    assert_eq!(decoder.config().limits, Some(DecodeLimits::STRICT));
+    decoder.decode(super::SAMPLE_JXL)?;
+
+    let decoder = decoder_builder()
+        .limits(DecodeLimits {
+            max_dimension: 16,
+            ..DecodeLimits::STRICT
+        })
+        .build()?;
+    assert!(matches!(
+        decoder.decode(super::SAMPLE_JXL),
+        Err(DecodeError::LimitExceeded(_))
+    ));
+    // The decoder is reset and can be reused
+    assert!(matches!(
+        decoder.decode(super::SAMPLE_JXL),
+        Err(DecodeError::LimitExceeded(_))
+    ));
+
+    let decoder = decoder_builder()
+        .limits(DecodeLimits {
+            max_frames: 0,
+            ..DecodeLimits::STRICT
+        })
+        .build()?;
+    for _ in 0..2 {
+        assert!(matches!(
+            decoder.decode_progressive(super::SAMPLE_JXL, |_| {}),
+            Err(DecodeError::LimitExceeded(_))
+        ));
+    }
+
+    let decoder = decoder_builder()
+        .icc_profile(true)
+        .limits(DecodeLimits {
+            max_box_size: 16,
+            ..DecodeLimits::STRICT
+        })
+        .build()?;
+    assert!(matches!(
+        decoder.decode(super::SAMPLE_JXL),
+        Err(DecodeError::LimitExceeded(_))
+    ));
+    decoder.decode_progressive(super::SAMPLE_JXL, |_| {})?;
+
+    Ok(())
+}
+
+#[test]
+fn warnings() -> TestResult {
+    use crate::decode::DecodeWarning;
+
+    let decoder = decoder_builder().build()?;
+    let (metadata, _) = decoder.decode(super::SAMPLE_JXL)?;
+    assert!(metadata.warnings.is_empty());
+
+    let (metadata, _) = decoder.decode_with::<u8>(super::SAMPLE_JXL)?;
+    assert!(matches!(
+        metadata.warnings[..],
+        [DecodeWarning::PrecisionLoss { output_bits: 8, .. }]
+    ));
+
+    let decoder = decoder_builder()
+        .pixel_format(PixelFormat {
+            num_channels: 3,
+            ..PixelFormat::default()
+        })
+        .build()?;
+    let (metadata, _) = decoder.decode(super::SAMPLE_JXL)?;
+    assert_eq!(metadata.warnings, vec![DecodeWarning::AlphaDropped]);
+
+    #[cfg(feature = "jpeg")]
+    {
+        let (metadata, _) = decoder.reconstruct(super::SAMPLE_JXL_JPEG)?;
+        assert!(metadata.warnings.is_empty());
+        let (metadata, _) = decoder.reconstruct(super::SAMPLE_JXL)?;
+        assert!(metadata
+            .warnings
+            .contains(&DecodeWarning::NoJpegReconstruction));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "encoder")]
+fn file() -> TestResult {
+    let (metadata, Pixels::Uint16(reference)) = crate::decode_bytes(super::SAMPLE_JXL)? else {
+        panic!("Failed to decode");
+    };
+
+    let path = std::env::temp_dir().join(format!("jpegxl-rs-{}.jxl", std::process::id()));
+    crate::encode_file(
+        &path,
+        &crate::encode::EncoderFrame::new(&reference).num_channels(4),
+        metadata.width,
+        metadata.height,
+    )?;
+    let result = crate::decode_file(&path);
+    std::fs::remove_file(&path)?;
+    let (decoded, _) = result?;
+    assert_eq!(
+        (decoded.width, decoded.height),
+        (metadata.width, metadata.height)
+    );
+
+    assert!(matches!(
+        crate::decode_file(std::env::temp_dir().join("jpegxl-rs-missing.jxl")),
+        Err(DecodeError::Io(_))
+    ));
+
+    Ok(())
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[test]
+fn linear_output() -> TestResult {
+    let decoder = decoder_builder().linear_output(true).build()?;
+    assert!(decoder.config().linear_output);
+
+    // XYB images are converted by libjxl, from the XYB values rather than the sRGB output
+    let (_, linear) = decoder.decode_with::<f32>(super::SAMPLE_JXL)?;
+    let (_, pixels) = decoder_builder()
+        .build()?
+        .decode_with::<f32>(super::SAMPLE_JXL)?;
+    assert_eq!(linear.len(), pixels.len());
+    for (l, p) in linear.iter().zip(&pixels).step_by(97) {
+        assert!((l - srgb_to_linear(*p)).abs() < 1e-2, "{l} != {p}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn png16() -> TestResult {
+    let decoder = decoder_builder()
+        .pixel_format(PixelFormat {
+            align: 64,
+            ..PixelFormat::default()
+        })
+        .build()?;
+    let (metadata, bytes) = decoder.decode_png16(super::SAMPLE_JXL)?;
+    let (_, pixels) = decoder_builder()
+        .build()?
+        .decode_with::<u16>(super::SAMPLE_JXL)?;
+
+    assert_eq!(
+        bytes.len(),
+        metadata.width as usize * metadata.height as usize * 4 * 2
+    );
+    let samples: Vec<_> = bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(samples, pixels);
+    // The pixel format of the decoder is left untouched
+    assert_eq!(decoder.config().pixel_format.map(|f| f.align), Some(64));
+
+    Ok(())
+}
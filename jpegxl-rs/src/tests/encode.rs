@@ -15,18 +15,29 @@
  * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
  */
 
+use std::time::Duration;
+
 use half::f16;
 use image::DynamicImage;
 use pretty_assertions::assert_eq;
 use testresult::TestResult;
 
+#[cfg(all(feature = "threads", feature = "jpeg"))]
 use crate::decode::Data;
+use crate::decode::{Orientation, Pixels};
+use crate::encode::EncoderSpeed;
+#[cfg(feature = "boxes")]
+use crate::encode::{BoxType, Metadata};
 use crate::{
     decoder_builder,
-    encode::{ColorEncoding, EncoderFrame, EncoderResult, Metadata},
-    encoder_builder, Endianness,
+    encode::{
+        Animation, BlendMode, CancellationToken, ColorEncoding, EncoderFrame, EncoderPreset,
+        EncoderResult, ExtraChannel, FrameSetting, UpsamplingMode,
+    },
+    encoder_builder, DecodeError, EncodeError, Endianness,
 };
-use crate::{encode::EncoderSpeed, ResizableRunner, ThreadsRunner};
+#[cfg(feature = "threads")]
+use crate::{ResizableRunner, ThreadsRunner};
 
 fn get_sample() -> DynamicImage {
     image::load_from_memory_with_format(super::SAMPLE_PNG, image::ImageFormat::Png)
@@ -48,6 +59,7 @@ fn simple() -> TestResult {
 }
 
 #[test]
+#[cfg(all(feature = "threads", feature = "jpeg"))]
 fn jpeg() -> TestResult {
     let threads_runner = ThreadsRunner::default();
     let mut encoder = encoder_builder()
@@ -69,6 +81,7 @@ fn jpeg() -> TestResult {
 }
 
 #[test]
+#[cfg(feature = "boxes")]
 fn metadata() -> TestResult {
     let sample = get_sample().to_rgb8();
     let mut encoder = encoder_builder().build()?;
@@ -82,6 +95,78 @@ fn metadata() -> TestResult {
 }
 
 #[test]
+#[cfg(feature = "boxes")]
+fn boxes() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let xmp = std::str::from_utf8(super::SAMPLE_XMP)?;
+    let decoder = decoder_builder().build()?;
+
+    for compress in [false, true] {
+        let mut encoder = encoder_builder().build()?;
+        encoder.add_exif(super::SAMPLE_EXIF, compress)?;
+        encoder.add_xmp(xmp, compress)?;
+        let result: EncoderResult<u8> =
+            encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+        let exif = decoder
+            .read_box(&result, *b"Exif")?
+            .expect("Failed to get Exif");
+        assert_eq!(exif[..4], [0; 4]);
+        assert_eq!(&exif[4..], super::SAMPLE_EXIF);
+        assert_eq!(decoder.exif(&result)?.as_deref(), Some(super::SAMPLE_EXIF));
+        assert_eq!(
+            decoder.read_box(&result, *b"xml ")?.as_deref(),
+            Some(super::SAMPLE_XMP)
+        );
+
+        let c2pa = BoxType::new(*b"c2pa")?;
+        let mut encoder = encoder_builder().build()?;
+        encoder.add_box(c2pa, &[1, 2, 3], compress)?;
+        let result: EncoderResult<u8> =
+            encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+        assert_eq!(
+            decoder.read_box(&result, *c2pa.as_bytes())?.as_deref(),
+            Some(&[1, 2, 3][..])
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "boxes")]
+fn strict_boxes() -> TestResult {
+    use crate::decode::DecodeLimits;
+
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().build()?;
+    encoder.add_box(BoxType::new(*b"c2pa")?, &[0; 4096], false)?;
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    let decoder = decoder_builder()
+        .limits(DecodeLimits {
+            max_box_size: 2048,
+            reject_unknown_boxes: false,
+            ..DecodeLimits::STRICT
+        })
+        .build()?;
+    assert!(matches!(
+        decoder.read_box(&result, *b"c2pa"),
+        Err(DecodeError::LimitExceeded(_))
+    ));
+    assert!(matches!(
+        decoder.decode_progressive(&result, |_| {}),
+        Err(DecodeError::LimitExceeded(_))
+    ));
+    // The decoder is reset and can be reused
+    decoder.decode(super::SAMPLE_JXL)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "threads")]
 fn builder() -> TestResult {
     use crate::decode::Metadata;
 
@@ -121,6 +206,83 @@ fn builder() -> TestResult {
 }
 
 #[test]
+fn spot_color() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let ink = vec![u8::MAX; (sample.width() * sample.height()) as usize];
+
+    let mut builder = encoder_builder();
+    builder.extra_channel(ExtraChannel::spot_color("Red ink", [1.0, 0.0, 0.0, 1.0]));
+
+    assert!(matches!(
+        builder
+            .build()?
+            .encode::<_, u8>(sample.as_raw(), sample.width(), sample.height()),
+        Err(crate::EncodeError::ApiUsage)
+    ));
+
+    let mut encoder = builder.build()?;
+    let res: EncoderResult<u8> = encoder.encode_frame(
+        &EncoderFrame::new(sample.as_raw()).extra_channel(&ink),
+        sample.width(),
+        sample.height(),
+    )?;
+
+    let (metadata, plain) = decoder_builder()
+        .render_spotcolors(false)
+        .build()?
+        .decode_with::<u8>(&res)?;
+    assert_eq!(
+        metadata.warnings,
+        vec![crate::decode::DecodeWarning::ExtraChannelsIgnored(1)]
+    );
+    let (metadata, rendered) = decoder_builder()
+        .render_spotcolors(true)
+        .build()?
+        .decode_with::<u8>(&res)?;
+    assert!(metadata.warnings.is_empty());
+    assert_ne!(plain, rendered);
+    assert!(rendered.chunks_exact(3).all(|p| p == [255, 0, 0]));
+
+    Ok(())
+}
+
+#[test]
+fn typed_extra_channels() -> TestResult {
+    use crate::encode::ExtraChannelType;
+
+    let sample = get_sample().to_rgba8();
+    let plane = vec![0x7Fu8; (sample.width() * sample.height()) as usize];
+
+    let mut encoder = encoder_builder()
+        .has_alpha(true)
+        .alpha_quality(0.0)
+        .extra_channels(vec![
+            ExtraChannel::depth().name("Depth").quality(5.0),
+            ExtraChannel::selection_mask(),
+            ExtraChannel::cfa(1),
+            ExtraChannel::new(ExtraChannelType::Thermal),
+        ])
+        .build()?;
+
+    let res: EncoderResult<u8> = encoder.encode_frame(
+        &EncoderFrame::new(sample.as_raw())
+            .num_channels(4)
+            .extra_channel(&plane)
+            .extra_channel(&plane)
+            .extra_channel(&plane)
+            .extra_channel(&plane),
+        sample.width(),
+        sample.height(),
+    )?;
+
+    let (metadata, _) = decoder_builder().build()?.decode_with::<u8>(&res)?;
+    assert!(metadata.has_alpha_channel);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "threads")]
 fn resizable() -> TestResult {
     let resizable_runner = ResizableRunner::default();
     let sample = get_sample().to_rgb8();
@@ -177,13 +339,385 @@ fn multi_frames() -> TestResult {
     let decoder = decoder_builder().build()?;
     let _res = decoder.decode(&result)?;
 
-    encoder.uses_original_profile = true;
-    let result: EncoderResult<f32> = encoder
+    #[cfg(feature = "jpeg")]
+    {
+        encoder.uses_original_profile = true;
+        let result: EncoderResult<f32> = encoder
+            .multiple(sample.width(), sample.height())?
+            .add_jpeg_frame(super::SAMPLE_JPEG)?
+            .add_jpeg_frame(super::SAMPLE_JPEG)?
+            .encode()?;
+        let _res = decoder.reconstruct(&result)?;
+
+        let mut frames = encoder.multiple::<u8>(sample.width(), sample.height())?;
+        for _ in 0..3 {
+            frames.push_jpeg_frame(super::SAMPLE_JPEG)?;
+        }
+        let result = frames.finish()?;
+        let _res = decoder.reconstruct(&result)?;
+    }
+
+    let mut frames = encoder.multiple::<u8>(sample.width(), sample.height())?;
+    for _ in 0..3 {
+        frames.push_frame(&frame)?;
+    }
+    let result = frames.finish()?;
+    let _res = decoder.decode(&result)?;
+
+    Ok(())
+}
+
+#[test]
+fn frame_names() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().speed(EncoderSpeed::Lightning).build()?;
+    let decoder = decoder_builder().coalescing(false).build()?;
+
+    let result: EncoderResult<u8> = encoder
         .multiple(sample.width(), sample.height())?
-        .add_jpeg_frame(super::SAMPLE_JPEG)?
-        .add_jpeg_frame(super::SAMPLE_JPEG)?
+        .add_frame(&EncoderFrame::new(sample.as_raw()).name("background"))?
+        .add_frame(&EncoderFrame::new(sample.as_raw()))?
+        .add_frame(&EncoderFrame::new(sample.as_raw()).name("foreground"))?
+        .encode()?;
+
+    let (_, frames) = decoder.decode_frames::<u8>(&result)?;
+    assert_eq!(
+        frames.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+        ["background", "", "foreground"]
+    );
+    assert!(frames.iter().all(|f| f.pixels.len() == sample.len()));
+
+    Ok(())
+}
+
+#[test]
+fn layers() -> TestResult {
+    let background = [255_u8, 0, 0].repeat(16);
+    let layer = [0_u8, 255, 0].repeat(4);
+    let mut encoder = encoder_builder().lossless_original().build()?;
+
+    let result: EncoderResult<u8> = encoder
+        .multiple(4, 4)?
+        .add_frame(&EncoderFrame::new(&background).name("background"))?
+        .add_frame(
+            &EncoderFrame::new(&layer)
+                .name("layer")
+                .crop(1, 1, 2, 2)
+                .blend_mode(BlendMode::Replace),
+        )?
+        .encode()?;
+
+    let (_, pixels) = decoder_builder().build()?.decode_with::<u8>(&result)?;
+    assert_eq!(pixels[..3], [255, 0, 0]);
+    assert_eq!(pixels[(4 + 1) * 3..(4 + 2) * 3], [0, 255, 0]);
+
+    let (_, frames) = decoder_builder()
+        .coalescing(false)
+        .build()?
+        .decode_frames::<u8>(&result)?;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[1].pixels, layer);
+    assert_eq!(
+        (
+            frames[1].width,
+            frames[1].height,
+            frames[1].x0,
+            frames[1].y0
+        ),
+        (2, 2, 1, 1)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reference_frames() -> TestResult {
+    let background = [255_u8, 0, 0].repeat(16);
+    let green = [0_u8, 255, 0].repeat(4);
+    let blue = [0_u8, 0, 255].repeat(4);
+    let mut encoder = encoder_builder()
+        .lossless_original()
+        .animation(Animation::default())
+        .build()?;
+
+    let result: EncoderResult<u8> = encoder
+        .multiple(4, 4)?
+        .add_frame(
+            &EncoderFrame::new(&background)
+                .duration(10)
+                .save_as_reference(1),
+        )?
+        .add_frame(
+            &EncoderFrame::new(&green)
+                .crop(1, 1, 2, 2)
+                .blend_source(1)
+                .duration(20),
+        )?
+        .add_frame(
+            &EncoderFrame::new(&blue)
+                .crop(0, 0, 2, 2)
+                .blend_source(1)
+                .duration(30),
+        )?
+        .encode()?;
+
+    let (_, frames) = decoder_builder().build()?.decode_frames::<u8>(&result)?;
+    assert_eq!(
+        frames.iter().map(|f| f.duration).collect::<Vec<_>>(),
+        [10, 20, 30]
+    );
+    let pixel = |frame: usize, x: usize, y: usize| &frames[frame].pixels[(y * 4 + x) * 3..][..3];
+    assert_eq!(pixel(1, 2, 2), [0, 255, 0]);
+    assert_eq!(pixel(2, 1, 1), [0, 0, 255]);
+    // The last frame is blended onto the background, not onto the previous frame
+    assert_eq!(pixel(2, 2, 2), [255, 0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn frame_at() -> TestResult {
+    let background = [255_u8, 0, 0].repeat(16);
+    let green = [0_u8, 255, 0].repeat(4);
+    let blue = [0_u8, 0, 255].repeat(4);
+    let mut encoder = encoder_builder()
+        .lossless_original()
+        .animation(Animation {
+            num_loops: 1,
+            ..Animation::default()
+        })
+        .build()?;
+
+    let result: EncoderResult<u8> = encoder
+        .multiple(4, 4)?
+        .add_frame(
+            &EncoderFrame::new(&background)
+                .duration(10)
+                .save_as_reference(1),
+        )?
+        .add_frame(&EncoderFrame::new(&green).crop(1, 1, 2, 2).duration(20))?
+        .add_frame(
+            &EncoderFrame::new(&blue)
+                .crop(0, 0, 2, 2)
+                .blend_source(1)
+                .duration(30),
+        )?
         .encode()?;
-    let _res = decoder.reconstruct(&result)?;
+
+    let decoder = decoder_builder().coalescing(false).build()?;
+    let pixel = |t_ms| -> Result<Vec<u8>, DecodeError> {
+        let (metadata, pixels) = decoder.decode_frame_at(&result, t_ms)?;
+        assert_eq!((metadata.width, metadata.height), (4, 4));
+        let Pixels::Uint8(pixels) = pixels else {
+            panic!("unexpected pixel type");
+        };
+        Ok(pixels[(2 * 4 + 2) * 3..][..3].to_vec())
+    };
+    assert_eq!(pixel(50)?, [255, 0, 0]);
+    assert_eq!(pixel(100)?, [0, 255, 0]);
+    // The skipped background is still blended below the last frame
+    assert_eq!(pixel(300)?, [255, 0, 0]);
+    assert_eq!(pixel(10_000)?, [255, 0, 0]);
+
+    let (_, still) = decoder.decode_frame_at(super::SAMPLE_JXL, 10_000)?;
+    assert!(matches!(still, Pixels::Uint16(_)));
+
+    let playback = decoder_builder().build()?.decode_playback::<u8>(&result)?;
+    let ms = Duration::from_millis;
+    assert_eq!(playback.loop_duration(), Some(ms(600)));
+    assert_eq!(playback.index_at(ms(150)), 1);
+    assert_eq!(playback.next_change(ms(150)), Some(ms(150)));
+    // The animation plays once
+    assert_eq!(playback.next_change(ms(500)), None);
+    assert_eq!(playback.frame_at(ms(10_000)).map(|f| f.duration), Some(30));
+
+    Ok(())
+}
+
+#[test]
+fn orientation() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder()
+        .orientation(Orientation::Rotate90Cw)
+        .build()?;
+
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    let (metadata, _) = decoder_builder()
+        .skip_reorientation(true)
+        .build()?
+        .decode(&result)?;
+    assert_eq!(metadata.orientation, Orientation::Rotate90Cw);
+    assert_eq!(
+        (metadata.width, metadata.height),
+        (sample.width(), sample.height())
+    );
+
+    // Without skipping, the decoder applies the rotation
+    let (metadata, _) = decoder_builder().build()?.decode(&result)?;
+    assert_eq!(
+        (metadata.width, metadata.height),
+        (sample.height(), sample.width())
+    );
+
+    Ok(())
+}
+
+#[test]
+fn premultiplied_alpha() -> TestResult {
+    let pixels = [100_u8, 50, 0, 128].repeat(16);
+    let mut encoder = encoder_builder()
+        .has_alpha(true)
+        .alpha_premultiplied(true)
+        .build()?;
+
+    let result: EncoderResult<u8> =
+        encoder.encode_frame(&EncoderFrame::new(&pixels).num_channels(4), 4, 4)?;
+
+    let (metadata, decoded) = decoder_builder()
+        .unpremul_alpha(true)
+        .build()?
+        .decode_with::<u8>(&result)?;
+    assert_eq!(metadata.alpha_bits, 8);
+    assert!(metadata.alpha_premultiplied);
+    let expected = [199, 100, 0, 128];
+    assert!(decoded[..4]
+        .iter()
+        .zip(expected)
+        .all(|(&a, b)| a.abs_diff(b) <= 2));
+
+    Ok(())
+}
+
+#[test]
+fn expert_options() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().build()?;
+
+    assert!(matches!(
+        encoder.set_frame_option(FrameSetting::Effort, 11),
+        Err(EncodeError::NotSupported)
+    ));
+    encoder.reset();
+
+    encoder.allow_expert_options();
+    encoder.set_frame_option(FrameSetting::Effort, 11)?;
+    encoder.set_frame_float_option(FrameSetting::PhotonNoise, 100.0)?;
+    let _: EncoderResult<u8> = encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    Ok(())
+}
+
+#[test]
+fn target_size() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let frame = EncoderFrame::new(sample.as_raw());
+    let mut encoder = encoder_builder().speed(EncoderSpeed::Lightning).build()?;
+
+    let default: EncoderResult<u8> =
+        encoder.encode_frame(&frame, sample.width(), sample.height())?;
+    let target = default.len() / 2;
+
+    let result: EncoderResult<u8> =
+        encoder.encode_to_target_size(&frame, sample.width(), sample.height(), target)?;
+    assert!(result.len() <= target);
+    assert!(result.len() > target / 2);
+    assert!((encoder.quality - 1.0).abs() < f32::EPSILON);
+
+    let result: EncoderResult<u8> =
+        encoder.encode_to_target_bpp(&frame, sample.width(), sample.height(), 1.0)?;
+    assert!(result.len() * 8 <= (sample.width() * sample.height()) as usize);
+
+    // Boxes are part of every encode of the search
+    #[cfg(feature = "boxes")]
+    {
+        encoder.add_exif(super::SAMPLE_EXIF, false)?;
+        let result: EncoderResult<u8> =
+            encoder.encode_to_target_size(&frame, sample.width(), sample.height(), target)?;
+        assert!(result.len() <= target);
+        let exif = decoder_builder().build()?.exif(&result)?;
+        assert_eq!(exif.as_deref(), Some(super::SAMPLE_EXIF));
+    }
+
+    encoder.lossless = true;
+    assert!(matches!(
+        encoder.encode_to_target_size::<_, u8>(&frame, sample.width(), sample.height(), target),
+        Err(EncodeError::ApiUsage)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn cancellation() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let token = CancellationToken::new();
+    let mut encoder = encoder_builder().cancellation(token.clone()).build()?;
+
+    token.cancel();
+    assert!(matches!(
+        encoder.encode::<u8, u8>(sample.as_raw(), sample.width(), sample.height()),
+        Err(EncodeError::Cancelled)
+    ));
+
+    token.reset();
+    let _: EncoderResult<u8> = encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    Ok(())
+}
+
+#[test]
+fn resampling() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let decoder = decoder_builder().build()?;
+
+    let mut encoder = encoder_builder().resampling(2).build()?;
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    let (metadata, _) = decoder.decode(&result)?;
+    assert_eq!(
+        (metadata.width, metadata.height),
+        (sample.width(), sample.height())
+    );
+
+    // 2x2 pixel art, upsampled to 4x4
+    let pixels = [[255_u8, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]].concat();
+    let mut encoder = encoder_builder()
+        .resampling(2)
+        .already_downsampled(true)
+        .upsampling_mode(UpsamplingMode::NearestNeighbor)
+        .build()?;
+    let result: Result<EncoderResult<u8>, _> = encoder.encode(&pixels, 4, 4);
+    #[cfg(feature = "libjxl_0_8")]
+    assert!(matches!(result, Err(EncodeError::NotSupported)));
+    #[cfg(not(feature = "libjxl_0_8"))]
+    {
+        let (metadata, _) = decoder.decode(&result?)?;
+        assert_eq!((metadata.width, metadata.height), (4, 4));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn presets() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let decoder = decoder_builder().build()?;
+
+    for preset in [
+        EncoderPreset::photo_web(),
+        EncoderPreset::lossless_archive(),
+        EncoderPreset::screenshot(),
+    ] {
+        let mut encoder = encoder_builder().preset(preset).build()?;
+        let result: EncoderResult<u8> =
+            encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+        let (_, pixels) = decoder.decode_with::<u8>(&result)?;
+        if preset.lossless {
+            assert_eq!(pixels, sample.as_raw().as_slice());
+        }
+    }
 
     Ok(())
 }
@@ -211,10 +745,526 @@ fn gray() -> TestResult {
     )?;
     _ = decoder.decode(&result)?;
 
-    encoder.set_frame_option(
-        jpegxl_sys::encoder::encode::JxlEncoderFrameSettingId::BrotliEffort,
-        1,
+    encoder.color_encoding = ColorEncoding::gray_gamma22();
+    let result: EncoderResult<u8> = encoder.encode_frame(
+        &EncoderFrame::new(sample.as_raw()).num_channels(1),
+        sample.width(),
+        sample.height(),
     )?;
+    _ = decoder.decode(&result)?;
+
+    encoder.set_frame_option(FrameSetting::BrotliEffort, 1)?;
+
+    Ok(())
+}
+
+#[test]
+fn color_presets() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let decoder = decoder_builder().icc_profile(true).build()?;
+
+    let mut icc_profiles = vec![];
+    for preset in [
+        ColorEncoding::srgb(),
+        ColorEncoding::linear_srgb(),
+        ColorEncoding::display_p3(),
+        ColorEncoding::rec2020_pq(),
+    ] {
+        let mut encoder = encoder_builder().color_encoding(preset).build()?;
+        let result: EncoderResult<u16> =
+            encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+        let (metadata, _) = decoder.decode(&result)?;
+        icc_profiles.push(metadata.icc_profile);
+    }
+
+    icc_profiles.dedup();
+    assert_eq!(icc_profiles.len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn icc_profile() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let decoder = decoder_builder().icc_profile(true).build()?;
+    let (metadata, _) = decoder.decode(super::SAMPLE_JXL)?;
+    let icc_profile = metadata.icc_profile.expect("Failed to get ICC profile");
+
+    let mut encoder = encoder_builder()
+        .icc_profile(icc_profile.clone())
+        .uses_original_profile(true)
+        .build()?;
+    let result: EncoderResult<u16> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    let (metadata, _) = decoder.decode(&result)?;
+    assert_eq!(metadata.icc_profile, Some(icc_profile.clone()));
+
+    let mut encoder = encoder_builder()
+        .icc_profile(icc_profile)
+        .color_encoding(ColorEncoding::SrgbLuma)
+        .build()?;
+    assert!(matches!(
+        encoder.encode::<_, u8>(sample.as_raw(), sample.width(), sample.height()),
+        Err(crate::EncodeError::IccProfileMismatch)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn lossless_original() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().lossless_original().build()?;
+    assert!(encoder.lossless && encoder.uses_original_profile);
+
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    let (_, pixels) = decoder_builder().build()?.decode_with::<u8>(&result)?;
+    assert!(pixels == *sample.as_raw());
+
+    Ok(())
+}
+
+#[test]
+fn hdr_metadata() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder()
+        .color_encoding(ColorEncoding::rec2020_pq())
+        .intensity_target(4000.0)
+        .min_nits(0.05)
+        .relative_to_max_display(true)
+        .linear_below(0.5)
+        .build()?;
+
+    let result: EncoderResult<u16> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    let (metadata, _) = decoder_builder().build()?.decode(&result)?;
+    assert!((metadata.intensity_target - 4000.0).abs() < 1.0);
+    assert!((metadata.min_nits - 0.05).abs() < 1e-3);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "boxes", not(feature = "libjxl_0_10")))]
+fn gain_map() -> TestResult {
+    use crate::gain_map::GainMapBundle;
+
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().build()?;
+    let gain_map: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    let bundle = GainMapBundle {
+        metadata: vec![0, 1, 2, 3],
+        gain_map: gain_map.data,
+        ..Default::default()
+    };
+    encoder.add_gain_map(&bundle)?;
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    let decoder = decoder_builder().build()?;
+    let parsed = decoder.gain_map(&result)?.expect("Failed to get gain map");
+    assert_eq!(parsed.metadata, bundle.metadata);
+    assert_eq!(parsed.gain_map, bundle.gain_map);
+    assert!(parsed.color_encoding.is_none());
+
+    assert!(decoder.gain_map(super::SAMPLE_JXL)?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn custom_bit_depth() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let data: Vec<u16> = sample.as_raw().iter().map(|&v| u16::from(v) << 2).collect();
+
+    let mut encoder = encoder_builder()
+        .lossless_original()
+        .bits_per_sample(10)
+        .build()?;
+    let result: EncoderResult<u16> = encoder.encode(&data, sample.width(), sample.height())?;
+
+    let (_, pixels) = decoder_builder().build()?.decode_with::<u16>(&result)?;
+    assert!(pixels
+        .iter()
+        .zip(&data)
+        .all(|(&p, &v)| u32::from(p) == (u32::from(v) * 65535 + 511) / 1023));
+
+    Ok(())
+}
+
+#[test]
+fn float_input() -> TestResult {
+    let sample = get_sample().to_rgb32f();
+    let hdr: Vec<f32> = sample.as_raw().iter().map(|v| v * 4.0).collect();
+    let half: Vec<f16> = hdr.iter().copied().map(f16::from_f32).collect();
+
+    let mut encoder = encoder_builder()
+        .color_encoding(ColorEncoding::LinearSrgb)
+        .build()?;
+    let decoder = decoder_builder().build()?;
+
+    let result: EncoderResult<f32> = encoder.encode(&hdr, sample.width(), sample.height())?;
+    let (_, pixels) = decoder.decode_with::<f32>(&result)?;
+    assert!(pixels.iter().any(|&v| v > 1.0));
+
+    let result: EncoderResult<f16> = encoder.encode(&half, sample.width(), sample.height())?;
+    let (_, pixels) = decoder.decode_with::<f16>(&result)?;
+    assert!(pixels.iter().any(|&v| v > f16::ONE));
+
+    Ok(())
+}
+
+#[test]
+fn planar() -> TestResult {
+    let sample = get_sample().to_rgba8();
+    let planes: Vec<Vec<u8>> = (0..4)
+        .map(|c| sample.as_raw().iter().skip(c).step_by(4).copied().collect())
+        .collect();
+    let planes: Vec<&[u8]> = planes.iter().map(Vec::as_slice).collect();
+
+    let mut encoder = encoder_builder()
+        .has_alpha(true)
+        .lossless_original()
+        .build()?;
+    let result: EncoderResult<u8> = encoder.encode_frame(
+        &EncoderFrame::from_planes(&planes),
+        sample.width(),
+        sample.height(),
+    )?;
+
+    let (_, pixels) = decoder_builder().build()?.decode_with::<u8>(&result)?;
+    assert!(pixels == *sample.as_raw());
+
+    Ok(())
+}
+
+/// Build a minimal CMYK ICC profile with a `lut8` A2B0 transform
+#[allow(clippy::cast_possible_truncation)]
+fn cmyk_icc_profile() -> Vec<u8> {
+    let mut lut = b"mft1\0\0\0\0".to_vec();
+    lut.extend_from_slice(&[4, 3, 2, 0]);
+    for i in 0..9 {
+        let v: i32 = if i % 4 == 0 { 0x1_0000 } else { 0 };
+        lut.extend_from_slice(&v.to_be_bytes());
+    }
+    let ramp: Vec<u8> = (0..=255).collect();
+    for _ in 0..4 {
+        lut.extend_from_slice(&ramp);
+    }
+    for i in 0..16u8 {
+        lut.extend_from_slice(&[255 - 60 * i.count_ones() as u8, 128, 128]);
+    }
+    for _ in 0..3 {
+        lut.extend_from_slice(&ramp);
+    }
+
+    let mut wtpt = b"XYZ \0\0\0\0".to_vec();
+    for v in [0xF6D6_i32, 0x1_0000, 0xD32D] {
+        wtpt.extend_from_slice(&v.to_be_bytes());
+    }
+
+    let tags: [(&[u8; 4], &[u8]); 2] = [(b"A2B0", &lut), (b"wtpt", &wtpt)];
+    let mut offset = 128 + 4 + 12 * tags.len();
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = vec![];
+    for (sig, tag) in tags {
+        table.extend_from_slice(sig);
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+        data.extend_from_slice(tag);
+        offset += tag.len();
+    }
+
+    let mut header = vec![0; 128];
+    header[0..4].copy_from_slice(&(offset as u32).to_be_bytes());
+    header[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+    header[12..16].copy_from_slice(b"prtr");
+    header[16..20].copy_from_slice(b"CMYK");
+    header[20..24].copy_from_slice(b"Lab ");
+    header[36..40].copy_from_slice(b"acsp");
+    header[68..80].copy_from_slice(&wtpt[8..20]);
+
+    [header, table, data].concat()
+}
+
+#[test]
+fn cmyk() -> TestResult {
+    let sample = get_sample().to_rgba8();
+
+    let mut encoder = encoder_builder()
+        .cmyk(cmyk_icc_profile())
+        .lossless(true)
+        .build()?;
+    let result: EncoderResult<u8> = encoder.encode_frame(
+        &EncoderFrame::from_cmyk(sample.as_raw()),
+        sample.width(),
+        sample.height(),
+    )?;
+
+    let (metadata, _) = decoder_builder()
+        .icc_profile(true)
+        .build()?
+        .decode_with::<u8>(&result)?;
+    assert_eq!(metadata.icc_profile, Some(cmyk_icc_profile()));
+
+    // JPEG XL stores the inverted CMY as color, and the inverted K as black channel
+    let (cmy, black) = decode_cmyk(&result);
+    for ((pixel, cmy), k) in sample.pixels().zip(cmy.chunks_exact(3)).zip(black) {
+        assert_eq!(
+            cmy,
+            pixel.0[..3].iter().map(|v| 255 - v).collect::<Vec<_>>()
+        );
+        assert_eq!(k, 255 - pixel.0[3]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn cmyk_twice() -> TestResult {
+    let encoder = encoder_builder()
+        .cmyk(cmyk_icc_profile())
+        .cmyk(cmyk_icc_profile())
+        .build()?;
+    assert_eq!(encoder.extra_channels.len(), 1);
+    Ok(())
+}
+
+/// Decode the CMY color channels and the black channel with the raw API, as the decoder
+/// doesn't output extra channels
+fn decode_cmyk(data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    use jpegxl_sys::{
+        common::types::{JxlDataType, JxlPixelFormat},
+        decode::*,
+    };
+
+    let format = |num_channels| JxlPixelFormat {
+        num_channels,
+        data_type: JxlDataType::Uint8,
+        endianness: Endianness::Native,
+        align: 0,
+    };
+    let mut cmy = vec![];
+    let mut black = vec![];
+    unsafe {
+        let dec = JxlDecoderCreate(std::ptr::null());
+        JxlDecoderSubscribeEvents(dec, JxlDecoderStatus::FullImage as i32);
+        JxlDecoderSetInput(dec, data.as_ptr(), data.len());
+        JxlDecoderCloseInput(dec);
+        loop {
+            match JxlDecoderStatus::try_from(JxlDecoderProcessInput(dec)) {
+                Ok(JxlDecoderStatus::NeedImageOutBuffer) => {
+                    let mut size = 0;
+                    JxlDecoderImageOutBufferSize(dec, &format(3), &mut size);
+                    cmy.resize(size, 0);
+                    JxlDecoderSetImageOutBuffer(dec, &format(3), cmy.as_mut_ptr().cast(), size);
+                    JxlDecoderExtraChannelBufferSize(dec, &format(1), &mut size, 0);
+                    black.resize(size, 0);
+                    JxlDecoderSetExtraChannelBuffer(
+                        dec,
+                        &format(1),
+                        black.as_mut_ptr().cast(),
+                        size,
+                        0,
+                    );
+                }
+                Ok(JxlDecoderStatus::FullImage) => {}
+                Ok(JxlDecoderStatus::Success) => break,
+                status => panic!("unexpected decoder status {status:?}"),
+            }
+        }
+        JxlDecoderDestroy(dec);
+    }
+    (cmy, black)
+}
+
+#[test]
+fn writer() -> TestResult {
+    struct FailingWriter;
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().init_buffer_size(32).build()?;
+
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    let mut output = vec![];
+    encoder.encode_to_writer::<_, u8>(
+        sample.as_raw(),
+        sample.width(),
+        sample.height(),
+        &mut output,
+    )?;
+    assert_eq!(result.data, output);
+
+    #[cfg(feature = "jpeg")]
+    {
+        let mut output = vec![];
+        encoder.encode_jpeg_to_writer(super::SAMPLE_JPEG, &mut output)?;
+        assert!(decoder_builder().build()?.decode(&output).is_ok());
+    }
+
+    let mut output = vec![];
+    encoder
+        .multiple::<u8>(sample.width(), sample.height())?
+        .add_frame(&EncoderFrame::new(sample.as_raw()))?
+        .encode_to_writer(&mut output)?;
+    assert!(decoder_builder().build()?.decode(&output).is_ok());
+
+    assert!(matches!(
+        encoder.encode_to_writer::<_, u8>(
+            sample.as_raw(),
+            sample.width(),
+            sample.height(),
+            &mut FailingWriter
+        ),
+        Err(crate::EncodeError::Io(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "libjxl_0_9"))]
+fn seekable() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder()
+        .use_container(true)
+        .init_buffer_size(32)
+        .build()?;
+
+    let mut output = std::io::Cursor::new(b"abc".to_vec());
+    output.set_position(3);
+    encoder.encode_frame_to_seekable::<_, u8>(
+        &EncoderFrame::new(sample.as_raw()),
+        sample.width(),
+        sample.height(),
+        &mut output,
+    )?;
+
+    let output = output.into_inner();
+    assert_eq!(&output[..3], b"abc");
+    let (metadata, _) = decoder_builder().build()?.decode(&output[3..])?;
+    assert_eq!(metadata.width, sample.width());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "libjxl_0_9"))]
+fn seekable_cancellation() -> TestResult {
+    struct CancellingWriter(std::io::Cursor<Vec<u8>>, CancellationToken);
+    impl std::io::Write for CancellingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.1.cancel();
+            self.0.write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl std::io::Seek for CancellingWriter {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.0.seek(pos)
+        }
+    }
+
+    let sample = get_sample().to_rgb8();
+    let token = CancellationToken::new();
+    let mut encoder = encoder_builder()
+        .init_buffer_size(32)
+        .cancellation(token.clone())
+        .build()?;
+
+    // Cancelled while the output is written
+    let mut writer = CancellingWriter(std::io::Cursor::new(vec![]), token.clone());
+    assert!(matches!(
+        encoder.encode_frame_to_seekable::<_, u8>(
+            &EncoderFrame::new(sample.as_raw()),
+            sample.width(),
+            sample.height(),
+            &mut writer,
+        ),
+        Err(EncodeError::Cancelled)
+    ));
+
+    token.reset();
+    let mut output = std::io::Cursor::new(vec![]);
+    encoder.encode_frame_to_seekable::<_, u8>(
+        &EncoderFrame::new(sample.as_raw()),
+        sample.width(),
+        sample.height(),
+        &mut output,
+    )?;
+    decoder_builder().build()?.decode(output.get_ref())?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "libjxl_0_9"))]
+fn seekable_panic() -> TestResult {
+    struct PanickingWriter;
+    impl std::io::Write for PanickingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            panic!("Writer failed")
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl std::io::Seek for PanickingWriter {
+        fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            Ok(0)
+        }
+    }
+
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().init_buffer_size(32).build()?;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        encoder.encode_frame_to_seekable::<_, u8>(
+            &EncoderFrame::new(sample.as_raw()),
+            sample.width(),
+            sample.height(),
+            &mut PanickingWriter,
+        )
+    }));
+    assert!(result.is_err());
+
+    // The encoder is still usable after the panic
+    let _res: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    Ok(())
+}
+
+#[test]
+fn reuse() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().build()?;
+
+    for _ in 0..2 {
+        #[cfg(feature = "boxes")]
+        encoder.add_metadata(&Metadata::Xmp(super::SAMPLE_XMP), true)?;
+        let _: EncoderResult<u8> =
+            encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+    }
+
+    assert!(encoder
+        .encode::<_, u8>(sample.as_raw(), 0, sample.height())
+        .is_err());
+    encoder.reset();
+    let _: EncoderResult<u8> = encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
 
     Ok(())
 }
@@ -228,3 +1278,85 @@ fn initial_buffer() -> TestResult {
     let _: EncoderResult<f32> = encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
     Ok(())
 }
+
+#[test]
+fn invalid_configuration() {
+    let invalid = |builder: &mut crate::encode::JxlEncoderBuilder| {
+        matches!(builder.build(), Err(EncodeError::InvalidConfiguration(_)))
+    };
+
+    assert!(invalid(encoder_builder().quality(-1.0)));
+    assert!(invalid(encoder_builder().alpha_quality(26.0)));
+    assert!(invalid(encoder_builder().decoding_speed(5)));
+    assert!(invalid(encoder_builder().resampling(3)));
+    assert!(invalid(encoder_builder().extra_channel_resampling(0)));
+    assert!(encoder_builder()
+        .quality(25.0)
+        .resampling(8)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn config() -> TestResult {
+    let icc = vec![0; 128];
+    let encoder = encoder_builder()
+        .decoding_speed(2)
+        .icc_profile(icc)
+        .build()?;
+
+    let config = encoder.config();
+    assert_eq!(config.decoding_speed, 2);
+    assert_eq!(config.icc_profile_size, Some(128));
+    assert!(!config.lossless);
+    assert!(format!("{encoder:?}").contains("decoding_speed: 2"));
+
+    Ok(())
+}
+
+#[test]
+fn linear_output() -> TestResult {
+    let sample = get_sample().to_rgb8();
+    let mut encoder = encoder_builder().lossless_original().build()?;
+    let result: EncoderResult<u8> =
+        encoder.encode(sample.as_raw(), sample.width(), sample.height())?;
+
+    // The sRGB transfer function is undone on the `f32` output
+    let decoder = decoder_builder().linear_output(true).build()?;
+    let (_, linear) = decoder.decode_with::<f32>(&result)?;
+    for (l, p) in linear.iter().zip(sample.as_raw()).step_by(97) {
+        let v = f32::from(*p) / 255.0;
+        let expected = if v <= 0.04045 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        };
+        assert!((l - expected).abs() < 1e-4, "{l} != {expected}");
+    }
+
+    let (_, pixels) = decoder.decode(&result)?;
+    assert!(matches!(pixels, Pixels::Float(_)));
+    assert!(matches!(
+        decoder.decode_with::<u8>(&result),
+        Err(crate::DecodeError::ColorConversion)
+    ));
+
+    // Rows of 33 RGB pixels take 396 bytes, padded to 448 (112 samples)
+    let odd = image::imageops::crop_imm(&sample, 0, 0, 33, 8).to_image();
+    let result: EncoderResult<u8> = encoder.encode(odd.as_raw(), 33, 8)?;
+    let (_, linear) = decoder.decode_with::<f32>(&result)?;
+    let padded = decoder_builder()
+        .linear_output(true)
+        .pixel_format(crate::decode::PixelFormat {
+            num_channels: 3,
+            align: 64,
+            ..Default::default()
+        })
+        .build()?;
+    let (_, rows) = padded.decode_with::<f32>(&result)?;
+    for (y, row) in linear.chunks_exact(99).enumerate() {
+        assert_eq!(&rows[y * 112..y * 112 + 99], row);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Decode output matching common GPU texture formats, e.g. for `wgpu` or Vulkan
+
+use std::mem::MaybeUninit;
+
+use jpegxl_sys::common::types::JxlDataType;
+
+use crate::{
+    common::Endianness,
+    decode::{JxlDecoder, Metadata, PixelFormat},
+    DecodeError,
+};
+
+/// Row pitch alignment required when copying buffers to textures in `wgpu`
+pub const WGPU_ROW_ALIGNMENT: usize = 256;
+
+/// GPU texture format with 4 channels in little endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// `RGBA8Unorm`, `VK_FORMAT_R8G8B8A8_UNORM`
+    Rgba8Unorm,
+    /// `RGBA16Float`, `VK_FORMAT_R16G16B16A16_SFLOAT`
+    Rgba16Float,
+    /// `RGBA32Float`, `VK_FORMAT_R32G32B32A32_SFLOAT`
+    Rgba32Float,
+}
+
+impl TextureFormat {
+    /// Return the size of a pixel in bytes
+    #[must_use]
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba8Unorm => 4,
+            Self::Rgba16Float => 8,
+            Self::Rgba32Float => 16,
+        }
+    }
+
+    fn data_type(self) -> JxlDataType {
+        match self {
+            Self::Rgba8Unorm => JxlDataType::Uint8,
+            Self::Rgba16Float => JxlDataType::Float16,
+            Self::Rgba32Float => JxlDataType::Float,
+        }
+    }
+}
+
+/// Layout of a decoded texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureDescriptor {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Pixel format
+    pub format: TextureFormat,
+    /// Row pitch in bytes, a multiple of the requested alignment
+    pub bytes_per_row: u32,
+}
+
+/// Pixels ready to be uploaded to a GPU texture
+#[derive(Debug, Clone)]
+pub struct Texture {
+    /// Layout of `data`
+    pub descriptor: TextureDescriptor,
+    /// `bytes_per_row * height` bytes, with padding at the end of every row
+    pub data: Vec<u8>,
+}
+
+impl JxlDecoder<'_, '_> {
+    /// Decode the JPEG XL image to pixels in a GPU texture format
+    ///
+    /// Rows are padded to a multiple of `row_alignment` bytes, which is rounded up to a power
+    /// of two, e.g. [`WGPU_ROW_ALIGNMENT`]. This overrides `pixel_format` for this call.
+    ///
+    /// # Errors
+    /// Return a [`DecodeError`] when internal decoding fails
+    pub fn decode_to_texture(
+        &self,
+        data: &[u8],
+        format: TextureFormat,
+        row_alignment: usize,
+    ) -> Result<(Metadata, Texture), DecodeError> {
+        let align = row_alignment.max(1).next_power_of_two();
+        let pixel_format = PixelFormat {
+            num_channels: 4,
+            endianness: Endianness::Little,
+            align,
+        };
+
+        let mut buffer = vec![];
+        let mut format_out = MaybeUninit::uninit();
+        let metadata = self.decode_internal(
+            data,
+            Some(format.data_type()),
+            Some(pixel_format),
+            self.icc_profile,
+            None,
+            None,
+            format_out.as_mut_ptr(),
+            &mut buffer,
+        )?;
+
+        let bytes_per_row = usize::try_from(metadata.width)
+            .ok()
+            .and_then(|w| w.checked_mul(format.bytes_per_pixel()))
+            .and_then(|row| row.div_ceil(align).checked_mul(align))
+            .ok_or(DecodeError::SizeOverflow)?;
+        // The last row may be left unpadded by the decoder
+        let len = usize::try_from(metadata.height)
+            .ok()
+            .and_then(|h| h.checked_mul(bytes_per_row))
+            .ok_or(DecodeError::SizeOverflow)?;
+        buffer.resize(len, 0);
+
+        let descriptor = TextureDescriptor {
+            width: metadata.width,
+            height: metadata.height,
+            format,
+            bytes_per_row: u32::try_from(bytes_per_row)
+                .map_err(|_| DecodeError::InternalError("Row pitch overflows u32"))?,
+        };
+        Ok((
+            metadata,
+            Texture {
+                descriptor,
+                data: buffer,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use half::f16;
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::{decoder_builder, tests::SAMPLE_JXL};
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn decode_to_texture() -> TestResult {
+        let decoder = decoder_builder().build()?;
+
+        let (metadata, texture) =
+            decoder.decode_to_texture(SAMPLE_JXL, TextureFormat::Rgba8Unorm, WGPU_ROW_ALIGNMENT)?;
+        let TextureDescriptor {
+            width,
+            height,
+            bytes_per_row,
+            ..
+        } = texture.descriptor;
+        assert_eq!((width, height), (metadata.width, metadata.height));
+        assert_eq!(bytes_per_row as usize % WGPU_ROW_ALIGNMENT, 0);
+        assert!(bytes_per_row >= width * 4);
+        assert_eq!(texture.data.len(), (bytes_per_row * height) as usize);
+        assert!(decoder.pixel_format.is_none());
+
+        let (_, reference) = decoder_builder()
+            .pixel_format(PixelFormat {
+                num_channels: 4,
+                ..PixelFormat::default()
+            })
+            .build()?
+            .decode_with::<u8>(SAMPLE_JXL)?;
+        let row = width as usize * 4;
+        assert_eq!(&texture.data[..row], &reference[..row]);
+        assert_eq!(
+            &texture.data[bytes_per_row as usize..][..row],
+            &reference[row..row * 2]
+        );
+
+        let (_, texture) = decoder.decode_to_texture(SAMPLE_JXL, TextureFormat::Rgba16Float, 3)?;
+        assert_eq!(texture.descriptor.bytes_per_row % 4, 0);
+        let alpha = f16::from_le_bytes([texture.data[6], texture.data[7]]);
+        assert_eq!(alpha, f16::ONE);
+
+        Ok(())
+    }
+}
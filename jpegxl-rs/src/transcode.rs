@@ -0,0 +1,115 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Lossless JPEG ↔ JPEG XL transcoding with verification
+
+use crate::{
+    decode::{Data, JxlDecoder},
+    decoder_builder,
+    encode::JxlEncoder,
+    encoder_builder,
+    errors::TranscodeError,
+};
+
+/// Losslessly transcode a JPEG image to JPEG XL
+///
+/// The JPEG bitstream reconstruction data is stored in the container. The result is decoded
+/// again and the reconstructed JPEG is compared to the input byte by byte.
+///
+/// # Errors
+/// Return a [`TranscodeError`] if encoding fails, or the original JPEG cannot be reconstructed
+/// from the result bit-exactly
+pub fn jpeg_to_jxl(data: &[u8]) -> Result<Vec<u8>, TranscodeError> {
+    let mut encoder = encoder_builder()
+        .use_container(true)
+        .uses_original_profile(true)
+        .build()?;
+    let decoder = decoder_builder().build()?;
+    transcode(&mut encoder, &decoder, data)
+}
+
+/// Reconstruct the original JPEG image from a JPEG XL image
+///
+/// The reconstructed JPEG is transcoded again and compared to itself byte by byte, so the
+/// returned bytes are known to survive another round trip.
+///
+/// # Errors
+/// Return a [`TranscodeError`] if decoding fails, the image has no JPEG reconstruction data,
+/// or the round trip is not bit-exact
+pub fn jxl_to_jpeg(data: &[u8]) -> Result<Vec<u8>, TranscodeError> {
+    let mut encoder = encoder_builder()
+        .use_container(true)
+        .uses_original_profile(true)
+        .build()?;
+    let decoder = decoder_builder().build()?;
+
+    let jpeg = reconstruct(&decoder, data)?;
+    transcode(&mut encoder, &decoder, &jpeg)?;
+    Ok(jpeg)
+}
+
+/// Encode the JPEG and verify it is reconstructed bit-exactly
+fn transcode(
+    encoder: &mut JxlEncoder,
+    decoder: &JxlDecoder,
+    jpeg: &[u8],
+) -> Result<Vec<u8>, TranscodeError> {
+    let jxl = encoder.encode_jpeg(jpeg)?.data;
+    if reconstruct(decoder, &jxl)? == jpeg {
+        Ok(jxl)
+    } else {
+        Err(TranscodeError::VerificationFailed)
+    }
+}
+
+fn reconstruct(decoder: &JxlDecoder, jxl: &[u8]) -> Result<Vec<u8>, TranscodeError> {
+    match decoder.reconstruct(jxl)? {
+        (_, Data::Jpeg(jpeg)) => Ok(jpeg),
+        (_, Data::Pixels(_)) => Err(TranscodeError::NoReconstructionData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testresult::TestResult;
+
+    use super::*;
+    use crate::tests::{SAMPLE_JPEG, SAMPLE_JXL, SAMPLE_JXL_JPEG};
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_round_trip() -> TestResult {
+        let jxl = jpeg_to_jxl(SAMPLE_JPEG)?;
+        assert_eq!(jxl_to_jpeg(&jxl)?, SAMPLE_JPEG);
+        assert_eq!(jxl_to_jpeg(SAMPLE_JXL_JPEG)?, SAMPLE_JPEG);
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_errors() {
+        assert!(matches!(
+            jxl_to_jpeg(SAMPLE_JXL),
+            Err(TranscodeError::NoReconstructionData)
+        ));
+        assert!(matches!(
+            jpeg_to_jxl(SAMPLE_JXL),
+            Err(TranscodeError::Encode(_))
+        ));
+    }
+}
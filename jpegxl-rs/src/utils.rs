@@ -17,7 +17,12 @@ along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
 
 //! Utils functions when a decoder or encoder is not needed
 
-use jpegxl_sys::decode::{JxlSignature, JxlSignatureCheck};
+use std::fmt;
+
+use jpegxl_sys::{
+    decode::{JxlDecoderVersion, JxlSignature, JxlSignatureCheck},
+    encoder::encode::JxlEncoderVersion,
+};
 
 /// Check if the signature of the input is valid.
 /// Return `None` if it needs more data.
@@ -25,10 +30,88 @@ use jpegxl_sys::decode::{JxlSignature, JxlSignatureCheck};
 pub fn check_valid_signature(buf: &[u8]) -> Option<bool> {
     use JxlSignature::{Codestream, Container, Invalid, NotEnoughBytes};
 
-    match unsafe { JxlSignatureCheck(buf.as_ptr(), buf.len()) } {
-        NotEnoughBytes => None,
-        Invalid => Some(false),
-        Codestream | Container => Some(true),
+    match JxlSignature::try_from(unsafe { JxlSignatureCheck(buf.as_ptr(), buf.len()) }) {
+        Ok(NotEnoughBytes) => None,
+        Ok(Codestream | Container) => Some(true),
+        // A result unknown to the bindings is not a signature this crate can decode
+        Ok(Invalid) | Err(_) => Some(false),
+    }
+}
+
+/// Version of the linked `libjxl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    /// Major version
+    pub major: u32,
+    /// Minor version
+    pub minor: u32,
+    /// Patch version
+    pub patch: u32,
+}
+
+impl Version {
+    /// Create a version from the `MAJOR * 1000000 + MINOR * 1000 + PATCH` form used by `libjxl`
+    #[must_use]
+    pub const fn from_raw(raw: u32) -> Self {
+        Self {
+            major: raw / 1_000_000,
+            minor: raw / 1000 % 1000,
+            patch: raw % 1000,
+        }
+    }
+
+    const fn at_least(self, major: u32, minor: u32) -> bool {
+        self.major > major || (self.major == major && self.minor >= minor)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Return the version of the linked `libjxl` decoder
+#[must_use]
+pub fn version() -> Version {
+    Version::from_raw(unsafe { JxlDecoderVersion() })
+}
+
+/// Return the version of the linked `libjxl` encoder
+#[must_use]
+pub fn encoder_version() -> Version {
+    Version::from_raw(unsafe { JxlEncoderVersion() })
+}
+
+/// Features available in the linked `libjxl`, see [`capabilities`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Capabilities {
+    /// Whether the encoder can stream its output to a seekable writer (`libjxl` 0.10)
+    pub output_processor: bool,
+    /// Whether metadata boxes can be read and written, with the `boxes` feature
+    pub boxes: bool,
+    /// Whether gain map bundles can be read and written (`libjxl` 0.11)
+    pub gain_map: bool,
+    /// Whether `libjxl_threads` is linked, for `ThreadsRunner` and `ResizableRunner`
+    pub threads: bool,
+    /// Whether JPEG reconstruction and transcoding are built in
+    pub jpeg: bool,
+}
+
+/// Return the features available in the linked `libjxl` and this build of the crate
+#[must_use]
+pub fn capabilities() -> Capabilities {
+    let encoder = encoder_version();
+    Capabilities {
+        output_processor: !cfg!(feature = "libjxl_0_9") && encoder.at_least(0, 10),
+        boxes: cfg!(feature = "boxes"),
+        gain_map: cfg!(all(feature = "boxes", not(feature = "libjxl_0_10")))
+            && version().at_least(0, 11)
+            && encoder.at_least(0, 11),
+        threads: cfg!(feature = "threads"),
+        jpeg: cfg!(feature = "jpeg"),
     }
 }
 
@@ -45,4 +128,18 @@ mod tests {
         assert_eq!(check_valid_signature(&[0; 64]), Some(false));
         assert_eq!(check_valid_signature(SAMPLE_JXL), Some(true));
     }
+
+    #[test]
+    fn test_version() {
+        let v = Version::from_raw(11_001);
+        assert_eq!((v.major, v.minor, v.patch), (0, 11, 1));
+        assert_eq!(v.to_string(), "0.11.1");
+        assert!(Version::from_raw(1_000_000) > v);
+
+        assert_eq!(version(), encoder_version());
+        assert_eq!(
+            capabilities().output_processor,
+            !cfg!(feature = "libjxl_0_9")
+        );
+    }
 }
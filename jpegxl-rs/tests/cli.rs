@@ -0,0 +1,92 @@
+/*
+ * This file is part of jpegxl-rs.
+ *
+ * jpegxl-rs is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * jpegxl-rs is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with jpegxl-rs.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Round trips through the `cjxl-rs` and `djxl-rs` binaries
+
+use std::{ffi::OsStr, process::Command};
+
+use testresult::TestResult;
+
+const SAMPLE_PNG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../samples/sample.png");
+const SAMPLE_JPEG: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../samples/sample.jpg");
+
+fn run(bin: &str, args: &[&OsStr]) -> TestResult {
+    let output = Command::new(bin).args(args).output()?;
+    assert!(
+        output.status.success(),
+        "{bin} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+#[test]
+fn png() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("jpegxl-rs-cli-png-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let (jxl, png) = (dir.join("sample.jxl"), dir.join("sample.png"));
+
+    run(
+        env!("CARGO_BIN_EXE_cjxl-rs"),
+        &[
+            SAMPLE_PNG.as_ref(),
+            jxl.as_ref(),
+            "-d".as_ref(),
+            "0".as_ref(),
+        ],
+    )?;
+    run(
+        env!("CARGO_BIN_EXE_djxl-rs"),
+        &[jxl.as_ref(), png.as_ref(), "--bits".as_ref(), "16".as_ref()],
+    )?;
+
+    let original = image::open(SAMPLE_PNG)?.to_rgba16();
+    let decoded = image::open(&png)?.to_rgba16();
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(original.dimensions(), decoded.dimensions());
+    assert!(
+        original == decoded,
+        "lossless round trip changed the pixels"
+    );
+    Ok(())
+}
+
+#[test]
+fn jpeg() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("jpegxl-rs-cli-jpeg-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let (jxl, jpg) = (dir.join("sample.jxl"), dir.join("sample.jpg"));
+
+    run(
+        env!("CARGO_BIN_EXE_cjxl-rs"),
+        &[SAMPLE_JPEG.as_ref(), jxl.as_ref()],
+    )?;
+    run(env!("CARGO_BIN_EXE_djxl-rs"), &[jxl.as_ref(), jpg.as_ref()])?;
+
+    let reconstructed = std::fs::read(&jpg)?;
+    std::fs::remove_dir_all(&dir)?;
+    assert!(reconstructed == std::fs::read(SAMPLE_JPEG)?);
+    Ok(())
+}
+
+#[test]
+fn usage() -> TestResult {
+    let output = Command::new(env!("CARGO_BIN_EXE_djxl-rs")).output()?;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage"));
+    Ok(())
+}
@@ -29,9 +29,35 @@ fn source_dir() -> PathBuf {
     )
 }
 
+/// Parts of the source tree needed for the build, which are missing when the git submodules
+/// are not checked out
+fn missing_sources(source: &Path) -> Vec<&'static str> {
+    [
+        "CMakeLists.txt",
+        "third_party/brotli/CMakeLists.txt",
+        "third_party/highway/CMakeLists.txt",
+        "third_party/skcms/skcms.cc",
+    ]
+    .into_iter()
+    .filter(|p| !source.join(p).exists())
+    .collect()
+}
+
+/// Build libjxl and emit the `cargo:` directives to link it statically
+///
+/// # Panics
+/// Panics if the source tree is incomplete
 #[cfg_attr(coverage_nightly, coverage(off))]
 pub fn build() {
     let source = source_dir();
+    let missing = missing_sources(&source);
+    assert!(
+        missing.is_empty(),
+        "Incomplete libjxl source at `{}`, missing: {}. Run `git submodule update --init --recursive` \
+         or point `DEP_JXL_PATH` to a libjxl checkout.",
+        source.display(),
+        missing.join(", ")
+    );
 
     let mut config = cmake::Config::new(source);
     config
@@ -46,14 +72,32 @@ pub fn build() {
         .define("JPEGXL_ENABLE_SJPEG", "OFF")
         .define("JPEGXL_ENABLE_OPENEXR", "OFF")
         .define("JPEGXL_ENABLE_JPEGLI", "OFF")
-        .define("JPEGXL_BUNDLE_LIBPNG", "OFF");
+        .define("JPEGXL_BUNDLE_LIBPNG", "OFF")
+        // Always link the pinned third-party sources, never the ones of the system
+        .define("JPEGXL_FORCE_SYSTEM_BROTLI", "OFF")
+        .define("JPEGXL_FORCE_SYSTEM_HWY", "OFF");
 
     if let Ok(p) = std::thread::available_parallelism() {
         config.env("CMAKE_BUILD_PARALLEL_LEVEL", format!("{p}"));
     }
 
-    #[cfg(target_os = "windows")]
-    {
+    // Build scripts run on the host, so check the target through the environment
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let wasm = env::var("CARGO_CFG_TARGET_FAMILY").is_ok_and(|f| f.split(',').any(|f| f == "wasm"));
+    // Set by the build script of `jpegxl-sys`
+    let threads = env::var_os("CARGO_FEATURE_THREADS").is_some();
+
+    if wasm {
+        // The toolchain is taken from `CMAKE_TOOLCHAIN_FILE`, e.g. of emscripten or wasi-sdk
+        config
+            .define(
+                "JPEGXL_ENABLE_WASM_THREADS",
+                if threads { "ON" } else { "OFF" },
+            )
+            .define("JPEGXL_ENABLE_TCMALLOC", "OFF");
+    }
+
+    if target_os == "windows" {
         config
             .generator_toolset("ClangCL")
             .define(
@@ -74,20 +118,23 @@ pub fn build() {
 
     println!("cargo:rustc-link-lib=static=jxl");
     println!("cargo:rustc-link-lib=static=jxl_cms");
-    println!("cargo:rustc-link-lib=static=jxl_threads");
+    if threads {
+        println!("cargo:rustc-link-lib=static=jxl_threads");
+    }
 
     println!("cargo:rustc-link-lib=static=hwy");
     println!("cargo:rustc-link-lib=static=brotlidec");
     println!("cargo:rustc-link-lib=static=brotlienc");
     println!("cargo:rustc-link-lib=static=brotlicommon");
 
-    #[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
-    {
-        println!("cargo:rustc-link-lib=c++");
-    }
-    #[cfg(target_os = "linux")]
-    {
-        println!("cargo:rustc-link-lib=stdc++");
+    match target_os.as_str() {
+        "macos" | "ios" | "freebsd" | "emscripten" => println!("cargo:rustc-link-lib=c++"),
+        "wasi" => {
+            println!("cargo:rustc-link-lib=c++");
+            println!("cargo:rustc-link-lib=c++abi");
+        }
+        "linux" => println!("cargo:rustc-link-lib=stdc++"),
+        _ => {}
     }
 }
 
@@ -104,4 +151,14 @@ mod tests {
         path.push("lib/include/jxl/codestream_header.h");
         assert!(path.exists());
     }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_missing_sources() {
+        assert!(missing_sources(&source_dir()).is_empty());
+
+        let missing = missing_sources(Path::new(env!("CARGO_MANIFEST_DIR")));
+        assert!(missing.contains(&"CMakeLists.txt"));
+        assert!(missing.contains(&"third_party/highway/CMakeLists.txt"));
+    }
 }
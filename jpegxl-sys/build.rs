@@ -17,34 +17,200 @@
 
 //! Build script for jpegxl-sys.
 
+#[cfg(all(feature = "static", feature = "dynamic"))]
+compile_error!("Features `static` and `dynamic` are mutually exclusive");
+#[cfg(all(feature = "vendored", feature = "dynamic"))]
+compile_error!("`vendored` always links statically, and cannot be combined with `dynamic`");
+
 fn main() {
     #[cfg(all(not(feature = "vendored"), not(feature = "docs")))]
     {
-        use std::env;
-        let version = env!("CARGO_PKG_VERSION")
-            .split('+')
-            .nth(1)
-            .and_then(|s| s.split('-').nth(1))
-            .unwrap();
-
-        if let Ok(path) = env::var("DEP_JXL_LIB") {
-            println!("cargo:rustc-link-search=native={path}");
-            println!("cargo:rustc-link-lib=jxl");
-            println!("cargo:rustc-link-lib=jxl_threads");
+        // The oldest version enabled by the `libjxl_*` features wins
+        let version = if cfg!(feature = "libjxl_0_8") {
+            "0.8.0"
+        } else if cfg!(feature = "libjxl_0_9") {
+            "0.9.0"
+        } else if cfg!(feature = "libjxl_0_10") {
+            "0.10.0"
         } else {
-            pkg_config::Config::new()
-                .atleast_version(version)
-                .probe("libjxl")
-                .unwrap_or_else(|_| panic!("Cannot find `libjxl` with version >= {version}"));
-            pkg_config::Config::new()
-                .atleast_version(version)
-                .probe("libjxl_threads")
-                .unwrap_or_else(|_| {
-                    panic!("Cannot find `libjxl_threads` with version >= {version}")
-                });
-        }
+            env!("CARGO_PKG_VERSION")
+                .split('+')
+                .nth(1)
+                .and_then(|s| s.split('-').nth(1))
+                .unwrap()
+        };
+
+        #[cfg_attr(not(feature = "generate-bindings"), allow(unused_variables))]
+        let include_paths = find_libjxl(version);
+
+        #[cfg(feature = "generate-bindings")]
+        generate_bindings(&include_paths);
     }
 
     #[cfg(feature = "vendored")]
-    jpegxl_src::build();
+    {
+        jpegxl_src::build();
+        // `cmake` installs into the output directory of this build script
+        #[cfg(feature = "generate-bindings")]
+        generate_bindings(&[
+            std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("include")
+        ]);
+    }
+}
+
+#[cfg(all(not(feature = "vendored"), not(feature = "docs")))]
+fn env_var(key: &str) -> Option<String> {
+    println!("cargo:rerun-if-env-changed={key}");
+    std::env::var(key).ok().filter(|v| !v.is_empty())
+}
+
+/// Link the C++ standard library, which a static `libjxl` depends on
+#[cfg(all(not(feature = "vendored"), not(feature = "docs")))]
+fn link_cxx_stdlib() {
+    match std::env::var("CARGO_CFG_TARGET_OS")
+        .unwrap_or_default()
+        .as_str()
+    {
+        "macos" | "ios" | "freebsd" | "emscripten" => println!("cargo:rustc-link-lib=c++"),
+        "linux" => println!("cargo:rustc-link-lib=stdc++"),
+        _ => {}
+    }
+}
+
+/// Locate `libjxl` and emit the directives to link it, returning the directories of its headers
+///
+/// In order, the environment overrides, `pkg-config` and `vcpkg` are tried. The `static` and
+/// `dynamic` features force the kind of linking, except for `vcpkg` which follows its triplet.
+#[cfg(all(not(feature = "vendored"), not(feature = "docs")))]
+fn find_libjxl(version: &str) -> Vec<std::path::PathBuf> {
+    use std::path::{Path, PathBuf};
+
+    // Without either feature, leave the choice to the linker and `pkg-config`
+    let kind = if cfg!(feature = "static") {
+        "static="
+    } else if cfg!(feature = "dynamic") {
+        "dylib="
+    } else {
+        ""
+    };
+    let mut tried = Vec::new();
+
+    // `DEP_JXL_LIB` is kept for compatibility
+    if let Some(dir) = env_var("JXL_LIB_DIR").or_else(|| env_var("DEP_JXL_LIB")) {
+        println!("cargo:rustc-link-search=native={dir}");
+        #[cfg(feature = "threads")]
+        println!("cargo:rustc-link-lib={kind}jxl_threads");
+        println!("cargo:rustc-link-lib={kind}jxl");
+        if cfg!(feature = "static") {
+            // A shared `libjxl` brings its dependencies along, a static one does not
+            if cfg!(not(feature = "libjxl_0_8")) {
+                println!("cargo:rustc-link-lib=static=jxl_cms");
+            }
+            for lib in ["hwy", "brotlienc", "brotlidec", "brotlicommon"] {
+                println!("cargo:rustc-link-lib=static={lib}");
+            }
+            link_cxx_stdlib();
+        }
+
+        // Assume the usual `<prefix>/lib` and `<prefix>/include` layout by default
+        let include = env_var("JXL_INCLUDE_DIR")
+            .map_or_else(|| Path::new(&dir).with_file_name("include"), PathBuf::from);
+        return vec![include];
+    }
+    tried.push("`JXL_LIB_DIR`: not set".to_owned());
+
+    let probe = |name: &str, metadata: bool| {
+        let mut config = pkg_config::Config::new();
+        config.atleast_version(version).cargo_metadata(metadata);
+        if cfg!(any(feature = "static", feature = "dynamic")) {
+            config.statik(cfg!(feature = "static"));
+        }
+        config.probe(name)
+    };
+    let mut libs = vec!["libjxl"];
+    if cfg!(feature = "threads") {
+        libs.push("libjxl_threads");
+    }
+    // Only emit the directives once all libraries are found, so a partial match doesn't leak
+    // into the other methods
+    match libs.iter().try_for_each(|l| probe(l, false).map(drop)) {
+        Ok(()) => {
+            let mut include_paths = Vec::new();
+            for l in libs {
+                let lib = probe(l, true).expect("pkg-config results changed");
+                include_paths.extend(lib.include_paths);
+            }
+            if cfg!(feature = "static") {
+                link_cxx_stdlib();
+            }
+            return include_paths;
+        }
+        Err(e) => tried.push(format!("pkg-config: {e}")),
+    }
+
+    match vcpkg::Config::new().find_package("libjxl") {
+        Ok(lib) => return lib.include_paths,
+        Err(e) => tried.push(format!("vcpkg: {e}")),
+    }
+
+    panic!(
+        "Cannot find `libjxl` with version >= {version}, tried:\n\n{}\n\n\
+         Set `JXL_LIB_DIR` (and `JXL_INCLUDE_DIR`) to its location, \
+         or enable the `vendored` feature to build it from source.",
+        tried.join("\n\n")
+    );
+}
+
+/// Generate bindings from the libjxl headers, to be checked against the hand-written ones in
+/// `src/layout.rs`
+#[cfg(all(
+    feature = "generate-bindings",
+    any(feature = "vendored", not(feature = "docs"))
+))]
+fn generate_bindings(include_paths: &[std::path::PathBuf]) {
+    use std::{env, path::PathBuf};
+
+    let mut headers = vec![
+        "types.h",
+        "memory_manager.h",
+        "codestream_header.h",
+        "color_encoding.h",
+        "cms_interface.h",
+        "parallel_runner.h",
+        "decode.h",
+        "encode.h",
+    ];
+    if cfg!(not(feature = "libjxl_0_8")) {
+        headers.extend(["cms.h", "stats.h"]);
+    }
+    if cfg!(not(feature = "libjxl_0_10")) {
+        headers.extend(["compressed_icc.h", "gain_map.h"]);
+    }
+    if cfg!(feature = "threads") {
+        headers.extend(["thread_parallel_runner.h", "resizable_parallel_runner.h"]);
+    }
+    let wrapper = headers
+        .iter()
+        .map(|h| format!("#include <jxl/{h}>"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let bindings = bindgen::Builder::default()
+        .header_contents("wrapper.h", &wrapper)
+        .clang_args(include_paths.iter().map(|p| format!("-I{}", p.display())))
+        .allowlist_type("Jxl.*")
+        .allowlist_function("Jxl.*")
+        .allowlist_var("JXL_.*")
+        .layout_tests(false)
+        .derive_copy(false)
+        .derive_debug(false)
+        .generate_comments(false)
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .expect("Failed to generate bindings from the libjxl headers");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    bindings
+        .write_to_file(&out)
+        .unwrap_or_else(|e| panic!("Cannot write bindings to {}: {e}", out.display()));
 }
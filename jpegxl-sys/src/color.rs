@@ -17,6 +17,7 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 
 //! `jxl_color`: ICC profiles and color space conversions.
 
+#[cfg(not(feature = "libjxl_0_8"))]
 pub mod cms;
 pub mod cms_interface;
 pub mod color_encoding;
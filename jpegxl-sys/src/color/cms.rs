@@ -20,5 +20,9 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 use super::cms_interface::JxlCmsInterface;
 
 extern "C-unwind" {
+    /// Returns the default color management system, based on skcms or lcms2
+    /// depending on how `libjxl` was built, for use with
+    /// [`JxlDecoderSetCms`](crate::decode::JxlDecoderSetCms) and
+    /// [`JxlEncoderSetCms`](crate::encoder::encode::JxlEncoderSetCms).
     pub fn JxlGetDefaultCms() -> *const JxlCmsInterface;
 }
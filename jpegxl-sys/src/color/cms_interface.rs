@@ -28,6 +28,17 @@ use crate::common::types::JxlBool;
 
 use super::color_encoding::JxlColorEncoding;
 
+/// Parses an ICC profile and populates `c` and `cmyk` with the data.
+///
+/// # Parameters
+/// - `user_data`: [`JxlCmsInterface::set_fields_data`] passed as-is.
+/// - `icc_data`: the ICC data to parse.
+/// - `icc_size`: how many bytes of `icc_data` are valid.
+/// - `c`: a [`JxlColorEncoding`] to populate if applicable.
+/// - `cmyk`: a boolean to set to whether the colorspace is a CMYK colorspace.
+///
+/// # Returns
+/// Whether the relevant fields in `c` were successfully populated.
 pub type JpegXlCmsSetFieldsFromIccFunc = extern "C-unwind" fn(
     user_data: *mut c_void,
     icc_data: *const u8,
@@ -36,21 +47,61 @@ pub type JpegXlCmsSetFieldsFromIccFunc = extern "C-unwind" fn(
     cmyk: *mut JxlBool,
 ) -> JxlBool;
 
+/// The serialized ICC profile of a [`JxlColorProfile`].
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct JxlColorProfileIcc {
-    data: *const u8,
-    size: usize,
+    /// Start of the profile data.
+    pub data: *const u8,
+    /// Size of the profile data in bytes.
+    pub size: usize,
 }
 
+/// Represents an input or output colorspace to a color transform, as a
+/// serialized ICC profile.
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct JxlColorProfile {
+    /// The serialized ICC profile. This is guaranteed to be present and valid.
     pub icc: JxlColorProfileIcc,
+    /// Structured representation of the colorspace, if applicable. If all fields
+    /// are different from their "unknown" value, then this is equivalent to the
+    /// ICC representation of the colorspace. If some are "unknown", those that are
+    /// not are still valid and can still be used on their own if they are useful.
     pub color_encoding: JxlColorEncoding,
+    /// Number of components per pixel. This can be deduced from the other
+    /// representations of the colorspace but is provided for convenience and
+    /// validation.
     pub num_channels: usize,
 }
 
+/// Allocates and returns the data needed for `num_threads` parallel transforms
+/// from the `input_profile` colorspace to `output_profile`, with up to `pixels_per_thread`
+/// pixels to transform per call to [`JxlCmsInterface::run`]. `init_data` comes
+/// directly from the [`JxlCmsInterface`] instance. Since `run` only receives
+/// the data returned by `init`, a reference to `init_data` should be kept
+/// there if access to it is desired in `run`. Likewise for [`JxlCmsInterface::destroy`].
+///
+/// The ICC data in `input_profile` and `output_profile` is guaranteed to outlive the `init` /
+/// `run` / `destroy` cycle.
+///
+/// # Parameters
+/// - `init_data`: [`JxlCmsInterface::init_data`] passed as-is.
+/// - `num_threads`: the maximum number of threads from which [`JxlCmsInterface::run`]
+///   will be called.
+/// - `pixels_per_thread`: the maximum number of pixels that each call to
+///   [`JxlCmsInterface::run`] will have to transform.
+/// - `input_profile`: the input colorspace for the transform.
+/// - `output_profile`: the colorspace to which [`JxlCmsInterface::run`] should convert
+///   the input data.
+/// - `intensity_target`: for colorspaces where luminance is relative (essentially: not PQ),
+///   indicates the luminance at which (1, 1, 1) will be displayed. It is also used for
+///   conversions to and from HLG, as it is scene-referred while other colorspaces are
+///   assumed to be display-referred.
+///
+/// # Returns
+/// The data needed for the transform, or null in case of failure. This will be
+/// passed to the other functions as `user_data`.
 pub type JpegXlCmsInitFunc = extern "C-unwind" fn(
     init_data: *mut c_void,
     num_threads: usize,
@@ -60,9 +111,41 @@ pub type JpegXlCmsInitFunc = extern "C-unwind" fn(
     intensity_target: f32,
 ) -> *mut c_void;
 
+/// Returns a buffer that can be used by callers of the interface to store the
+/// input of the conversion or read its result, if they pass it as the input or
+/// output of the `run` function.
+///
+/// # Parameters
+/// - `user_data`: the data returned by `init`.
+/// - `thread`: the index of the thread for which to return a buffer.
+///
+/// # Returns
+/// A buffer that can be used by the caller for passing to `run`.
 pub type JpegXlCmsGetBufferFunc =
     extern "C-unwind" fn(user_data: *mut c_void, thread: usize) -> *mut f32;
 
+/// Executes one transform and returns true on success or false on error. It
+/// must be possible to call this from different threads with different values
+/// for `thread`, all between 0 (inclusive) and the value of `num_threads`
+/// passed to `init` (exclusive). It is allowed to implement this by locking
+/// such that the transforms are essentially performed sequentially.
+///
+/// The buffers each contain `num_pixels` × `num_channels` interleaved floating
+/// point (0..1) samples where `num_channels` is the number of color channels of
+/// their respective color profiles. The only case in which they might overlap is
+/// if the output has fewer channels than the input, in which case the pointers may
+/// be identical. For CMYK data, 0 represents the maximum amount of ink while 1
+/// represents no ink.
+///
+/// # Parameters
+/// - `user_data`: the data returned by `init`.
+/// - `thread`: the index of the thread from which the function is being called.
+/// - `input_buffer`: the buffer containing the pixel data to be transformed.
+/// - `output_buffer`: the buffer receiving the transformed pixel data.
+/// - `num_pixels`: the number of pixels to transform from `input_buffer` to `output_buffer`.
+///
+/// # Returns
+/// [`JxlBool::True`] on success, [`JxlBool::False`] on failure.
 pub type JpegXlCmsRunFunc = extern "C-unwind" fn(
     user_data: *mut c_void,
     thread: usize,
@@ -71,17 +154,33 @@ pub type JpegXlCmsRunFunc = extern "C-unwind" fn(
     num_pixels: usize,
 ) -> JxlBool;
 
+/// Performs the necessary clean-up and frees the memory allocated for user data.
 pub type JpegXlCmsDestroyFun = extern "C-unwind" fn(user_data: *mut c_void);
 
+/// Interface for performing colorspace transforms. The `init` function can be
+/// called several times to instantiate several transforms, including before
+/// other transforms have been destroyed.
+///
+/// For a given transform, `init` is called once, then `run` is called from up to
+/// `num_threads` threads, each with its own `thread` index, and finally `destroy`
+/// releases the data returned by `init`.
 #[repr(C)]
 #[derive(Debug, Clone)]
 pub struct JxlCmsInterface {
+    /// CMS-specific data that will be passed to [`set_fields_from_icc`](Self::set_fields_from_icc).
     pub set_fields_data: *mut c_void,
+    /// Populates a [`JxlColorEncoding`] from an ICC profile.
     pub set_fields_from_icc: JpegXlCmsSetFieldsFromIccFunc,
+    /// CMS-specific data that will be passed to [`init`](Self::init).
     pub init_data: *mut c_void,
+    /// Prepares a colorspace transform as described in the documentation of [`JpegXlCmsInitFunc`].
     pub init: JpegXlCmsInitFunc,
+    /// Returns a buffer that can be used as input to `run`.
     pub get_src_buf: JpegXlCmsGetBufferFunc,
+    /// Returns a buffer that can be used as output from `run`.
     pub get_dst_buf: JpegXlCmsGetBufferFunc,
+    /// Executes the transform on a batch of pixels, per [`JpegXlCmsRunFunc`].
     pub run: JpegXlCmsRunFunc,
+    /// Cleans up the transform.
     pub destroy: JpegXlCmsDestroyFun,
 }
@@ -24,6 +24,7 @@ use crate::metadata::codestream_header::JxlBasicInfo;
 /// Color space of the image data.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlColorSpace {
     /// Tristimulus RGB
     Rgb = 0,
@@ -37,6 +38,13 @@ pub enum JxlColorSpace {
     Unknown,
 }
 
+impl_try_from_i32!(JxlColorSpace {
+    Rgb,
+    Gray,
+    Xyb,
+    Unknown,
+});
+
 /// Built-in white points for color encoding. When decoding, the numerical xy
 /// white point value can be read from the [`JxlColorEncoding::white_point`]
 /// field regardless of the enum value. When encoding, enum values except
@@ -45,6 +53,7 @@ pub enum JxlColorSpace {
 /// the white point and RGB primaries are separate enums here.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlWhitePoint {
     /// CIE Standard Illuminant D65: 0.3127, 0.3290
     D65 = 1,
@@ -58,6 +67,13 @@ pub enum JxlWhitePoint {
     Dci = 11,
 }
 
+impl_try_from_i32!(JxlWhitePoint {
+    D65,
+    Custom,
+    E,
+    Dci,
+});
+
 /// Built-in primaries for color encoding. When decoding, the primaries can be
 /// read from the [`JxlColorEncoding::primaries_red_xy`], [`JxlColorEncoding::primaries_green_xy`],
 /// and [`JxlColorEncoding::primaries_blue_xy`] fields regardless of the enum value. When encoding,
@@ -66,6 +82,7 @@ pub enum JxlWhitePoint {
 /// and RGB primaries are separate enums here.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlPrimaries {
     /// The CIE xy values of the red, green and blue primaries are: 0.639998686,
     /// 0.330010138; 0.300003784, 0.600003357; 0.150002046, 0.059997204
@@ -80,11 +97,19 @@ pub enum JxlPrimaries {
     P3 = 11,
 }
 
+impl_try_from_i32!(JxlPrimaries {
+    SRgb,
+    Custom,
+    Rec2100,
+    P3,
+});
+
 /// Built-in transfer functions for color encoding. Enum values match a subset
 /// of CICP (Rec. ITU-T H.273 | ISO/IEC 23091-2:2019(E)) unless specified
 /// otherwise.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlTransferFunction {
     /// As specified in ITU-R BT.709-6
     BT709 = 1,
@@ -105,6 +130,17 @@ pub enum JxlTransferFunction {
     Gamma = 65535,
 }
 
+impl_try_from_i32!(JxlTransferFunction {
+    BT709,
+    Unknown,
+    Linear,
+    SRGB,
+    PQ,
+    DCI,
+    HLG,
+    Gamma,
+});
+
 /// Rendering intent for color encoding, as specified in ISO 15076-1:2010
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -119,6 +155,13 @@ pub enum JxlRenderingIntent {
     Absolute,
 }
 
+impl_try_from_i32!(JxlRenderingIntent {
+    Perceptual,
+    Relative,
+    Saturation,
+    Absolute,
+});
+
 /// Color encoding of the image as structured information.
 #[repr(C)]
 #[derive(Clone, Debug)]
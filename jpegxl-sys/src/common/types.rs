@@ -29,6 +29,8 @@ pub enum JxlBool {
     False = 0,
 }
 
+impl_try_from_i32!(JxlBool { True, False });
+
 impl From<bool> for JxlBool {
     fn from(b: bool) -> Self {
         if b {
@@ -59,6 +61,13 @@ pub enum JxlDataType {
     Float16 = 5,
 }
 
+impl_try_from_i32!(JxlDataType {
+    Float,
+    Uint8,
+    Uint16,
+    Float16,
+});
+
 /// Ordering of multi-byte data.
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -73,6 +82,12 @@ pub enum JxlEndianness {
     Big = 2,
 }
 
+impl_try_from_i32!(JxlEndianness {
+    Native,
+    Little,
+    Big,
+});
+
 /// Data type for the sample values per channel per pixel for the output buffer
 /// for pixels. This is not necessarily the same as the data type encoded in the
 /// codestream. The channels are interleaved per pixel. The pixels are
@@ -128,6 +143,12 @@ pub enum JxlBitDepthType {
     Custom = 2,
 }
 
+impl_try_from_i32!(JxlBitDepthType {
+    FromPixelFormat,
+    FromCodestream,
+    Custom,
+});
+
 /// Data type for describing the interpretation of the input and output buffers
 /// in terms of the range of allowed input and output pixel values.
 #[repr(C)]
@@ -16,14 +16,19 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 //! Decoding API for JPEG XL.
+//!
+//! Functions return the raw status as a `c_int`, since a newer libjxl may return a value
+//! these bindings don't know. Convert it with [`JxlDecoderStatus::try_from`].
 
 use std::{
     ffi::c_void,
     os::raw::{c_char, c_int},
 };
 
+#[cfg(not(feature = "libjxl_0_8"))]
+use crate::color::cms_interface::JxlCmsInterface;
 use crate::{
-    color::{cms_interface::JxlCmsInterface, color_encoding::JxlColorEncoding},
+    color::color_encoding::JxlColorEncoding,
     common::memory_manager::JxlMemoryManager,
     common::types::{JxlBitDepth, JxlBool, JxlBoxType, JxlPixelFormat},
     metadata::codestream_header::{
@@ -37,7 +42,7 @@ use crate::{
     metadata::codestream_header::{JxlOrientation, JxlPreviewHeader},
 };
 
-/// The result of [`JxlSignatureCheck`].
+/// The result of [`JxlSignatureCheck`], converted from its raw value with `TryFrom<i32>`.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum JxlSignature {
@@ -53,6 +58,13 @@ pub enum JxlSignature {
     Container = 3,
 }
 
+impl_try_from_i32!(JxlSignature {
+    NotEnoughBytes,
+    Invalid,
+    Codestream,
+    Container,
+});
+
 /// Opaque structure that holds the JPEG XL decoder.
 ///
 /// Allocated and initialized with [`JxlDecoderCreate`].
@@ -68,6 +80,7 @@ pub struct JxlDecoder {
 /// have not been registered with [`JxlDecoderSubscribeEvents`].
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlDecoderStatus {
     /// Function call finished successfully, or decoding is finished and there is
     /// nothing more to be done.
@@ -247,6 +260,25 @@ pub enum JxlDecoderStatus {
     BoxComplete = 0x10000,
 }
 
+impl_try_from_i32!(JxlDecoderStatus {
+    Success,
+    Error,
+    NeedMoreInput,
+    NeedPreviewOutBuffer,
+    NeedImageOutBuffer,
+    JPEGNeedMoreOutput,
+    BoxNeedMoreOutput,
+    BasicInfo,
+    ColorEncoding,
+    PreviewImage,
+    Frame,
+    FullImage,
+    JPEGReconstruction,
+    Box,
+    FrameProgression,
+    BoxComplete,
+});
+
 /// Types of progressive detail.
 /// Setting a progressive detail with value N implies all progressive details
 /// with smaller or equal value. Currently only the following level of
@@ -275,6 +307,16 @@ pub enum JxlProgressiveDetail {
     Groups = 6,
 }
 
+impl_try_from_i32!(JxlProgressiveDetail {
+    Frames,
+    DC,
+    LastPasses,
+    Passes,
+    DCProgressive,
+    DCGroups,
+    Groups,
+});
+
 /// Defines which color profile to get: the profile from the codestream
 /// metadata header, which represents the color profile of the original image,
 /// or the color profile from the pixel data produced by the decoder. Both are
@@ -286,6 +328,8 @@ pub enum JxlColorProfileTarget {
     Data = 1,
 }
 
+impl_try_from_i32!(JxlColorProfileTarget { Original, Data });
+
 /// Function type for [`JxlDecoderSetImageOutCallback`].
 ///
 /// The callback may be called simultaneously by different threads when using a
@@ -312,11 +356,11 @@ pub type JxlImageOutCallback = extern "C" fn(
 ///
 /// # Parameters
 /// - `init_opaque`: optional user data, as given to
-///     [`JxlDecoderSetMultithreadedImageOutCallback`].
+///   [`JxlDecoderSetMultithreadedImageOutCallback`].
 /// - `num_threads`: maximum number of threads that will call the [`run`](JxlImageOutRunCallback)
-///     callback concurrently.
+///   callback concurrently.
 /// - `num_pixels_per_thread`: maximum number of pixels that will be passed in
-///     one call to [`run`](JxlImageOutRunCallback).
+///   one call to [`run`](JxlImageOutRunCallback).
 ///
 /// # Returns
 /// - a pointer to data that will be passed to the [`run`](JxlImageOutRunCallback) callback, or
@@ -332,16 +376,16 @@ pub type JxlImageOutInitCallback = extern "C" fn(
 /// # Parameters
 /// - `run_opaque`: user data returned by the [`init`](JxlImageOutInitCallback) callback.
 /// - `thread_id`: number in `[0, num_threads)` identifying the thread of the
-///     current invocation of the callback.
+///   current invocation of the callback.
 /// - `x`: horizontal position of the first (leftmost) pixel of the pixel data.
 /// - `y`: vertical position of the pixel data.
 /// - `num_pixels`: number of pixels in the pixel data. May be less than the
-///     full `xsize` of the image, and will be at most equal to the `num_pixels_per_thread`
-///     that was passed to [`init`](JxlImageOutInitCallback).
+///   full `xsize` of the image, and will be at most equal to the `num_pixels_per_thread`
+///   that was passed to [`init`](JxlImageOutInitCallback).
 /// - `pixels`: pixel data as a horizontal stripe, in the format passed to
-///     [`JxlDecoderSetMultithreadedImageOutCallback`]. The data pointed to
-///     remains owned by the caller and is only guaranteed to outlive the current
-///     callback invocation.
+///   [`JxlDecoderSetMultithreadedImageOutCallback`]. The data pointed to
+///   remains owned by the caller and is only guaranteed to outlive the current
+///   callback invocation.
 pub type JxlImageOutRunCallback = extern "C" fn(
     run_opaque: *mut c_void,
     thread_id: usize,
@@ -382,7 +426,7 @@ extern "C-unwind" {
     /// - [`JxlSignature::Codestream`] if a valid JPEG XL codestream signature was
     ///   found.
     /// - [`JxlSignature::Container`] if a valid JPEG XL container signature was found.
-    pub fn JxlSignatureCheck(buf: *const u8, len: usize) -> JxlSignature;
+    pub fn JxlSignatureCheck(buf: *const u8, len: usize) -> c_int;
 
     /// Creates an instance of [`JxlDecoder`] and initializes it.
     ///
@@ -469,7 +513,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if there is a frame to skip, and
     /// - [`JxlDecoderStatus::Error`] if the function was not called during frame processing.
-    pub fn JxlDecoderSkipCurrentFrame(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+    pub fn JxlDecoderSkipCurrentFrame(dec: *mut JxlDecoder) -> c_int;
 
     /// Set the parallel runner for multithreading. May only be set before starting
     /// decoding.
@@ -488,7 +532,7 @@ extern "C-unwind" {
         dec: *mut JxlDecoder,
         parallel_runner: JxlParallelRunner,
         parallel_runner_opaque: *mut c_void,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Returns a hint indicating how many more bytes the decoder is expected to
     /// need to make [`JxlDecoderGetBasicInfo`] available after the next
@@ -522,10 +566,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if no error, [`JxlDecoderStatus::Error`] otherwise.
-    pub fn JxlDecoderSubscribeEvents(
-        dec: *mut JxlDecoder,
-        events_wanted: c_int,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSubscribeEvents(dec: *mut JxlDecoder, events_wanted: c_int) -> c_int;
 
     /// Enables or disables preserving of as-in-bitstream pixeldata
     /// orientation. Some images are encoded with an Orientation tag
@@ -560,10 +601,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if no error, [`JxlDecoderStatus::Error`] otherwise.
-    pub fn JxlDecoderSetKeepOrientation(
-        dec: *mut JxlDecoder,
-        keep_orientation: JxlBool,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetKeepOrientation(dec: *mut JxlDecoder, keep_orientation: JxlBool) -> c_int;
 
     /// Enables or disables preserving of associated alpha channels. If
     /// `unpremul_alpha` is set to [`JxlBool::False`] then for associated alpha channel,
@@ -582,10 +620,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if no error, [`JxlDecoderStatus::Error`] otherwise.
-    pub fn JxlDecoderSetUnpremultiplyAlpha(
-        dec: *mut JxlDecoder,
-        unpremul_alpha: JxlBool,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetUnpremultiplyAlpha(dec: *mut JxlDecoder, unpremul_alpha: JxlBool) -> c_int;
 
     /// Enables or disables rendering spot colors. By default, spot colors
     /// are rendered, which is OK for viewing the decoded image. If `render_spotcolors`
@@ -599,10 +634,8 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if no error, [`JxlDecoderStatus::Error`] otherwise.
-    pub fn JxlDecoderSetRenderSpotcolors(
-        dec: *mut JxlDecoder,
-        render_spotcolors: JxlBool,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetRenderSpotcolors(dec: *mut JxlDecoder, render_spotcolors: JxlBool)
+        -> c_int;
 
     /// Enables or disables coalescing of zero-duration frames. By default, frames
     /// are returned with coalescing enabled, i.e. all frames have the image
@@ -619,7 +652,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if no error, [`JxlDecoderStatus::Error`] otherwise.
-    pub fn JxlDecoderSetCoalescing(dec: *mut JxlDecoder, coalescing: JxlBool) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetCoalescing(dec: *mut JxlDecoder, coalescing: JxlBool) -> c_int;
 
     /// Decodes JPEG XL file using the available bytes. Requires input has been
     /// set with [`JxlDecoderSetInput`]. After [`JxlDecoderProcessInput`], input
@@ -659,7 +692,7 @@ extern "C-unwind" {
     ///   available and output in the preview buffer.
     /// - [`JxlDecoderStatus::FullImage`] when all pixel information at highest detail
     ///   is available and has been output in the pixel buffer.
-    pub fn JxlDecoderProcessInput(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+    pub fn JxlDecoderProcessInput(dec: *mut JxlDecoder) -> c_int;
 
     /// Sets input data for [`JxlDecoderProcessInput`]. The data is owned by the
     /// caller and may be used by the decoder until [`JxlDecoderReleaseInput`] is
@@ -677,11 +710,7 @@ extern "C-unwind" {
     /// - [`JxlDecoderStatus::Error`] if input was already set without releasing or [`JxlDecoderCloseInput`]
     ///   was already called
     /// - [`JxlDecoderStatus::Success`] otherwise
-    pub fn JxlDecoderSetInput(
-        dec: *mut JxlDecoder,
-        data: *const u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetInput(dec: *mut JxlDecoder, data: *const u8, size: usize) -> c_int;
 
     /// Releases input which was provided with [`JxlDecoderSetInput`]. Between
     /// [`JxlDecoderProcessInput`] and [`JxlDecoderReleaseInput`], the user may not
@@ -729,16 +758,13 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `info`: struct to copy the information into, or `NULL` to only check
-    ///     whether the information is available through the return value.
+    ///   whether the information is available through the return value.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the value is available
     /// - [`JxlDecoderStatus::NeedMoreInput`] if not yet available
     /// - [`JxlDecoderStatus::Error`] in case of other error conditions.
-    pub fn JxlDecoderGetBasicInfo(
-        dec: *const JxlDecoder,
-        info: *mut JxlBasicInfo,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderGetBasicInfo(dec: *const JxlDecoder, info: *mut JxlBasicInfo) -> c_int;
 
     /// Outputs information for extra channel at the given index. The index must be
     /// smaller than `num_extra_channels` in the associated [`JxlBasicInfo`].
@@ -747,7 +773,7 @@ extern "C-unwind" {
     /// - `dec`: decoder object
     /// - `index`: index of the extra channel to query.
     /// - `info`: struct to copy the information into, or `NULL` to only check
-    ///     whether the information is available through the return value.
+    ///   whether the information is available through the return value.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the value is available
@@ -757,7 +783,7 @@ extern "C-unwind" {
         dec: *const JxlDecoder,
         index: usize,
         info: *mut JxlExtraChannelInfo,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Outputs name for extra channel at the given index in UTF-8. The index must be
     /// smaller than `num_extra_channels` in the associated [`JxlBasicInfo`]. The
@@ -779,7 +805,7 @@ extern "C-unwind" {
         index: usize,
         name: *mut c_char,
         size: usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Outputs the color profile as JPEG XL encoded structured data, if available.
     /// This is an alternative to an ICC Profile, which can represent a more limited
@@ -834,7 +860,7 @@ extern "C-unwind" {
         dec: *const JxlDecoder,
         target: JxlColorProfileTarget,
         color_encoding: *mut JxlColorEncoding,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Outputs the size in bytes of the ICC profile returned by [`JxlDecoderGetColorAsICCProfile`], if available,
     /// or indicates there is none available. In most cases, the image will have an ICC profile available, but
@@ -848,22 +874,22 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `target`: whether to get the original color profile from the metadata
-    ///     or the color profile of the decoded pixels.
+    ///   or the color profile of the decoded pixels.
     /// - `size`: variable to output the size into, or `NULL` to only check the
-    ///     return status.
+    ///   return status.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the ICC profile is available
     /// - [`JxlDecoderStatus::NeedMoreInput`] if the decoder has not yet received enough
-    ///     input data to determine whether an ICC profile is available or what its
-    ///     size is
+    ///   input data to determine whether an ICC profile is available or what its
+    ///   size is
     /// - [`JxlDecoderStatus::Error`] in case the ICC profile is not available and
-    ///     cannot be generated.
+    ///   cannot be generated.
     pub fn JxlDecoderGetICCProfileSize(
         dec: *const JxlDecoder,
         target: JxlColorProfileTarget,
         size: *mut usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Outputs ICC profile if available. The profile is only available if
     /// [`JxlDecoderGetICCProfileSize`] returns success. The output buffer must have
@@ -886,7 +912,7 @@ extern "C-unwind" {
         target: JxlColorProfileTarget,
         icc_profile: *mut u8,
         size: usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the desired output color profile of the decoded image by calling
     /// [`JxlDecoderSetOutputColorProfile`], passing on `color_encoding` and
@@ -902,7 +928,7 @@ extern "C-unwind" {
     pub fn JxlDecoderSetPreferredColorProfile(
         dec: *mut JxlDecoder,
         color_encoding: *const JxlColorEncoding,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Requests that the decoder perform tone mapping to the peak display luminance
     /// passed as `desired_intensity_target`, if appropriate.
@@ -921,7 +947,7 @@ extern "C-unwind" {
     pub fn JxlDecoderSetDesiredIntensityTarget(
         dec: *mut JxlDecoder,
         desired_intensity_target: f32,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the desired output color profile of the decoded image either from a
     /// color encoding or an ICC profile. Valid calls of this function have either
@@ -965,12 +991,13 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the color profile was set successfully,
     ///   [`JxlDecoderStatus::Error`] otherwise.
+    #[cfg(not(feature = "libjxl_0_8"))]
     pub fn JxlDecoderSetOutputColorProfile(
         dec: *mut JxlDecoder,
         color_encoding: *const JxlColorEncoding,
         icc_data: *const u8,
         icc_size: usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the color management system (CMS) that will be used for color
     /// conversion (if applicable) during decoding. May only be set before starting
@@ -982,7 +1009,8 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object.
     /// - `cms`: structure representing a CMS implementation. See [`JxlCmsInterface`] for more details.
-    pub fn JxlDecoderSetCms(dec: *mut JxlDecoder, cms: JxlCmsInterface) -> JxlDecoderStatus;
+    #[cfg(not(feature = "libjxl_0_8"))]
+    pub fn JxlDecoderSetCms(dec: *mut JxlDecoder, cms: JxlCmsInterface) -> c_int;
 
     /// Returns the minimum size in bytes of the preview image output pixel buffer
     /// for the given format. This is the buffer for [`JxlDecoderSetPreviewOutBuffer`].
@@ -1000,7 +1028,7 @@ extern "C-unwind" {
         dec: *const JxlDecoder,
         format: *const JxlPixelFormat,
         size: *mut usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to write the low-resolution preview image
     /// to. The size of the buffer must be at least as large as given by [`JxlDecoderPreviewOutBufferSize`].
@@ -1010,19 +1038,19 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of pixels. Object owned by user and its contents are
-    ///     copied internally.
+    ///   copied internally.
     /// - `buffer`: buffer type to output the pixel data to
     /// - `size`: size of buffer in bytes
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     size too small.
+    ///   size too small.
     pub fn JxlDecoderSetPreviewOutBuffer(
         dec: *mut JxlDecoder,
         format: *const JxlPixelFormat,
         buffer: *mut c_void,
         size: usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Outputs the information from the frame, such as duration when `have_animation`.
     /// This function can be called when [`JxlDecoderStatus::Frame`] occurred for the current
@@ -1031,16 +1059,13 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `header`: struct to copy the information into, or `NULL` to only check
-    ///     whether the information is available through the return value.
+    ///   whether the information is available through the return value.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the value is available
     /// - [`JxlDecoderStatus::NeedMoreInput`] if not yet available
     /// - [`JxlDecoderStatus::Error`] in case of other error conditions.
-    pub fn JxlDecoderGetFrameHeader(
-        dec: *const JxlDecoder,
-        header: *mut JxlFrameHeader,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderGetFrameHeader(dec: *const JxlDecoder, header: *mut JxlFrameHeader) -> c_int;
 
     /// Outputs name for the current frame. The buffer for name must have at least
     /// `name_length + 1` bytes allocated, gotten from the associated [`JxlFrameHeader`].
@@ -1055,11 +1080,7 @@ extern "C-unwind" {
     /// - [`JxlDecoderStatus::Success`] if the value is available
     /// - [`JxlDecoderStatus::NeedMoreInput`] if not yet available
     /// - [`JxlDecoderStatus::Error`] in case of other error conditions.
-    pub fn JxlDecoderGetFrameName(
-        dec: *const JxlDecoder,
-        name: *mut c_char,
-        size: usize,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderGetFrameName(dec: *const JxlDecoder, name: *mut c_char, size: usize) -> c_int;
 
     /// Outputs the blend information for the current frame for a specific extra
     /// channel. This function can be called once the [`JxlDecoderStatus::Frame`] event occurred
@@ -1096,12 +1117,12 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     information not available yet.
+    ///   information not available yet.
     pub fn JxlDecoderImageOutBufferSize(
         dec: *const JxlDecoder,
         format: *const JxlPixelFormat,
         size: *mut usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to write the full resolution image to. This can be set when
     /// the [`JxlDecoderStatus::Frame`] event occurs, must be set when the
@@ -1113,19 +1134,19 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of the pixels. Object owned by user and its contents
-    ///     are copied internally.
+    ///   are copied internally.
     /// - `buffer`: buffer type to output the pixel data to
     /// - `size`: size of buffer in bytes
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     size too small.
+    ///   size too small.
     pub fn JxlDecoderSetImageOutBuffer(
         dec: *mut JxlDecoder,
         format: *const JxlPixelFormat,
         buffer: *mut c_void,
         size: usize,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets pixel output callback. This is an alternative to [`JxlDecoderSetImageOutBuffer`].
     /// This can be set when the [`JxlDecoderStatus::Frame`] event occurs, must be set when the
@@ -1160,21 +1181,21 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of the pixels. Object owned by user; its contents are
-    ///     copied internally.
+    ///   copied internally.
     /// - `callback`: the callback function receiving partial scanlines of pixel
-    ///     data.
+    ///   data.
     /// - `opaque`: optional user data, which will be passed on to the callback,
-    ///     may be `NULL`.
+    ///   may be `NULL`.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such
-    ///     as [`JxlDecoderSetImageOutBuffer`] already set.
+    ///   as [`JxlDecoderSetImageOutBuffer`] already set.
     pub fn JxlDecoderSetImageOutCallback(
         dec: *mut JxlDecoder,
         format: *const JxlPixelFormat,
         callback: JxlImageOutCallback,
         opaque: *mut c_void,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Similar to [`JxlDecoderSetImageOutCallback`] except that the callback is
     /// allowed an initialization phase during which it is informed of how many
@@ -1184,19 +1205,19 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of the pixels. Object owned by user; its contents are
-    ///     copied internally.
+    ///   copied internally.
     /// - `init_callback`: initialization callback.
     /// - `run_callback`: the callback function receiving partial scanlines of
-    ///     pixel data.
+    ///   pixel data.
     /// - `destroy_callback`: clean-up callback invoked after all calls to `run_callback`.
-    ///     May be `NULL` if no clean-up is necessary.
+    ///   May be `NULL` if no clean-up is necessary.
     /// - `init_opaque`: optional user data passed to `init_callback`, may be `NULL`
-    ///     (unlike the return value from `init_callback` which may only be `NULL` if
-    ///     initialization failed).
+    ///   (unlike the return value from `init_callback` which may only be `NULL` if
+    ///   initialization failed).
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such
-    ///     as [`JxlDecoderSetImageOutBuffer`] having already been called.
+    ///   as [`JxlDecoderSetImageOutBuffer`] having already been called.
     pub fn JxlDecoderSetMultithreadedImageOutCallback(
         dec: *mut JxlDecoder,
         format: *const JxlPixelFormat,
@@ -1204,7 +1225,7 @@ extern "C-unwind" {
         run_callback: JxlImageOutRunCallback,
         destroy_callback: JxlImageOutDestroyCallback,
         init_opaque: *mut c_void,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Returns the minimum size in bytes of an extra channel pixel buffer for the
     /// given format. This is the buffer for [`JxlDecoderSetExtraChannelBuffer`].
@@ -1213,10 +1234,10 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of the pixels. The `num_channels` value is ignored and is
-    ///     always treated to be `1`.
+    ///   always treated to be `1`.
     /// - `size`: output value, buffer size in bytes
     /// - `index`: which extra channel to get, matching the index used in [`JxlDecoderGetExtraChannelInfo`].
-    ///     Must be smaller than `num_extra_channels` in the associated [`JxlBasicInfo`].
+    ///   Must be smaller than `num_extra_channels` in the associated [`JxlBasicInfo`].
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success
@@ -1226,7 +1247,7 @@ extern "C-unwind" {
         format: *const JxlPixelFormat,
         size: *mut usize,
         index: u32,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to write an extra channel to. This can be set when
     /// the [`JxlDecoderStatus::Frame`] or [`JxlDecoderStatus::NeedImageOutBuffer`] event occurs,
@@ -1245,23 +1266,23 @@ extern "C-unwind" {
     /// # Parameters
     /// - `dec`: decoder object
     /// - `format`: format of the pixels. Object owned by user and its contents
-    ///     are copied internally. The `num_channels` value is ignored and is always
-    ///     treated to be `1`.
+    ///   are copied internally. The `num_channels` value is ignored and is always
+    ///   treated to be `1`.
     /// - `buffer`: buffer type to output the pixel data to
     /// - `size`: size of buffer in bytes
     /// - `index`: which extra channel to get, matching the index used in [`JxlDecoderGetExtraChannelInfo`].
-    ///     Must be smaller than `num_extra_channels` in the associated [`JxlBasicInfo`].
+    ///   Must be smaller than `num_extra_channels` in the associated [`JxlBasicInfo`].
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     size too small or invalid index.
+    ///   size too small or invalid index.
     pub fn JxlDecoderSetExtraChannelBuffer(
         dec: *mut JxlDecoder,
         format: *const JxlPixelFormat,
         buffer: *mut c_void,
         size: usize,
         index: u32,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Sets output buffer for reconstructed JPEG codestream.
     ///
@@ -1279,12 +1300,8 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Error`] if output buffer was already set and [`JxlDecoderReleaseJPEGBuffer`]
-    ///     was not called on it, [`JxlDecoderStatus::Success`] otherwise
-    pub fn JxlDecoderSetJPEGBuffer(
-        dec: *mut JxlDecoder,
-        data: *mut u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
+    ///   was not called on it, [`JxlDecoderStatus::Success`] otherwise
+    pub fn JxlDecoderSetJPEGBuffer(dec: *mut JxlDecoder, data: *mut u8, size: usize) -> c_int;
 
     /// Releases buffer which was provided with [`JxlDecoderSetJPEGBuffer`].
     ///
@@ -1300,8 +1317,8 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - The amount of bytes the decoder has not yet written to of the data
-    ///     set by [`JxlDecoderSetJPEGBuffer`], or `0` if no buffer is set or
-    ///     [`JxlDecoderReleaseJPEGBuffer`] was already called.
+    ///   set by [`JxlDecoderSetJPEGBuffer`], or `0` if no buffer is set or
+    ///   [`JxlDecoderReleaseJPEGBuffer`] was already called.
     pub fn JxlDecoderReleaseJPEGBuffer(dec: *mut JxlDecoder) -> usize;
 
     /// Sets output buffer for box output codestream.
@@ -1322,12 +1339,8 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Error`] if output buffer was already set and [`JxlDecoderReleaseBoxBuffer`]
-    ///     was not called on it, [`JxlDecoderStatus::Success`] otherwise
-    pub fn JxlDecoderSetBoxBuffer(
-        dec: *mut JxlDecoder,
-        data: *mut u8,
-        size: usize,
-    ) -> JxlDecoderStatus;
+    ///   was not called on it, [`JxlDecoderStatus::Success`] otherwise
+    pub fn JxlDecoderSetBoxBuffer(dec: *mut JxlDecoder, data: *mut u8, size: usize) -> c_int;
 
     /// Releases buffer which was provided with [`JxlDecoderSetBoxBuffer`].
     ///
@@ -1342,7 +1355,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - The amount of bytes the decoder has not yet written to of the data set by [`JxlDecoderSetBoxBuffer`],
-    ///    or `0` if no buffer is set or [`JxlDecoderReleaseBoxBuffer`] was already called.,
+    ///   or `0` if no buffer is set or [`JxlDecoderReleaseBoxBuffer`] was already called.,
     pub fn JxlDecoderReleaseBoxBuffer(dec: *mut JxlDecoder) -> usize;
 
     /// Configures whether to get boxes in raw mode or in decompressed mode. In raw
@@ -1366,10 +1379,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlDecoderStatus::Error`] if decompressed mode is set and Brotli is not
     ///   available, [`JxlDecoderStatus::Success`] otherwise.
-    pub fn JxlDecoderSetDecompressBoxes(
-        dec: *mut JxlDecoder,
-        decompress: JxlBool,
-    ) -> JxlDecoderStatus;
+    pub fn JxlDecoderSetDecompressBoxes(dec: *mut JxlDecoder, decompress: JxlBool) -> c_int;
 
     /// Outputs the type of the current box, after a [`JxlDecoderStatus::Box`] event occurred,
     /// as `4` characters without null termination character. In case of a compressed
@@ -1432,7 +1442,7 @@ extern "C-unwind" {
     /// - `dec`: decoder object
     /// - `type`: buffer to copy the type into
     /// - `decompressed`: which box type to get: [`JxlBool::False`] to get the raw box type,
-    ///     which can be `"brob"`, [`JxlBool::True`] to get the underlying box type.
+    ///   which can be `"brob"`, [`JxlBool::True`] to get the underlying box type.
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] if the value is available
@@ -1441,7 +1451,7 @@ extern "C-unwind" {
         dec: *mut JxlDecoder,
         box_type: &mut JxlBoxType,
         decompressed: JxlBool,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Returns the size of a box as it appears in the container file, after the
     /// [`JxlDecoderStatus::Box`] event. This includes all the box headers.
@@ -1452,7 +1462,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Error`] if no box size is available, [`JxlDecoderStatus::Success`] otherwise.
-    pub fn JxlDecoderGetBoxSizeRaw(dec: *mut JxlDecoder, size: *mut u64) -> JxlDecoderStatus;
+    pub fn JxlDecoderGetBoxSizeRaw(dec: *mut JxlDecoder, size: *mut u64) -> c_int;
 
     /// Returns the size of the contents of a box, after the [`JxlDecoderStatus::Box`] event.
     /// This does not include any of the headers of the box. For compressed "brob" boxes,
@@ -1466,8 +1476,9 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Error`] if no box size is available, [`JxlDecoderStatus::Success`]
-    ///     otherwise.
-    pub fn JxlDecoderGetBoxSizeContents(dec: *mut JxlDecoder, size: *mut u64) -> JxlDecoderStatus;
+    ///   otherwise.
+    #[cfg(not(feature = "libjxl_0_10"))]
+    pub fn JxlDecoderGetBoxSizeContents(dec: *mut JxlDecoder, size: *mut u64) -> c_int;
 
     /// Configures at which progressive steps in frame decoding the [`JxlDecoderStatus::FrameProgression`] event occurs.
     /// The default value for the level of detail if this function is never called is [`JxlProgressiveDetail::DC`].
@@ -1478,11 +1489,11 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     an invalid value for the progressive detail.
+    ///   an invalid value for the progressive detail.
     pub fn JxlDecoderSetProgressiveDetail(
         dec: *mut JxlDecoder,
         detail: JxlProgressiveDetail,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 
     /// Returns the intended downsampling ratio for the progressive frame produced
     /// by [`JxlDecoderFlushImage`] after the latest [`JxlDecoderStatus::FrameProgression`] event.
@@ -1510,7 +1521,7 @@ extern "C-unwind" {
     /// - [`JxlDecoderStatus::Error`] when no flush was done, e.g., if not enough image data was available yet
     ///   even for flush, or no output buffer was set yet. This error is not fatal, it only indicates no flushed
     ///   image is available right now. Regular decoding can still be performed.
-    pub fn JxlDecoderFlushImage(dec: *mut JxlDecoder) -> JxlDecoderStatus;
+    pub fn JxlDecoderFlushImage(dec: *mut JxlDecoder) -> c_int;
 
     /// Sets the bit depth of the output buffer or callback.
     ///
@@ -1524,9 +1535,9 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// - [`JxlDecoderStatus::Success`] on success, [`JxlDecoderStatus::Error`] on error, such as
-    ///     incompatible custom bit depth and pixel data type.
+    ///   incompatible custom bit depth and pixel data type.
     pub fn JxlDecoderSetImageOutBitDepth(
         dec: *mut JxlDecoder,
         bit_depth: *const JxlBitDepth,
-    ) -> JxlDecoderStatus;
+    ) -> c_int;
 }
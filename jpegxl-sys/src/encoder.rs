@@ -18,4 +18,5 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 //! `jxl_encoder`: JPEG XL encoder.
 
 pub mod encode;
+#[cfg(not(feature = "libjxl_0_8"))]
 pub mod stats;
@@ -16,9 +16,16 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 */
 
 //! Encoding API for JPEG XL.
+//!
+//! Functions return the raw status as a `c_int`, since a newer libjxl may return a value
+//! these bindings don't know. Convert it with [`JxlEncoderStatus::try_from`] and
+//! [`JxlEncoderError::try_from`].
 
-use std::ffi::{c_char, c_void};
+#[cfg(not(feature = "libjxl_0_8"))]
+use std::ffi::c_char;
+use std::ffi::{c_int, c_void};
 
+#[cfg(not(feature = "libjxl_0_8"))]
 use super::stats::JxlEncoderStats;
 use crate::{
     color::{cms_interface::JxlCmsInterface, color_encoding::JxlColorEncoding},
@@ -31,10 +38,9 @@ use crate::{
 };
 
 #[cfg(doc)]
-use crate::{
-    common::types::{JxlBitDepthType, JxlDataType},
-    encoder::stats::JxlEncoderStatsCreate,
-};
+use crate::common::types::{JxlBitDepthType, JxlDataType};
+#[cfg(all(doc, not(feature = "libjxl_0_8")))]
+use crate::encoder::stats::JxlEncoderStatsCreate;
 
 /// Opaque structure that holds the JPEG XL encoder.
 ///
@@ -59,6 +65,7 @@ pub struct JxlEncoderFrameSettings {
 /// Return value for multiple encoder functions.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlEncoderStatus {
     /// Function call finished successfully, or encoding is finished and there is
     /// nothing more to be done
@@ -69,11 +76,18 @@ pub enum JxlEncoderStatus {
     NeedMoreOutput = 2,
 }
 
+impl_try_from_i32!(JxlEncoderStatus {
+    Success,
+    Error,
+    NeedMoreOutput,
+});
+
 /// Error conditions:
 /// API usage errors have the 0x80 bit set to 1
 /// Other errors have the 0x80 bit set to 0
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlEncoderError {
     /// No error
     OK = 0,
@@ -101,12 +115,23 @@ pub enum JxlEncoderError {
     ApiUsage = 0x81,
 }
 
+impl_try_from_i32!(JxlEncoderError {
+    OK,
+    Generic,
+    OutOfMemory,
+    Jbrd,
+    BadInput,
+    NotSupported,
+    ApiUsage,
+});
+
 /// Id of encoder options for a frame. This includes options such as setting
 /// encoding effort/speed or overriding the use of certain coding tools, for this
 /// frame. This does not include non-frame related encoder options such as for
 /// boxes.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlEncoderFrameSettingId {
     /// Sets encoder effort/speed level without affecting decoding speed. Valid
     /// values are, from faster to slower speed: 1:lightning 2:thunder 3:falcon
@@ -341,6 +366,50 @@ pub enum JxlEncoderFrameSettingId {
     FillEnum = 65535,
 }
 
+impl_try_from_i32!(JxlEncoderFrameSettingId {
+    Effort,
+    DecodingSpeed,
+    Resampling,
+    ExtraChannelResampling,
+    AlreadyDownsampled,
+    PhotonNoise,
+    Noise,
+    Dots,
+    Patches,
+    Epf,
+    Gaborish,
+    Modular,
+    KeepInvisible,
+    GroupOrder,
+    GroupOrderCenterX,
+    GroupOrderCenterY,
+    Responsive,
+    ProgressiveAc,
+    QprogressiveAc,
+    ProgressiveDc,
+    ChannelColorsGlobalPercent,
+    ChannelColorsGroupPercent,
+    PaletteColors,
+    LossyPalette,
+    ColorTransform,
+    ModularColorSpace,
+    ModularGroupSize,
+    ModularPredictor,
+    ModularMaTreeLearningPercent,
+    ModularNbPrevChannels,
+    JpegReconCfl,
+    IndexBox,
+    BrotliEffort,
+    JpegCompressBoxes,
+    Buffering,
+    JpegKeepExif,
+    JpegKeepXmp,
+    JpegKeepJumbf,
+    UseFullImageHeuristics,
+    DisablePerceptualHeuristics,
+    FillEnum,
+});
+
 /// The [`JxlEncoderOutputProcessor`] structure provides an interface for the
 /// encoder's output processing. Users of the library, who want to do streaming
 /// encoding, should implement the required callbacks for buffering, writing,
@@ -370,10 +439,11 @@ pub enum JxlEncoderFrameSettingId {
 /// All fields but `seek` are required, `seek` is optional and can be `None`.
 #[repr(C)]
 #[derive(Debug, Clone)]
+#[cfg(not(feature = "libjxl_0_9"))]
 pub struct JxlEncoderOutputProcessor {
     /// An opaque pointer that the client can use to store custom data.
     /// This data will be passed to the associated callback functions.
-    opaque: *mut c_void,
+    pub opaque: *mut c_void,
     /// Acquires a buffer at the current position into which the library will write
     /// the output data.
     ///
@@ -391,15 +461,15 @@ pub struct JxlEncoderOutputProcessor {
     /// # Returns
     /// A pointer to the acquired buffer or NULL to indicate a stop
     /// condition.
-    get_buffer: extern "C-unwind" fn(opaque: *mut c_void, size: *mut usize) -> *mut c_void,
+    pub get_buffer: extern "C-unwind" fn(opaque: *mut c_void, size: *mut usize) -> *mut c_void,
     /// Notifies the user of library that the current buffer's data has been
     /// written and can be released. This function should advance the current
-    /// osition of the buffer by `written_bytes` number of bytes.
+    /// position of the buffer by `written_bytes` number of bytes.
     ///
     /// # Parameters
     /// - `opaque`: user supplied parameters to the callback
     /// - `written_bytes`: the number of bytes written to the buffer.
-    release_buffer: extern "C-unwind" fn(opaque: *mut c_void, written_bytes: usize),
+    pub release_buffer: extern "C-unwind" fn(opaque: *mut c_void, written_bytes: usize),
     /// Seeks to a specific position in the output. This function is optional and
     /// can be set to `None` if the output doesn't support seeking. Can only be done
     /// when there is no buffer. Cannot be used to seek before the finalized
@@ -408,7 +478,7 @@ pub struct JxlEncoderOutputProcessor {
     /// # Parameters
     /// - `opaque`: User supplied parameters to the callback.
     /// - `position`: The position to seek to, in bytes.
-    seek: Option<extern "C-unwind" fn(opaque: *mut c_void, position: u64)>,
+    pub seek: Option<extern "C-unwind" fn(opaque: *mut c_void, position: u64)>,
     /// Sets a finalized position on the output data, at a specific position.
     /// Seeking will never request a position before the finalized position.
     ///
@@ -418,17 +488,18 @@ pub struct JxlEncoderOutputProcessor {
     /// - `opaque`: User supplied parameters to the callback.
     /// - `finalized_position`: The position, in bytes, where the finalized
     ///   position should be set.
-    set_finalized_position: extern "C-unwind" fn(opaque: *mut c_void, finalized_position: u64),
+    pub set_finalized_position: extern "C-unwind" fn(opaque: *mut c_void, finalized_position: u64),
 }
 
 /// This struct provides callback functions to pass pixel data in a streaming
 /// manner instead of requiring the entire frame data in memory at once.
 #[repr(C)]
 #[derive(Debug, Clone)]
+#[cfg(not(feature = "libjxl_0_9"))]
 pub struct JxlChunkedFrameInputSource {
     /// A pointer to any user-defined data or state. This can be used to pass
     /// information to the callback functions.
-    opaque: *mut c_void,
+    pub opaque: *mut c_void,
 
     /// Get the pixel format that color channel data will be provided in.
     /// When called, `pixel_format` points to a suggested pixel format; if
@@ -441,7 +512,7 @@ pub struct JxlChunkedFrameInputSource {
     /// # Parameters
     /// - `opaque`: User supplied parameters to the callback.
     /// - `pixel_format`: Format for pixels.
-    get_color_channels_pixel_format:
+    pub get_color_channels_pixel_format:
         extern "C-unwind" fn(opaque: *mut c_void, pixel_format: *mut JxlPixelFormat),
 
     /// Callback to retrieve a rectangle of color channel data at a specific
@@ -468,7 +539,7 @@ pub struct JxlChunkedFrameInputSource {
     ///
     /// # Returns
     /// Pointer to the retrieved pixel data.
-    get_color_channels_data_at: extern "C-unwind" fn(
+    pub get_color_channels_data_at: extern "C-unwind" fn(
         opaque: *mut c_void,
         xpos: usize,
         ypos: usize,
@@ -489,7 +560,7 @@ pub struct JxlChunkedFrameInputSource {
     /// - `opaque`: User supplied parameters to the callback.
     /// - `ec_index`: Zero-indexed index of the extra channel.
     /// - `pixel_format`: Format for extra channel data.
-    get_extra_channel_pixel_format: extern "C-unwind" fn(
+    pub get_extra_channel_pixel_format: extern "C-unwind" fn(
         opaque: *mut c_void,
         ec_index: usize,
         pixel_format: *mut JxlPixelFormat,
@@ -520,7 +591,7 @@ pub struct JxlChunkedFrameInputSource {
     ///
     /// # Returns
     /// Pointer to the retrieved pixel data.
-    get_extra_channel_data_at: extern "C-unwind" fn(
+    pub get_extra_channel_data_at: extern "C-unwind" fn(
         opaque: *mut c_void,
         ec_index: usize,
         xpos: usize,
@@ -539,7 +610,7 @@ pub struct JxlChunkedFrameInputSource {
     /// - `opaque`: User supplied parameters to the callback.
     /// - `buf`: Pointer returned by [`Self::get_color_channels_data_at`] or
     ///   [`Self::get_extra_channel_data_at`].
-    release_buffer: extern "C-unwind" fn(opaque: *mut c_void, buf: *const c_void),
+    pub release_buffer: extern "C-unwind" fn(opaque: *mut c_void, buf: *const c_void),
 }
 
 /// Function type for [`JxlEncoderSetDebugImageCallback`].
@@ -556,6 +627,7 @@ pub struct JxlChunkedFrameInputSource {
 /// - `pixels`: Pixel data of debug image as big-endian 16-bit unsigned samples.
 ///   The memory is not owned by the user, and is only valid during the time the
 ///   callback is running.
+#[cfg(not(feature = "libjxl_0_8"))]
 pub type JxlDebugImageCallback = extern "C-unwind" fn(
     opaque: *mut c_void,
     label: *const c_char,
@@ -626,7 +698,7 @@ extern "C-unwind" {
         enc: *mut JxlEncoder,
         parallel_runner: JxlParallelRunner,
         parallel_runner_opaque: *mut c_void,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Get the (last) error code in case [`JxlEncoderStatus::Error`] was returned.
     ///
@@ -635,7 +707,7 @@ extern "C-unwind" {
     ///
     /// # Returns
     /// The [`JxlEncoderError`] that caused the (last) [`JxlEncoderStatus::Error`] to be returned.
-    pub fn JxlEncoderGetError(enc: *mut JxlEncoder) -> JxlEncoderError;
+    pub fn JxlEncoderGetError(enc: *mut JxlEncoder) -> c_int;
 
     /// Encodes a JPEG XL file using the available bytes. `*avail_out` indicates how
     /// many output bytes are available, and `*next_out` points to the input bytes.
@@ -672,7 +744,7 @@ extern "C-unwind" {
         enc: *mut JxlEncoder,
         next_out: *mut *mut u8,
         avail_out: *mut usize,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the frame information for this frame to the encoder. This includes
     /// animation information such as frame duration to store in the frame header.
@@ -711,7 +783,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetFrameHeader(
         frame_settings: *mut JxlEncoderFrameSettings,
         frame_header: *const JxlFrameHeader,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets blend info of an extra channel. The blend info of extra channels is set
     /// separately from that of the color channels, the color channels are set with
@@ -730,7 +802,7 @@ extern "C-unwind" {
         frame_settings: *mut JxlEncoderFrameSettings,
         index: usize,
         blend_info: *const JxlBlendInfo,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the name of the animation frame. This function is optional, frames are
     /// not required to have a name. This setting is a part of the frame header, and
@@ -757,7 +829,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetFrameName(
         frame_settings: *mut JxlEncoderFrameSettings,
         frame_name: *const u8,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the bit depth of the input buffer.
     ///
@@ -777,7 +849,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetFrameBitDepth(
         frame_settings: *mut JxlEncoderFrameSettings,
         bit_depth: *const JxlBitDepth,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to read JPEG encoded bytes from for the next frame to encode.
     ///
@@ -813,7 +885,7 @@ extern "C-unwind" {
         options: *const JxlEncoderFrameSettings,
         buffer: *const u8,
         size: usize,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to read pixels from for the next image to encode. Must call
     /// [`JxlEncoderSetBasicInfo`] before [`JxlEncoderAddImageFrame`].
@@ -884,7 +956,7 @@ extern "C-unwind" {
         pixel_format: *const JxlPixelFormat,
         buffer: *const c_void,
         size: usize,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the output processor for the encoder. This processor determines how the
     /// encoder will handle buffering, writing, seeking (if supported), and
@@ -900,10 +972,11 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] on success.
     /// - [`JxlEncoderStatus::Error`] on error.
+    #[cfg(not(feature = "libjxl_0_9"))]
     pub fn JxlEncoderSetOutputProcessor(
         enc: *mut JxlEncoder,
         output_processor: JxlEncoderOutputProcessor,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Flushes any buffered input in the encoder, ensuring that all available input
     /// data has been processed and written to the output.
@@ -920,7 +993,8 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] on success.
     /// - [`JxlEncoderStatus::Error`] on error.
-    pub fn JxlEncoderFlushInput(enc: *mut JxlEncoder) -> JxlEncoderStatus;
+    #[cfg(not(feature = "libjxl_0_9"))]
+    pub fn JxlEncoderFlushInput(enc: *mut JxlEncoder) -> c_int;
 
     /// Adds a frame to the encoder using a chunked input source.
     ///
@@ -942,11 +1016,12 @@ extern "C-unwind" {
     /// # Returns
     /// Returns a status indicating the success or failure of adding the
     /// frame.
+    #[cfg(not(feature = "libjxl_0_9"))]
     pub fn JxlEncoderAddChunkedFrame(
         frame_settings: *const JxlEncoderFrameSettings,
         is_last_frame: JxlBool,
         chunked_frame_input: JxlChunkedFrameInputSource,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the buffer to read pixels from for an extra channel at a given index.
     /// The index must be smaller than the `num_extra_channels` in the associated
@@ -979,7 +1054,7 @@ extern "C-unwind" {
         buffer: *const c_void,
         size: usize,
         index: u32,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Adds a metadata box to the file format. [`JxlEncoderProcessOutput`] must be
     /// used to effectively write the box to the output. [`JxlEncoderUseBoxes`] must
@@ -1058,7 +1133,7 @@ extern "C-unwind" {
         contents: *const u8,
         size: usize,
         compress_box: JxlBool,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Indicates the intention to add metadata boxes. This allows [`JxlEncoderAddBox`] to be used.
     /// When using this function, then it is required to use [`JxlEncoderCloseBoxes`] at the end.
@@ -1069,7 +1144,7 @@ extern "C-unwind" {
     ///
     /// # Parameters
     /// - `enc`: encoder object.
-    pub fn JxlEncoderUseBoxes(enc: *mut JxlEncoder) -> JxlEncoderStatus;
+    pub fn JxlEncoderUseBoxes(enc: *mut JxlEncoder) -> c_int;
 
     /// Declares that no further boxes will be added with [`JxlEncoderAddBox`].
     /// This function must be called after the last box is added so the encoder knows
@@ -1131,7 +1206,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetColorEncoding(
         enc: *mut JxlEncoder,
         color: *const JxlColorEncoding,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the original color encoding of the image encoded by this encoder as an
     /// ICC color profile. This is an alternative to [`JxlEncoderSetColorEncoding`]
@@ -1151,7 +1226,7 @@ extern "C-unwind" {
         enc: *mut JxlEncoder,
         icc_profile: *const u8,
         size: usize,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Initializes a [`JxlBasicInfo`] struct to default values.
     /// For forwards-compatibility, this function has to be called before values
@@ -1198,10 +1273,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] if the operation was successful.
     /// - [`JxlEncoderStatus::Error`] otherwise.
-    pub fn JxlEncoderSetBasicInfo(
-        enc: *mut JxlEncoder,
-        info: *const JxlBasicInfo,
-    ) -> JxlEncoderStatus;
+    pub fn JxlEncoderSetBasicInfo(enc: *mut JxlEncoder, info: *const JxlBasicInfo) -> c_int;
 
     /// Sets the upsampling method the decoder will use in case there are frames
     /// with [`JxlEncoderFrameSettingsSetOption`] set. This is useful in combination
@@ -1220,11 +1292,8 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] if the operation was successful,
     /// - [`JxlEncoderStatus::Error`] otherwise
-    pub fn JxlEncoderSetUpsamplingMode(
-        enc: *mut JxlEncoder,
-        factor: i64,
-        mode: i64,
-    ) -> JxlEncoderStatus;
+    #[cfg(not(feature = "libjxl_0_8"))]
+    pub fn JxlEncoderSetUpsamplingMode(enc: *mut JxlEncoder, factor: i64, mode: i64) -> c_int;
 
     /// Initializes a [`JxlExtraChannelInfo`] struct to default values.
     /// For forwards-compatibility, this function has to be called before values
@@ -1256,7 +1325,7 @@ extern "C-unwind" {
         enc: *mut JxlEncoder,
         index: usize,
         info: *const JxlExtraChannelInfo,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the name for the extra channel at the given index in UTF-8. The index
     /// must be smaller than the `num_extra_channels` in the associated [`JxlBasicInfo`].
@@ -1278,7 +1347,7 @@ extern "C-unwind" {
         index: usize,
         name: *const u8,
         size: usize,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets a frame-specific option of integer type to the encoder options.
     /// The [`JxlEncoderFrameSettingId`] argument determines which option is set.
@@ -1299,7 +1368,7 @@ extern "C-unwind" {
         frame_settings: *mut JxlEncoderFrameSettings,
         option: JxlEncoderFrameSettingId,
         value: i64,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets a frame-specific option of float type to the encoder options.
     /// The [`JxlEncoderFrameSettingId`] argument determines which option is set.
@@ -1320,7 +1389,7 @@ extern "C-unwind" {
         frame_settings: *mut JxlEncoderFrameSettings,
         option: JxlEncoderFrameSettingId,
         value: f32,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Forces the encoder to use the box-based container format (BMFF) even
     /// when not necessary.
@@ -1342,7 +1411,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] if the operation was successful.
     /// - [`JxlEncoderStatus::Error`] otherwise.
-    pub fn JxlEncoderUseContainer(enc: *mut JxlEncoder, use_container: bool) -> JxlEncoderStatus;
+    pub fn JxlEncoderUseContainer(enc: *mut JxlEncoder, use_container: bool) -> c_int;
 
     /// Configure the encoder to store JPEG reconstruction metadata in the JPEG XL
     /// container.
@@ -1359,10 +1428,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] if the operation was successful.
     /// - [`JxlEncoderStatus::Error`] otherwise.
-    pub fn JxlEncoderStoreJPEGMetadata(
-        enc: *mut JxlEncoder,
-        store_jpeg_metadata: bool,
-    ) -> JxlEncoderStatus;
+    pub fn JxlEncoderStoreJPEGMetadata(enc: *mut JxlEncoder, store_jpeg_metadata: bool) -> c_int;
 
     /// Sets the feature level of the JPEG XL codestream. Valid values are 5 and
     /// 10, or -1 (to choose automatically). Using the minimum required level, or
@@ -1399,7 +1465,7 @@ extern "C-unwind" {
     /// # Returns
     /// - [`JxlEncoderStatus::Success`] if the operation was successful.
     /// - [`JxlEncoderStatus::Error`] otherwise.
-    pub fn JxlEncoderSetCodestreamLevel(enc: *mut JxlEncoder, level: i32) -> JxlEncoderStatus;
+    pub fn JxlEncoderSetCodestreamLevel(enc: *mut JxlEncoder, level: i32) -> c_int;
 
     /// Returns the codestream level required to support the currently configured
     /// settings and basic info. This function can only be used at the beginning,
@@ -1445,7 +1511,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetFrameLossless(
         frame_settings: *mut JxlEncoderFrameSettings,
         lossless: bool,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the distance level for lossy compression: target max butteraugli
     /// distance, lower = higher quality. Range: 0 .. 25.
@@ -1464,7 +1530,7 @@ extern "C-unwind" {
     pub fn JxlEncoderSetFrameDistance(
         options: *mut JxlEncoderFrameSettings,
         distance: f32,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Sets the distance level for lossy compression of extra channels.
     /// The distance is as in [`JxlEncoderSetFrameDistance`] (lower = higher
@@ -1483,7 +1549,7 @@ extern "C-unwind" {
         frame_settings: *mut JxlEncoderFrameSettings,
         index: usize,
         distance: f32,
-    ) -> JxlEncoderStatus;
+    ) -> c_int;
 
     /// Maps JPEG-style quality factor to distance.
     ///
@@ -1518,6 +1584,7 @@ extern "C-unwind" {
     /// `quality` parameter, but that is by no means guaranteed; do not assume that
     /// the same quality value will result in similar file sizes and image quality
     /// across different codecs.
+    #[cfg(not(feature = "libjxl_0_8"))]
     pub fn JxlEncoderDistanceFromQuality(quality: f32) -> f32;
 
     /// Create a new set of encoder options, with all values initially copied from
@@ -1575,6 +1642,7 @@ extern "C-unwind" {
     ///   includes reference to the encoder object.
     /// - `callback`: used to return the debug image.
     /// - `opaque`: user supplied parameter to the image callback.
+    #[cfg(not(feature = "libjxl_0_8"))]
     pub fn JxlEncoderSetDebugImageCallback(
         frame_settings: *mut JxlEncoderFrameSettings,
         callback: JxlDebugImageCallback,
@@ -1591,6 +1659,7 @@ extern "C-unwind" {
     ///   includes reference to the encoder object.
     /// - `stats`: object that can be used to query the gathered stats (created
     ///   by [`JxlEncoderStatsCreate`])
+    #[cfg(not(feature = "libjxl_0_8"))]
     pub fn JxlEncoderCollectStats(
         frame_settings: *mut JxlEncoderFrameSettings,
         stats: *mut JxlEncoderStats,
@@ -65,6 +65,7 @@ extern "C-unwind" {
 /// Data type for querying [`JxlEncoderStats`] object
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum JxlEncoderStatsKey {
     HeaderBits,
     TocBits,
@@ -94,3 +95,33 @@ pub enum JxlEncoderStatsKey {
     NumButteraugliIters,
     NumStats,
 }
+
+impl_try_from_i32!(JxlEncoderStatsKey {
+    HeaderBits,
+    TocBits,
+    DictionaryBits,
+    SplinesBits,
+    NoiseBits,
+    QuantBits,
+    ModularTreeBits,
+    ModularGlobalBits,
+    DcBits,
+    ModularDcGroupBits,
+    ControlFieldsBits,
+    CoefOrderBits,
+    AcHistogramBits,
+    AcBits,
+    ModularAcGroupBits,
+    NumSmallBlocks,
+    NumDct4x8Blocks,
+    NumAfvBlocks,
+    NumDct8Blocks,
+    NumDct8x32Blocks,
+    NumDct16Blocks,
+    NumDct16x32Blocks,
+    NumDct32Blocks,
+    NumDct32x64Blocks,
+    NumDct64Blocks,
+    NumButteraugliIters,
+    NumStats,
+});
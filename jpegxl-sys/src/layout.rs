@@ -0,0 +1,104 @@
+/*
+This file is part of jpegxl-sys.
+
+jpegxl-sys is free software: you can redistribute it and/or modify
+it under the terms of the GNU General Public License as published by
+the Free Software Foundation, either version 3 of the License, or
+(at your option) any later version.
+
+jpegxl-sys is distributed in the hope that it will be useful,
+but WITHOUT ANY WARRANTY; without even the implied warranty of
+MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+GNU General Public License for more details.
+
+You should have received a copy of the GNU General Public License
+along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! Compile-time checks of the hand-written declarations against bindings generated from the
+//! installed libjxl headers, enabled by the `generate-bindings` feature.
+//!
+//! A struct or enum that changes size or alignment in a libjxl release fails the build here,
+//! instead of silently corrupting memory at runtime.
+
+use std::mem::{align_of, size_of};
+
+#[allow(
+    dead_code,
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    clippy::pedantic
+)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+macro_rules! check_layout {
+    ($($ty:ty => $gen:ident),* $(,)?) => {
+        $(
+            const _: () = assert!(
+                size_of::<$ty>() == size_of::<generated::$gen>()
+                    && align_of::<$ty>() == align_of::<generated::$gen>(),
+                concat!("Layout of `", stringify!($gen), "` differs from the libjxl headers")
+            );
+        )*
+    };
+}
+
+check_layout! {
+    crate::common::types::JxlDataType => JxlDataType,
+    crate::common::types::JxlEndianness => JxlEndianness,
+    crate::common::types::JxlPixelFormat => JxlPixelFormat,
+    crate::common::types::JxlBitDepthType => JxlBitDepthType,
+    crate::common::types::JxlBitDepth => JxlBitDepth,
+    crate::common::types::JxlBoxType => JxlBoxType,
+    crate::common::memory_manager::JxlMemoryManager => JxlMemoryManager,
+
+    crate::color::color_encoding::JxlColorSpace => JxlColorSpace,
+    crate::color::color_encoding::JxlWhitePoint => JxlWhitePoint,
+    crate::color::color_encoding::JxlPrimaries => JxlPrimaries,
+    crate::color::color_encoding::JxlTransferFunction => JxlTransferFunction,
+    crate::color::color_encoding::JxlRenderingIntent => JxlRenderingIntent,
+    crate::color::color_encoding::JxlColorEncoding => JxlColorEncoding,
+    crate::color::cms_interface::JxlColorProfileIcc => JxlColorProfile__bindgen_ty_1,
+    crate::color::cms_interface::JxlColorProfile => JxlColorProfile,
+    crate::color::cms_interface::JxlCmsInterface => JxlCmsInterface,
+
+    crate::metadata::codestream_header::JxlOrientation => JxlOrientation,
+    crate::metadata::codestream_header::JxlExtraChannelType => JxlExtraChannelType,
+    crate::metadata::codestream_header::JxlPreviewHeader => JxlPreviewHeader,
+    crate::metadata::codestream_header::JxlAnimationHeader => JxlAnimationHeader,
+    crate::metadata::codestream_header::JxlBasicInfo => JxlBasicInfo,
+    crate::metadata::codestream_header::JxlExtraChannelInfo => JxlExtraChannelInfo,
+    crate::metadata::codestream_header::JxlHeaderExtensions => JxlHeaderExtensions,
+    crate::metadata::codestream_header::JxlBlendMode => JxlBlendMode,
+    crate::metadata::codestream_header::JxlBlendInfo => JxlBlendInfo,
+    crate::metadata::codestream_header::JxlLayerInfo => JxlLayerInfo,
+    crate::metadata::codestream_header::JxlFrameHeader => JxlFrameHeader,
+
+    crate::decode::JxlSignature => JxlSignature,
+    crate::decode::JxlDecoderStatus => JxlDecoderStatus,
+    crate::decode::JxlProgressiveDetail => JxlProgressiveDetail,
+    crate::decode::JxlColorProfileTarget => JxlColorProfileTarget,
+
+    crate::encoder::encode::JxlEncoderStatus => JxlEncoderStatus,
+    crate::encoder::encode::JxlEncoderError => JxlEncoderError,
+    crate::encoder::encode::JxlEncoderFrameSettingId => JxlEncoderFrameSettingId,
+}
+
+#[cfg(not(feature = "libjxl_0_8"))]
+check_layout! {
+    crate::encoder::stats::JxlEncoderStatsKey => JxlEncoderStatsKey,
+}
+
+#[cfg(not(feature = "libjxl_0_9"))]
+check_layout! {
+    crate::encoder::encode::JxlEncoderOutputProcessor => JxlEncoderOutputProcessor,
+    crate::encoder::encode::JxlChunkedFrameInputSource => JxlChunkedFrameInputSource,
+}
+
+#[cfg(not(feature = "libjxl_0_10"))]
+check_layout! {
+    crate::metadata::gain_map::JxlGainMapBundle => JxlGainMapBundle,
+}
@@ -17,6 +17,31 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
+/// Implement `TryFrom<i32>` for an enum, to check a raw value coming from libjxl before it is
+/// turned into the enum. All variants have to be listed.
+macro_rules! impl_try_from_i32 {
+    ($name:ident { $($variant:ident),+ $(,)? }) => {
+        impl TryFrom<i32> for $name {
+            type Error = i32;
+
+            /// Return the value back if it is not a known variant, e.g. one added by a newer libjxl
+            fn try_from(value: i32) -> Result<Self, i32> {
+                $(
+                    if value == Self::$variant as i32 {
+                        return Ok(Self::$variant);
+                    }
+                )+
+                Err(value)
+            }
+        }
+
+        // Fails to compile when a variant is missing from the list
+        const _: fn($name) = |v| match v {
+            $($name::$variant)|+ => {}
+        };
+    };
+}
+
 pub mod decode;
 
 pub mod color;
@@ -25,22 +50,36 @@ pub mod encoder;
 pub mod metadata;
 pub mod threads;
 
+#[cfg(all(
+    feature = "generate-bindings",
+    any(feature = "vendored", not(feature = "docs"))
+))]
+mod layout;
+
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "threads")]
+    use crate::threads::thread_parallel_runner::{
+        JxlThreadParallelRunner, JxlThreadParallelRunnerCreate,
+        JxlThreadParallelRunnerDefaultNumWorkerThreads, JxlThreadParallelRunnerDestroy,
+    };
     use crate::{
         common::types::*,
         decode::*,
         encoder::encode::*,
-        threads::thread_parallel_runner::{
-            JxlThreadParallelRunner, JxlThreadParallelRunnerCreate,
-            JxlThreadParallelRunnerDefaultNumWorkerThreads, JxlThreadParallelRunnerDestroy,
+        metadata::codestream_header::{
+            JxlBasicInfo, JxlBlendInfo, JxlBlendMode, JxlExtraChannelInfo, JxlExtraChannelType,
+            JxlFrameHeader, JxlLayerInfo,
         },
     };
 
+    #[cfg(not(feature = "libjxl_0_9"))]
+    use std::ffi::c_void;
     use std::{mem::MaybeUninit, ptr};
 
     use pretty_assertions::assert_eq;
 
+    #[cfg(feature = "threads")]
     const SAMPLE_PNG: &[u8] = include_bytes!("../../samples/sample.png");
     const SAMPLE_JXL: &[u8] = include_bytes!("../../samples/sample.jxl");
 
@@ -73,6 +112,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(not(feature = "libjxl_0_10"))]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn test_bindings_version() {
         unsafe {
@@ -81,89 +121,99 @@ mod test {
         }
     }
 
+    #[test]
     #[cfg_attr(coverage_nightly, coverage(off))]
-    unsafe fn decode(decoder: *mut JxlDecoder, sample: &[u8]) {
-        use JxlDecoderStatus::{
-            BasicInfo, Error, FullImage, NeedImageOutBuffer, NeedMoreInput, Success,
-        };
+    fn test_enum_try_from() {
+        assert_eq!(
+            JxlDecoderStatus::try_from(0x10000),
+            Ok(JxlDecoderStatus::BoxComplete)
+        );
+        assert_eq!(JxlDecoderStatus::try_from(0x20000), Err(0x20000));
+        assert_eq!(
+            JxlExtraChannelType::try_from(16),
+            Ok(JxlExtraChannelType::Optional)
+        );
+        assert_eq!(JxlExtraChannelType::try_from(17), Err(17));
+        assert_eq!(
+            JxlEncoderError::try_from(0x81),
+            Ok(JxlEncoderError::ApiUsage)
+        );
+        assert_eq!(JxlBool::try_from(-1), Err(-1));
+    }
 
-        // Stop after getting the basic info and decoding the image
-        let mut status = JxlDecoderSubscribeEvents(
-            decoder,
-            jxl_dec_events!(JxlDecoderStatus::BasicInfo, JxlDecoderStatus::FullImage),
+    /// Decode `data` with the events subscribed, passing every status to `on_event` until
+    /// [`JxlDecoderStatus::Success`]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn decode_with(
+        decoder: *mut JxlDecoder,
+        data: &[u8],
+        events: i32,
+        mut on_event: impl FnMut(JxlDecoderStatus),
+    ) {
+        jxl_dec_assert!(
+            JxlDecoderSubscribeEvents(decoder, events),
+            "Subscribe Events"
         );
-        jxl_dec_assert!(status, "Subscribe Events");
+        jxl_dec_assert!(
+            JxlDecoderSetInput(decoder, data.as_ptr(), data.len()),
+            "Set input"
+        );
+        JxlDecoderCloseInput(decoder);
 
-        // Read everything in memory
-        let signature = JxlSignatureCheck(sample.as_ptr(), 2);
-        assert_eq!(signature, JxlSignature::Codestream);
+        loop {
+            let status = process_input(decoder);
+            on_event(status);
+            if status == JxlDecoderStatus::Success {
+                break;
+            }
+        }
+    }
 
-        let next_in = sample.as_ptr();
-        let avail_in = sample.len();
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn decode(decoder: *mut JxlDecoder, sample: &[u8]) {
+        use JxlDecoderStatus::{BasicInfo, FullImage, NeedImageOutBuffer, Success};
 
-        let pixel_format = JxlPixelFormat {
-            num_channels: 3,
-            data_type: JxlDataType::Uint8,
-            endianness: JxlEndianness::Native,
-            align: 0,
-        };
+        let signature = JxlSignatureCheck(sample.as_ptr(), 2);
+        assert_eq!(signature, JxlSignature::Codestream as _);
 
-        let mut basic_info;
-        let mut buffer: Vec<f32> = Vec::new();
+        let pixel_format = u8_format(3);
+        let mut buffer = vec![];
         let mut x_size = 0;
         let mut y_size = 0;
 
-        status = JxlDecoderSetInput(decoder, next_in, avail_in);
-        jxl_dec_assert!(status, "Set input");
-
-        loop {
-            status = JxlDecoderProcessInput(decoder);
-
-            match status {
-                Error => panic!("Decoder error!"),
-                NeedMoreInput => {
-                    panic!("Error, already provided all input")
-                }
-
-                // Get the basic info
-                BasicInfo => {
-                    basic_info = {
-                        let mut info = MaybeUninit::uninit();
-                        status = JxlDecoderGetBasicInfo(decoder, info.as_mut_ptr());
-                        jxl_dec_assert!(status, "BasicInfo");
-                        info.assume_init()
-                    };
-
-                    x_size = basic_info.xsize;
-                    y_size = basic_info.ysize;
-                    assert_eq!(basic_info.xsize, 40);
-                    assert_eq!(basic_info.ysize, 50);
-                }
-
-                // Get the output buffer
-                NeedImageOutBuffer => {
-                    let mut size = 0;
-                    status = JxlDecoderImageOutBufferSize(decoder, &pixel_format, &mut size);
-                    jxl_dec_assert!(status, "BufferSize");
-
-                    buffer.resize(size, 0f32);
-                    status = JxlDecoderSetImageOutBuffer(
-                        decoder,
-                        &pixel_format,
-                        buffer.as_mut_ptr().cast(),
-                        size,
-                    );
-                    jxl_dec_assert!(status, "SetBuffer");
-                }
-
-                FullImage => continue,
-                Success => {
-                    assert_eq!(buffer.len(), (x_size * y_size * 3) as usize);
-                    return;
-                }
-                _ => panic!("Unknown decoder status: {status:#?}"),
+        // Stop after getting the basic info and decoding the image
+        let events = jxl_dec_events!(BasicInfo, FullImage);
+        decode_with(decoder, sample, events, |status| match status {
+            BasicInfo => {
+                let mut info = MaybeUninit::uninit();
+                jxl_dec_assert!(
+                    JxlDecoderGetBasicInfo(decoder, info.as_mut_ptr()),
+                    "BasicInfo"
+                );
+                let info = info.assume_init();
+                x_size = info.xsize;
+                y_size = info.ysize;
+                assert_eq!(info.xsize, 40);
+                assert_eq!(info.ysize, 50);
             }
-        }
+            NeedImageOutBuffer => {
+                let mut size = 0;
+                let status = JxlDecoderImageOutBufferSize(decoder, &pixel_format, &mut size);
+                jxl_dec_assert!(status, "BufferSize");
+
+                buffer.resize(size, 0u8);
+                let status = JxlDecoderSetImageOutBuffer(
+                    decoder,
+                    &pixel_format,
+                    buffer.as_mut_ptr().cast(),
+                    size,
+                );
+                jxl_dec_assert!(status, "SetBuffer");
+            }
+            FullImage | Success => {}
+            status => panic!("Unexpected decoder status: {status:#?}"),
+        });
+        assert_eq!(buffer.len(), (x_size * y_size * 3) as usize);
     }
 
     #[test]
@@ -180,6 +230,7 @@ mod test {
         }
     }
 
+    #[cfg(feature = "threads")]
     #[test]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn test_bindings_thread_pool() {
@@ -203,6 +254,7 @@ mod test {
         }
     }
 
+    #[cfg(feature = "threads")]
     #[test]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn test_bindings_resizable() {
@@ -235,7 +287,7 @@ mod test {
 
             // Read everything in memory
             let signature = JxlSignatureCheck(SAMPLE_JXL.as_ptr(), 2);
-            assert_eq!(signature, JxlSignature::Codestream);
+            assert_eq!(signature, JxlSignature::Codestream as _);
 
             let next_in = SAMPLE_JXL.as_ptr();
             let avail_in = SAMPLE_JXL.len();
@@ -256,9 +308,7 @@ mod test {
             jxl_dec_assert!(status, "Set input");
 
             loop {
-                status = JxlDecoderProcessInput(dec);
-
-                match status {
+                match process_input(dec) {
                     Error => panic!("Decoder error!"),
                     NeedMoreInput => {
                         panic!("Error, already provided all input")
@@ -301,7 +351,7 @@ mod test {
                         jxl_dec_assert!(status, "SetBuffer");
                     }
 
-                    FullImage => continue,
+                    FullImage => {}
                     Success => {
                         assert_eq!(buffer.len(), (x_size * y_size * 3) as usize);
                         break;
@@ -315,79 +365,111 @@ mod test {
         }
     }
 
+    /// Process input, converting the raw status
+    unsafe fn process_input(dec: *mut JxlDecoder) -> JxlDecoderStatus {
+        JxlDecoderStatus::try_from(JxlDecoderProcessInput(dec)).expect("Unknown decoder status")
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn process_output(enc: *mut JxlEncoder) -> Vec<u8> {
+        let chunk_size = 1024 * 512; // 512 KB is a good initial value
+        let mut buffer = vec![0u8; chunk_size];
+        let mut next_out = buffer.as_mut_ptr();
+        let mut avail_out = chunk_size;
+
+        let status = loop {
+            let status =
+                JxlEncoderProcessOutput(enc, std::ptr::addr_of_mut!(next_out), &mut avail_out);
+
+            if status != JxlEncoderStatus::NeedMoreOutput as _ {
+                break status;
+            }
+
+            let offset = next_out as usize - buffer.as_ptr() as usize;
+            buffer.resize(buffer.len() * 2, 0);
+            next_out = buffer.as_mut_ptr().add(offset);
+            avail_out = buffer.len() - offset;
+        };
+        buffer.truncate(next_out as usize - buffer.as_ptr() as usize);
+        jxl_enc_assert!(status, "Encoding");
+        buffer
+    }
+
+    /// Create an encoder of an sRGB image, letting `setup` adjust the basic info
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn create_encoder(
+        setup: impl FnOnce(&mut JxlBasicInfo),
+    ) -> (*mut JxlEncoder, *mut JxlEncoderFrameSettings) {
+        let enc = JxlEncoderCreate(ptr::null());
+        let mut basic_info = MaybeUninit::uninit();
+        JxlEncoderInitBasicInfo(basic_info.as_mut_ptr());
+        let mut basic_info = basic_info.assume_init();
+        setup(&mut basic_info);
+        jxl_enc_assert!(JxlEncoderSetBasicInfo(enc, &basic_info), "Set Basic Info");
+
+        let mut color_encoding = MaybeUninit::uninit();
+        JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), false);
+        jxl_enc_assert!(
+            JxlEncoderSetColorEncoding(enc, color_encoding.as_ptr()),
+            "Set Color Encoding"
+        );
+
+        (enc, JxlEncoderFrameSettingsCreate(enc, ptr::null()))
+    }
+
+    /// Add a frame of 8-bit RGB pixels
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn add_u8_frame(settings: *mut JxlEncoderFrameSettings, pixels: &[u8]) {
+        jxl_enc_assert!(
+            JxlEncoderAddImageFrame(
+                settings,
+                &u8_format(3),
+                pixels.as_ptr().cast(),
+                pixels.len()
+            ),
+            "Add Image Frame"
+        );
+    }
+
+    /// Encode an sRGB image, letting `setup` adjust the basic info and `add_frame` configure
+    /// the encoder and add the frame
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    unsafe fn encode_with(
+        setup: impl FnOnce(&mut JxlBasicInfo),
+        add_frame: impl FnOnce(*mut JxlEncoder, *mut JxlEncoderFrameSettings),
+    ) -> Vec<u8> {
+        let (enc, settings) = create_encoder(setup);
+        add_frame(enc, settings);
+        JxlEncoderCloseInput(enc);
+        let output = process_output(enc);
+        JxlEncoderDestroy(enc);
+        output
+    }
+
+    #[cfg(feature = "threads")]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn encode(pixels: &[u8], x_size: u32, ysize: u32) -> Vec<u8> {
         unsafe {
-            let enc = JxlEncoderCreate(std::ptr::null());
-
             let runner = JxlThreadParallelRunnerCreate(
                 std::ptr::null(),
                 JxlThreadParallelRunnerDefaultNumWorkerThreads(),
             );
 
-            let mut status = JxlEncoderSetParallelRunner(enc, JxlThreadParallelRunner, runner);
-            jxl_enc_assert!(status, "Set Parallel Runner");
-
-            let mut basic_info = {
-                let mut basic_info = MaybeUninit::uninit();
-                JxlEncoderInitBasicInfo(basic_info.as_mut_ptr());
-                basic_info.assume_init()
-            };
-            basic_info.xsize = x_size;
-            basic_info.ysize = ysize;
-
-            status = JxlEncoderSetBasicInfo(enc, &basic_info);
-            jxl_enc_assert!(status, "Set Basic Info");
-
-            let pixel_format = JxlPixelFormat {
-                num_channels: 3,
-                data_type: JxlDataType::Uint8,
-                endianness: JxlEndianness::Native,
-                align: 0,
-            };
-            let mut color_encoding = MaybeUninit::uninit();
-            JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), false);
-            status = JxlEncoderSetColorEncoding(enc, color_encoding.as_ptr());
-            jxl_enc_assert!(status, "Set Color Encoding");
-
-            status = JxlEncoderAddImageFrame(
-                JxlEncoderFrameSettingsCreate(enc, std::ptr::null()),
-                &pixel_format,
-                pixels.as_ptr().cast_mut().cast(),
-                pixels.len(),
+            let buffer = encode_with(
+                |info| (info.xsize, info.ysize) = (x_size, ysize),
+                |enc, settings| {
+                    let status = JxlEncoderSetParallelRunner(enc, JxlThreadParallelRunner, runner);
+                    jxl_enc_assert!(status, "Set Parallel Runner");
+                    add_u8_frame(settings, pixels);
+                },
             );
-            jxl_enc_assert!(status, "Add Image Frame");
-
-            JxlEncoderCloseInput(enc);
 
-            let chunk_size = 1024 * 512; // 512 KB is a good initial value
-            let mut buffer = vec![0u8; chunk_size];
-            let mut next_out = buffer.as_mut_ptr();
-            let mut avail_out = chunk_size;
-
-            loop {
-                status =
-                    JxlEncoderProcessOutput(enc, std::ptr::addr_of_mut!(next_out), &mut avail_out);
-
-                if status != JxlEncoderStatus::NeedMoreOutput {
-                    break;
-                }
-
-                let offset = next_out as usize - buffer.as_ptr() as usize;
-                buffer.resize(buffer.len() * 2, 0);
-                next_out = buffer.as_mut_ptr().add(offset);
-                avail_out = buffer.len() - offset;
-            }
-            buffer.truncate(next_out as usize - buffer.as_ptr() as usize);
-            jxl_enc_assert!(status, "Encoding");
-
-            JxlEncoderDestroy(enc);
             JxlThreadParallelRunnerDestroy(runner);
-
             buffer
         }
     }
 
+    #[cfg(feature = "threads")]
     #[test]
     #[cfg_attr(coverage_nightly, coverage(off))]
     fn test_bindings_encoding() {
@@ -418,4 +500,898 @@ mod test {
             JxlThreadParallelRunnerDestroy(runner);
         }
     }
+
+    /// Offset of a field, as `std::mem::offset_of!` is newer than the MSRV
+    macro_rules! offset {
+        ($ty:ty, $($field:tt)+) => {{
+            let value = MaybeUninit::<$ty>::uninit();
+            let base = value.as_ptr();
+            // Safety: only the address is taken, the memory is not read
+            unsafe { ptr::addr_of!((*base).$($field)+) as usize - base as usize }
+        }};
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_frame_header_layout() {
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (size_of::<JxlBlendInfo>(), align_of::<JxlBlendInfo>()),
+            (16, 4)
+        );
+        assert_eq!(offset!(JxlBlendInfo, source), 4);
+        assert_eq!(offset!(JxlBlendInfo, alpha), 8);
+        assert_eq!(offset!(JxlBlendInfo, clamp), 12);
+
+        assert_eq!(
+            (size_of::<JxlLayerInfo>(), align_of::<JxlLayerInfo>()),
+            (40, 4)
+        );
+        assert_eq!(offset!(JxlLayerInfo, crop_x0), 4);
+        assert_eq!(offset!(JxlLayerInfo, xsize), 12);
+        assert_eq!(offset!(JxlLayerInfo, blend_info), 20);
+        assert_eq!(offset!(JxlLayerInfo, save_as_reference), 36);
+
+        assert_eq!(
+            (size_of::<JxlFrameHeader>(), align_of::<JxlFrameHeader>()),
+            (56, 4)
+        );
+        assert_eq!(offset!(JxlFrameHeader, timecode), 4);
+        assert_eq!(offset!(JxlFrameHeader, name_length), 8);
+        assert_eq!(offset!(JxlFrameHeader, is_last), 12);
+        assert_eq!(offset!(JxlFrameHeader, layer_info), 16);
+        assert_eq!(offset!(JxlFrameHeader, layer_info.blend_info.blendmode), 36);
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_frame_header() {
+        unsafe {
+            let mut header = MaybeUninit::uninit();
+            JxlEncoderInitFrameHeader(header.as_mut_ptr());
+            let header = header.assume_init();
+            assert_eq!(header.duration, 0);
+            assert_eq!(header.name_length, 0);
+            assert_eq!(header.layer_info.have_crop, JxlBool::False);
+            assert_eq!(
+                header.layer_info.blend_info.blendmode,
+                JxlBlendMode::Replace
+            );
+
+            let dec = JxlDecoderCreate(ptr::null());
+            assert!(!dec.is_null());
+            let status = JxlDecoderSubscribeEvents(dec, jxl_dec_events!(JxlDecoderStatus::Frame));
+            jxl_dec_assert!(status, "Subscribe Events");
+            let status = JxlDecoderSetInput(dec, SAMPLE_JXL.as_ptr(), SAMPLE_JXL.len());
+            jxl_dec_assert!(status, "Set input");
+            JxlDecoderCloseInput(dec);
+
+            assert_eq!(process_input(dec), JxlDecoderStatus::Frame);
+            let mut header = MaybeUninit::uninit();
+            let status = JxlDecoderGetFrameHeader(dec, header.as_mut_ptr());
+            jxl_dec_assert!(status, "Get frame header");
+            let header = header.assume_init();
+            assert_eq!((header.layer_info.xsize, header.layer_info.ysize), (40, 50));
+            assert_eq!(header.is_last, JxlBool::True);
+            assert_eq!(header.name_length, 0);
+
+            let mut name = [1u8; 1];
+            let status = JxlDecoderGetFrameName(dec, name.as_mut_ptr().cast(), name.len());
+            jxl_dec_assert!(status, "Get frame name");
+            assert_eq!(name, [0]);
+
+            JxlDecoderDestroy(dec);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_extra_channel_layout() {
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (
+                size_of::<JxlExtraChannelInfo>(),
+                align_of::<JxlExtraChannelInfo>()
+            ),
+            (44, 4)
+        );
+        assert_eq!(offset!(JxlExtraChannelInfo, bits_per_sample), 4);
+        assert_eq!(offset!(JxlExtraChannelInfo, dim_shift), 12);
+        assert_eq!(offset!(JxlExtraChannelInfo, name_length), 16);
+        assert_eq!(offset!(JxlExtraChannelInfo, alpha_premultiplied), 20);
+        assert_eq!(offset!(JxlExtraChannelInfo, spot_color), 24);
+        assert_eq!(offset!(JxlExtraChannelInfo, cfa_channel), 40);
+        assert_eq!(JxlExtraChannelType::Thermal as i32, 6);
+        assert_eq!(JxlExtraChannelType::Unknown as i32, 15);
+    }
+
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn u8_format(num_channels: u32) -> JxlPixelFormat {
+        JxlPixelFormat {
+            num_channels,
+            data_type: JxlDataType::Uint8,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_extra_channel() {
+        const NAME: &[u8] = b"depth";
+        let color = vec![0x80u8; 4 * 4 * 3];
+        let depth: Vec<u8> = (0..16).map(|i| i * 16).collect();
+
+        unsafe {
+            let output = encode_with(
+                |info| {
+                    (info.xsize, info.ysize) = (4, 4);
+                    info.num_extra_channels = 1;
+                    info.uses_original_profile = JxlBool::True;
+                },
+                |enc, settings| {
+                    let mut info = MaybeUninit::uninit();
+                    JxlEncoderInitExtraChannelInfo(JxlExtraChannelType::Depth, info.as_mut_ptr());
+                    let info = info.assume_init();
+                    assert_eq!(info.bits_per_sample, 8);
+                    jxl_enc_assert!(
+                        JxlEncoderSetExtraChannelInfo(enc, 0, &info),
+                        "Set Extra Channel Info"
+                    );
+                    jxl_enc_assert!(
+                        JxlEncoderSetExtraChannelName(enc, 0, NAME.as_ptr(), NAME.len()),
+                        "Set Extra Channel Name"
+                    );
+
+                    jxl_enc_assert!(JxlEncoderSetFrameLossless(settings, true), "Set Lossless");
+                    add_u8_frame(settings, &color);
+                    jxl_enc_assert!(
+                        JxlEncoderSetExtraChannelBuffer(
+                            settings,
+                            &u8_format(1),
+                            depth.as_ptr().cast(),
+                            depth.len(),
+                            0
+                        ),
+                        "Set Extra Channel Buffer"
+                    );
+                },
+            );
+
+            let dec = JxlDecoderCreate(ptr::null());
+            let events = jxl_dec_events!(JxlDecoderStatus::BasicInfo, JxlDecoderStatus::FullImage);
+            let mut pixels = vec![];
+            let mut decoded_depth = vec![];
+            decode_with(dec, &output, events, |status| match status {
+                JxlDecoderStatus::BasicInfo => {
+                    let mut info = MaybeUninit::uninit();
+                    jxl_dec_assert!(
+                        JxlDecoderGetExtraChannelInfo(dec, 0, info.as_mut_ptr()),
+                        "Get Extra Channel Info"
+                    );
+                    let info = info.assume_init();
+                    assert_eq!(info.r#type, JxlExtraChannelType::Depth);
+                    assert_eq!(info.name_length as usize, NAME.len());
+
+                    let mut name = vec![0u8; NAME.len() + 1];
+                    jxl_dec_assert!(
+                        JxlDecoderGetExtraChannelName(dec, 0, name.as_mut_ptr().cast(), name.len()),
+                        "Get Extra Channel Name"
+                    );
+                    assert_eq!(&name[..NAME.len()], NAME);
+                }
+                JxlDecoderStatus::NeedImageOutBuffer => {
+                    let mut size = 0;
+                    JxlDecoderImageOutBufferSize(dec, &u8_format(3), &mut size);
+                    pixels.resize(size, 0u8);
+                    jxl_dec_assert!(
+                        JxlDecoderSetImageOutBuffer(
+                            dec,
+                            &u8_format(3),
+                            pixels.as_mut_ptr().cast(),
+                            size
+                        ),
+                        "Set Image Buffer"
+                    );
+
+                    jxl_dec_assert!(
+                        JxlDecoderExtraChannelBufferSize(dec, &u8_format(1), &mut size, 0),
+                        "Extra Channel Buffer Size"
+                    );
+                    decoded_depth.resize(size, 0u8);
+                    jxl_dec_assert!(
+                        JxlDecoderSetExtraChannelBuffer(
+                            dec,
+                            &u8_format(1),
+                            decoded_depth.as_mut_ptr().cast(),
+                            size,
+                            0
+                        ),
+                        "Set Extra Channel Buffer"
+                    );
+                }
+                JxlDecoderStatus::FullImage | JxlDecoderStatus::Success => {}
+                status => panic!("Unexpected decoder status: {status:#?}"),
+            });
+            JxlDecoderDestroy(dec);
+
+            assert_eq!(pixels, color);
+            assert_eq!(decoded_depth, depth);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_boxes() {
+        const EXIF: &[u8] = b"\0\0\0\0MM\0*exif";
+        const XML: &[u8] = b"<x:xmpmeta xmlns:x='adobe:ns:meta/'/>";
+
+        unsafe {
+            let output = encode_with(
+                |info| (info.xsize, info.ysize) = (1, 1),
+                |enc, settings| {
+                    jxl_enc_assert!(JxlEncoderUseContainer(enc, true), "Use Container");
+                    jxl_enc_assert!(JxlEncoderUseBoxes(enc), "Use Boxes");
+                    for (ty, contents, compress) in [(b"Exif", EXIF, false), (b"xml ", XML, true)] {
+                        let box_type = JxlBoxType(ty.map(|c| std::ffi::c_char::from_ne_bytes([c])));
+                        jxl_enc_assert!(
+                            JxlEncoderAddBox(
+                                enc,
+                                &box_type,
+                                contents.as_ptr(),
+                                contents.len(),
+                                compress.into()
+                            ),
+                            "Add Box"
+                        );
+                    }
+                    JxlEncoderCloseBoxes(enc);
+
+                    add_u8_frame(settings, &[0; 3]);
+                },
+            );
+
+            let dec = JxlDecoderCreate(ptr::null());
+            jxl_dec_assert!(
+                JxlDecoderSetDecompressBoxes(dec, JxlBool::True),
+                "Set Decompress Boxes"
+            );
+
+            let mut boxes = vec![];
+            let mut buffer = vec![0u8; 256];
+            let mut buffer_set = false;
+            let events = jxl_dec_events!(JxlDecoderStatus::Box);
+            decode_with(dec, &output, events, |status| {
+                // Any previously set buffer is complete at the next box or at the end
+                if std::mem::take(&mut buffer_set) {
+                    let remaining = JxlDecoderReleaseBoxBuffer(dec);
+                    if let Some((_, contents)) = boxes.last_mut() {
+                        *contents = buffer[..buffer.len() - remaining].to_vec();
+                    }
+                }
+
+                match status {
+                    JxlDecoderStatus::Box => {
+                        let mut raw_type = JxlBoxType([0; 4]);
+                        jxl_dec_assert!(
+                            JxlDecoderGetBoxType(dec, &mut raw_type, JxlBool::False),
+                            "Get Box Type"
+                        );
+                        let mut box_type = JxlBoxType([0; 4]);
+                        jxl_dec_assert!(
+                            JxlDecoderGetBoxType(dec, &mut box_type, JxlBool::True),
+                            "Get Box Type"
+                        );
+                        let mut size = 0;
+                        jxl_dec_assert!(
+                            JxlDecoderGetBoxSizeRaw(dec, &mut size),
+                            "Get Box Size Raw"
+                        );
+
+                        let box_type = box_type.0.map(|c| c.to_ne_bytes()[0]);
+                        let raw_type = raw_type.0.map(|c| c.to_ne_bytes()[0]);
+                        match &box_type {
+                            b"Exif" => assert_eq!(size, 8 + EXIF.len() as u64),
+                            b"xml " => assert_eq!(&raw_type, b"brob"),
+                            _ => return,
+                        }
+                        jxl_dec_assert!(
+                            JxlDecoderSetBoxBuffer(dec, buffer.as_mut_ptr(), buffer.len()),
+                            "Set Box Buffer"
+                        );
+                        buffer_set = true;
+                        boxes.push((box_type, vec![]));
+                    }
+                    JxlDecoderStatus::Success => {}
+                    status => panic!("Unexpected decoder status: {status:#?}"),
+                }
+            });
+            JxlDecoderDestroy(dec);
+
+            assert_eq!(boxes, [(*b"Exif", EXIF.to_vec()), (*b"xml ", XML.to_vec())]);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_progressive() {
+        const SIZE: u32 = 256;
+        let pixels: Vec<u8> = (0..SIZE * SIZE * 3)
+            .map(|i| (((i % (SIZE * 3)) ^ (i / (SIZE * 3))) & 0xff) as u8)
+            .collect();
+
+        unsafe {
+            let output = encode_with(
+                |info| (info.xsize, info.ysize) = (SIZE, SIZE),
+                |_, settings| {
+                    for option in [
+                        JxlEncoderFrameSettingId::ProgressiveAc,
+                        JxlEncoderFrameSettingId::ProgressiveDc,
+                    ] {
+                        jxl_enc_assert!(
+                            JxlEncoderFrameSettingsSetOption(settings, option, 1),
+                            "Set Progressive Option"
+                        );
+                    }
+                    add_u8_frame(settings, &pixels);
+                },
+            );
+            let chunk = output.len() / 8;
+
+            let dec = JxlDecoderCreate(ptr::null());
+            let events = jxl_dec_events!(
+                JxlDecoderStatus::FrameProgression,
+                JxlDecoderStatus::FullImage
+            );
+            jxl_dec_assert!(JxlDecoderSubscribeEvents(dec, events), "Subscribe Events");
+            jxl_dec_assert!(
+                JxlDecoderSetProgressiveDetail(dec, JxlProgressiveDetail::Passes),
+                "Set Progressive Detail"
+            );
+
+            let mut fed = 0;
+            let mut buffer = vec![];
+            let mut ratios = vec![];
+            loop {
+                match process_input(dec) {
+                    JxlDecoderStatus::NeedMoreInput => {
+                        let consumed = fed - JxlDecoderReleaseInput(dec);
+                        fed = (fed + chunk).min(output.len());
+                        jxl_dec_assert!(
+                            JxlDecoderSetInput(dec, output.as_ptr().add(consumed), fed - consumed),
+                            "Set input"
+                        );
+                        if fed == output.len() {
+                            JxlDecoderCloseInput(dec);
+                        }
+                    }
+                    JxlDecoderStatus::NeedImageOutBuffer => {
+                        buffer.resize(pixels.len(), 0u8);
+                        jxl_dec_assert!(
+                            JxlDecoderSetImageOutBuffer(
+                                dec,
+                                &u8_format(3),
+                                buffer.as_mut_ptr().cast(),
+                                buffer.len()
+                            ),
+                            "Set Image Buffer"
+                        );
+                    }
+                    JxlDecoderStatus::FrameProgression => {
+                        ratios.push(JxlDecoderGetIntendedDownsamplingRatio(dec));
+                        jxl_dec_assert!(JxlDecoderFlushImage(dec), "Flush Image");
+                    }
+                    JxlDecoderStatus::FullImage => {}
+                    JxlDecoderStatus::Success => break,
+                    status => panic!("Unexpected decoder status: {status:#?}"),
+                }
+            }
+            JxlDecoderDestroy(dec);
+
+            assert!(!ratios.is_empty());
+            assert!(ratios.iter().all(|r| [1, 2, 4, 8].contains(r)));
+            assert!(ratios.windows(2).all(|w| w[0] >= w[1]));
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_pointer_width = "64", not(feature = "libjxl_0_10")))]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_gain_map_layout() {
+        use crate::metadata::gain_map::JxlGainMapBundle;
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (
+                size_of::<JxlGainMapBundle>(),
+                align_of::<JxlGainMapBundle>()
+            ),
+            (160, 8)
+        );
+        assert_eq!(offset!(JxlGainMapBundle, gain_map_metadata_size), 2);
+        assert_eq!(offset!(JxlGainMapBundle, gain_map_metadata), 8);
+        assert_eq!(offset!(JxlGainMapBundle, has_color_encoding), 16);
+        assert_eq!(offset!(JxlGainMapBundle, color_encoding), 24);
+        assert_eq!(offset!(JxlGainMapBundle, alt_icc_size), 128);
+        assert_eq!(offset!(JxlGainMapBundle, alt_icc), 136);
+        assert_eq!(offset!(JxlGainMapBundle, gain_map_size), 144);
+        assert_eq!(offset!(JxlGainMapBundle, gain_map), 152);
+    }
+
+    #[test]
+    #[cfg(not(feature = "libjxl_0_10"))]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_gain_map() {
+        use crate::metadata::gain_map::{
+            JxlGainMapBundle, JxlGainMapGetBundleSize, JxlGainMapReadBundle, JxlGainMapWriteBundle,
+        };
+
+        let metadata = [1u8, 2, 3, 4];
+        let gain_map = SAMPLE_JXL;
+
+        unsafe {
+            let mut color_encoding = MaybeUninit::uninit();
+            JxlColorEncodingSetToSRGB(color_encoding.as_mut_ptr(), false);
+            let bundle = JxlGainMapBundle {
+                jhgm_version: 0,
+                gain_map_metadata_size: 4,
+                gain_map_metadata: metadata.as_ptr(),
+                has_color_encoding: JxlBool::True,
+                color_encoding: color_encoding.assume_init(),
+                alt_icc_size: 0,
+                alt_icc: ptr::null(),
+                gain_map_size: gain_map.len().try_into().unwrap(),
+                gain_map: gain_map.as_ptr(),
+            };
+
+            let mut size = 0;
+            assert_eq!(JxlGainMapGetBundleSize(&bundle, &mut size), JxlBool::True);
+            let mut buffer = vec![0u8; size];
+            let mut written = 0;
+            assert_eq!(
+                JxlGainMapWriteBundle(&bundle, buffer.as_mut_ptr(), buffer.len(), &mut written),
+                JxlBool::True
+            );
+            assert_eq!(written, size);
+
+            let mut read_back = MaybeUninit::<JxlGainMapBundle>::zeroed();
+            let mut read = 0;
+            assert_eq!(
+                JxlGainMapReadBundle(
+                    read_back.as_mut_ptr(),
+                    buffer.as_ptr(),
+                    buffer.len(),
+                    &mut read
+                ),
+                JxlBool::True
+            );
+            let read_back = read_back.assume_init();
+            assert_eq!(read, size);
+            assert_eq!(read_back.has_color_encoding, JxlBool::True);
+            assert_eq!(
+                std::slice::from_raw_parts(
+                    read_back.gain_map_metadata,
+                    read_back.gain_map_metadata_size.into()
+                ),
+                metadata
+            );
+            assert_eq!(
+                std::slice::from_raw_parts(read_back.gain_map, read_back.gain_map_size as _),
+                gain_map
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_cms_interface_layout() {
+        use crate::color::cms_interface::{JxlCmsInterface, JxlColorProfile};
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (size_of::<JxlColorProfile>(), align_of::<JxlColorProfile>()),
+            (128, 8)
+        );
+        assert_eq!(offset!(JxlColorProfile, color_encoding), 16);
+        assert_eq!(offset!(JxlColorProfile, num_channels), 120);
+        assert_eq!(
+            (size_of::<JxlCmsInterface>(), align_of::<JxlCmsInterface>()),
+            (64, 8)
+        );
+        assert_eq!(offset!(JxlCmsInterface, init), 24);
+        assert_eq!(offset!(JxlCmsInterface, run), 48);
+        assert_eq!(offset!(JxlCmsInterface, destroy), 56);
+    }
+
+    #[test]
+    #[cfg(not(feature = "libjxl_0_8"))]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_cms() {
+        use crate::color::{
+            cms::JxlGetDefaultCms,
+            cms_interface::{JxlCmsInterface, JxlColorProfile},
+            color_encoding::{JxlColorEncoding, JxlPrimaries},
+        };
+        use std::{
+            ffi::c_void,
+            sync::atomic::{AtomicUsize, Ordering},
+        };
+
+        // A CMS delegating to the default one, counting the transforms and the pixels converted
+        static TRANSFORMS: AtomicUsize = AtomicUsize::new(0);
+        static PIXELS: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C-unwind" fn init(
+            init_data: *mut c_void,
+            num_threads: usize,
+            pixels_per_thread: usize,
+            input: *const JxlColorProfile,
+            output: *const JxlColorProfile,
+            intensity_target: f32,
+        ) -> *mut c_void {
+            TRANSFORMS.fetch_add(1, Ordering::Relaxed);
+            let default = unsafe { &*JxlGetDefaultCms() };
+            assert!(unsafe { (*input).icc.size } > 0);
+            (default.init)(
+                init_data,
+                num_threads,
+                pixels_per_thread,
+                input,
+                output,
+                intensity_target,
+            )
+        }
+
+        extern "C-unwind" fn run(
+            user_data: *mut c_void,
+            thread: usize,
+            input: *const f32,
+            output: *mut f32,
+            num_pixels: usize,
+        ) -> JxlBool {
+            PIXELS.fetch_add(num_pixels, Ordering::Relaxed);
+            let default = unsafe { &*JxlGetDefaultCms() };
+            (default.run)(user_data, thread, input, output, num_pixels)
+        }
+
+        // Lossy images are decoded from XYB, converting to another color space uses the CMS
+        let pixels = vec![0x80u8; 16 * 16 * 3];
+
+        unsafe {
+            let input = encode_with(
+                |info| (info.xsize, info.ysize) = (16, 16),
+                |_, settings| add_u8_frame(settings, &pixels),
+            );
+            let mut p3 = MaybeUninit::<JxlColorEncoding>::uninit();
+            JxlColorEncodingSetToSRGB(p3.as_mut_ptr(), false);
+            let mut p3 = p3.assume_init();
+            p3.primaries = JxlPrimaries::P3;
+
+            let cms = JxlCmsInterface {
+                init,
+                run,
+                ..(*JxlGetDefaultCms()).clone()
+            };
+
+            let dec = JxlDecoderCreate(ptr::null());
+            let status = JxlDecoderSetCms(dec, cms);
+            jxl_dec_assert!(status, "Set CMS");
+            let events =
+                jxl_dec_events!(JxlDecoderStatus::ColorEncoding, JxlDecoderStatus::FullImage);
+            jxl_dec_assert!(JxlDecoderSubscribeEvents(dec, events), "Subscribe Events");
+            jxl_dec_assert!(
+                JxlDecoderSetInput(dec, input.as_ptr(), input.len()),
+                "Set input"
+            );
+            JxlDecoderCloseInput(dec);
+
+            let mut buffer = vec![];
+            loop {
+                match process_input(dec) {
+                    JxlDecoderStatus::ColorEncoding => jxl_dec_assert!(
+                        JxlDecoderSetOutputColorProfile(dec, &p3, ptr::null(), 0),
+                        "Set Output Color Profile"
+                    ),
+                    JxlDecoderStatus::NeedImageOutBuffer => {
+                        let mut size = 0;
+                        JxlDecoderImageOutBufferSize(dec, &u8_format(3), &mut size);
+                        buffer.resize(size, 0u8);
+                        jxl_dec_assert!(
+                            JxlDecoderSetImageOutBuffer(
+                                dec,
+                                &u8_format(3),
+                                buffer.as_mut_ptr().cast(),
+                                size
+                            ),
+                            "Set Image Buffer"
+                        );
+                    }
+                    JxlDecoderStatus::FullImage => {}
+                    JxlDecoderStatus::Success => break,
+                    status => panic!("Unexpected decoder status: {status:#?}"),
+                }
+            }
+            JxlDecoderDestroy(dec);
+
+            assert!(TRANSFORMS.load(Ordering::Relaxed) > 0);
+            assert!(PIXELS.load(Ordering::Relaxed) >= 16 * 16);
+        }
+    }
+
+    #[test]
+    #[cfg(all(target_pointer_width = "64", not(feature = "libjxl_0_9")))]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_streaming_encoder_layout() {
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (
+                size_of::<JxlEncoderOutputProcessor>(),
+                align_of::<JxlEncoderOutputProcessor>()
+            ),
+            (40, 8)
+        );
+        assert_eq!(offset!(JxlEncoderOutputProcessor, seek), 24);
+        assert_eq!(
+            offset!(JxlEncoderOutputProcessor, set_finalized_position),
+            32
+        );
+        assert_eq!(
+            (
+                size_of::<JxlChunkedFrameInputSource>(),
+                align_of::<JxlChunkedFrameInputSource>()
+            ),
+            (48, 8)
+        );
+        assert_eq!(
+            offset!(JxlChunkedFrameInputSource, get_color_channels_data_at),
+            16
+        );
+        assert_eq!(offset!(JxlChunkedFrameInputSource, release_buffer), 40);
+    }
+
+    /// Output written through a [`JxlEncoderOutputProcessor`]
+    #[cfg(not(feature = "libjxl_0_9"))]
+    #[derive(Default)]
+    struct Sink {
+        data: Vec<u8>,
+        position: usize,
+        end: usize,
+    }
+
+    #[cfg(not(feature = "libjxl_0_9"))]
+    impl Sink {
+        extern "C-unwind" fn get_buffer(opaque: *mut c_void, size: *mut usize) -> *mut c_void {
+            let sink = unsafe { &mut *opaque.cast::<Self>() };
+            let size = unsafe { *size };
+            if sink.data.len() < sink.position + size {
+                sink.data.resize(sink.position + size, 0);
+            }
+            unsafe { sink.data.as_mut_ptr().add(sink.position).cast() }
+        }
+
+        extern "C-unwind" fn release_buffer(opaque: *mut c_void, written_bytes: usize) {
+            let sink = unsafe { &mut *opaque.cast::<Self>() };
+            sink.position += written_bytes;
+            sink.end = sink.end.max(sink.position);
+        }
+
+        extern "C-unwind" fn seek(opaque: *mut c_void, position: u64) {
+            let sink = unsafe { &mut *opaque.cast::<Self>() };
+            sink.position = position.try_into().unwrap();
+        }
+
+        extern "C-unwind" fn set_finalized_position(_opaque: *mut c_void, _position: u64) {}
+
+        fn processor(&mut self) -> JxlEncoderOutputProcessor {
+            JxlEncoderOutputProcessor {
+                opaque: (self as *mut Self).cast(),
+                get_buffer: Self::get_buffer,
+                release_buffer: Self::release_buffer,
+                seek: Some(Self::seek),
+                set_finalized_position: Self::set_finalized_position,
+            }
+        }
+    }
+
+    /// Interleaved RGB pixels read through a [`JxlChunkedFrameInputSource`]
+    #[cfg(not(feature = "libjxl_0_9"))]
+    struct Source {
+        pixels: Vec<u8>,
+        width: usize,
+        chunks: usize,
+    }
+
+    #[cfg(not(feature = "libjxl_0_9"))]
+    impl Source {
+        extern "C-unwind" fn pixel_format(_opaque: *mut c_void, pixel_format: *mut JxlPixelFormat) {
+            unsafe { *pixel_format = u8_format(3) };
+        }
+
+        extern "C-unwind" fn data_at(
+            opaque: *mut c_void,
+            xpos: usize,
+            ypos: usize,
+            _xsize: usize,
+            _ysize: usize,
+            row_offset: *mut usize,
+        ) -> *const c_void {
+            let source = unsafe { &mut *opaque.cast::<Self>() };
+            source.chunks += 1;
+            unsafe { *row_offset = source.width * 3 };
+            source.pixels[(ypos * source.width + xpos) * 3..]
+                .as_ptr()
+                .cast()
+        }
+
+        extern "C-unwind" fn extra_pixel_format(
+            _opaque: *mut c_void,
+            _ec_index: usize,
+            _pixel_format: *mut JxlPixelFormat,
+        ) {
+            unreachable!("no extra channels");
+        }
+
+        extern "C-unwind" fn extra_data_at(
+            _opaque: *mut c_void,
+            _ec_index: usize,
+            _xpos: usize,
+            _ypos: usize,
+            _xsize: usize,
+            _ysize: usize,
+            _row_offset: *mut usize,
+        ) -> *const c_void {
+            unreachable!("no extra channels");
+        }
+
+        extern "C-unwind" fn release_buffer(_opaque: *mut c_void, _buf: *const c_void) {}
+
+        fn input_source(&mut self) -> JxlChunkedFrameInputSource {
+            JxlChunkedFrameInputSource {
+                opaque: (self as *mut Self).cast(),
+                get_color_channels_pixel_format: Self::pixel_format,
+                get_color_channels_data_at: Self::data_at,
+                get_extra_channel_pixel_format: Self::extra_pixel_format,
+                get_extra_channel_data_at: Self::extra_data_at,
+                release_buffer: Self::release_buffer,
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "libjxl_0_9"))]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_chunked_frame() {
+        const SIZE: u32 = 64;
+        let mut source = Source {
+            pixels: (0..SIZE * SIZE * 3).map(|i| (i % 251) as u8).collect(),
+            width: SIZE as usize,
+            chunks: 0,
+        };
+        let mut sink = Sink::default();
+
+        unsafe {
+            let (enc, settings) = create_encoder(|info| {
+                (info.xsize, info.ysize) = (SIZE, SIZE);
+                info.uses_original_profile = JxlBool::True;
+            });
+            jxl_enc_assert!(JxlEncoderSetFrameLossless(settings, true), "Set Lossless");
+            let status = JxlEncoderSetOutputProcessor(enc, sink.processor());
+            jxl_enc_assert!(status, "Set Output Processor");
+            let status = JxlEncoderAddChunkedFrame(settings, JxlBool::True, source.input_source());
+            jxl_enc_assert!(status, "Add Chunked Frame");
+            jxl_enc_assert!(JxlEncoderFlushInput(enc), "Flush Input");
+            JxlEncoderDestroy(enc);
+        }
+        sink.data.truncate(sink.end);
+        assert!(source.chunks > 0);
+
+        unsafe {
+            let dec = JxlDecoderCreate(ptr::null());
+            let mut pixels = vec![];
+            let events = jxl_dec_events!(JxlDecoderStatus::FullImage);
+            decode_with(dec, &sink.data, events, |status| match status {
+                JxlDecoderStatus::NeedImageOutBuffer => {
+                    pixels.resize(source.pixels.len(), 0u8);
+                    jxl_dec_assert!(
+                        JxlDecoderSetImageOutBuffer(
+                            dec,
+                            &u8_format(3),
+                            pixels.as_mut_ptr().cast(),
+                            pixels.len()
+                        ),
+                        "Set Image Buffer"
+                    );
+                }
+                JxlDecoderStatus::FullImage | JxlDecoderStatus::Success => {}
+                status => panic!("Unexpected decoder status: {status:#?}"),
+            });
+            JxlDecoderDestroy(dec);
+
+            assert_eq!(pixels, source.pixels);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    fn test_bindings_bit_depth() {
+        use std::mem::{align_of, size_of};
+
+        assert_eq!(
+            (size_of::<JxlBitDepth>(), align_of::<JxlBitDepth>()),
+            (12, 4)
+        );
+        assert_eq!(offset!(JxlBitDepth, bits_per_sample), 4);
+        assert_eq!(offset!(JxlBitDepth, exponent_bits_per_sample), 8);
+
+        // 12-bit samples in u16 buffers, without rescaling to the full u16 range
+        let bit_depth = JxlBitDepth {
+            r#type: JxlBitDepthType::FromCodestream,
+            bits_per_sample: 0,
+            exponent_bits_per_sample: 0,
+        };
+        let format = JxlPixelFormat {
+            num_channels: 3,
+            data_type: JxlDataType::Uint16,
+            endianness: JxlEndianness::Native,
+            align: 0,
+        };
+        let pixels: Vec<u16> = (0..8 * 8 * 3).map(|i| i * 21).collect();
+
+        unsafe {
+            let output = encode_with(
+                |info| {
+                    (info.xsize, info.ysize) = (8, 8);
+                    info.bits_per_sample = 12;
+                    info.uses_original_profile = JxlBool::True;
+                },
+                |_, settings| {
+                    jxl_enc_assert!(JxlEncoderSetFrameLossless(settings, true), "Set Lossless");
+                    jxl_enc_assert!(
+                        JxlEncoderSetFrameBitDepth(settings, &bit_depth),
+                        "Set Frame Bit Depth"
+                    );
+                    jxl_enc_assert!(
+                        JxlEncoderAddImageFrame(
+                            settings,
+                            &format,
+                            pixels.as_ptr().cast(),
+                            pixels.len() * 2
+                        ),
+                        "Add Image Frame"
+                    );
+                },
+            );
+
+            let dec = JxlDecoderCreate(ptr::null());
+            let mut decoded = vec![0u16; pixels.len()];
+            let events = jxl_dec_events!(JxlDecoderStatus::FullImage);
+            decode_with(dec, &output, events, |status| match status {
+                JxlDecoderStatus::NeedImageOutBuffer => {
+                    jxl_dec_assert!(
+                        JxlDecoderSetImageOutBuffer(
+                            dec,
+                            &format,
+                            decoded.as_mut_ptr().cast(),
+                            decoded.len() * 2
+                        ),
+                        "Set Image Buffer"
+                    );
+                    // The bit depth applies to the output buffer, so it is set afterwards
+                    jxl_dec_assert!(
+                        JxlDecoderSetImageOutBitDepth(dec, &bit_depth),
+                        "Set Image Out Bit Depth"
+                    );
+                }
+                JxlDecoderStatus::FullImage | JxlDecoderStatus::Success => {}
+                status => panic!("Unexpected decoder status: {status:#?}"),
+            });
+            JxlDecoderDestroy(dec);
+
+            assert_eq!(decoded, pixels);
+        }
+    }
 }
@@ -19,4 +19,5 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 
 pub mod codestream_header;
 pub mod compressed_icc;
+#[cfg(not(feature = "libjxl_0_10"))]
 pub mod gain_map;
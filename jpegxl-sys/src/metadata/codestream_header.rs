@@ -48,9 +48,21 @@ pub enum JxlOrientation {
     Rotate90Ccw = 8,
 }
 
+impl_try_from_i32!(JxlOrientation {
+    Identity,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Transpose,
+    Rotate90Cw,
+    AntiTranspose,
+    Rotate90Ccw,
+});
+
 /// Given type of an extra channel.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum JxlExtraChannelType {
     Alpha,
     Depth,
@@ -71,6 +83,26 @@ pub enum JxlExtraChannelType {
     Optional,
 }
 
+impl_try_from_i32!(JxlExtraChannelType {
+    Alpha,
+    Depth,
+    SpotColor,
+    SelectionMask,
+    Black,
+    Cfa,
+    Thermal,
+    Reserved0,
+    Reserved1,
+    Reserved2,
+    Reserved3,
+    Reserved4,
+    Reserved5,
+    Reserved6,
+    Reserved7,
+    Unknown,
+    Optional,
+});
+
 /// The codestream preview header
 #[repr(C)]
 #[derive(Debug, Clone)]
@@ -288,6 +320,7 @@ pub struct JxlHeaderExtensions {
 /// When decoding, if coalescing is enabled (default), this can be ignored.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum JxlBlendMode {
     Replace = 0,
     Add = 1,
@@ -296,6 +329,14 @@ pub enum JxlBlendMode {
     MUL = 4,
 }
 
+impl_try_from_i32!(JxlBlendMode {
+    Replace,
+    Add,
+    Blend,
+    MULADD,
+    MUL,
+});
+
 /// The information about blending the color channels or a single extra channel.
 /// When decoding, if coalescing is enabled (default), this can be ignored and
 /// the blend mode is considered to be [`JxlBlendMode::Replace`].
@@ -18,5 +18,7 @@ along with jpegxl-sys.  If not, see <https://www.gnu.org/licenses/>.
 //! `libjxl_threads`: Parallel processing support for JPEG XL.
 
 pub mod parallel_runner;
+#[cfg(feature = "threads")]
 pub mod resizable_parallel_runner;
+#[cfg(feature = "threads")]
 pub mod thread_parallel_runner;